@@ -0,0 +1,305 @@
+use crate::{
+    amount::format_sol,
+    rpc::Rpc,
+    transaction::{self, BlockhashSource, Receipt, SendOptions},
+};
+use serde::Serialize;
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::{
+        self, instruction,
+        state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
+        AddressLookupTableAccount,
+    },
+    clock::Slot,
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    slot_hashes::MAX_ENTRIES,
+    transaction::TransactionError,
+};
+use std::{collections::HashSet, error::Error, fmt};
+
+/// Addresses added per `extend` transaction, which keeps each one well
+/// inside the packet size limit.
+const EXTEND_CHUNK: usize = 20;
+
+/// Decodes an address lookup table account.
+pub fn decode_lookup_table(
+    key: &Pubkey,
+    account: &Account,
+) -> Result<AddressLookupTableAccount, Box<dyn Error>> {
+    if account.owner != address_lookup_table::program::id() {
+        return Err(format!("{} is not an address lookup table", key).into());
+    }
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| format!("Invalid address lookup table {}: {}", key, e))?;
+    Ok(AddressLookupTableAccount {
+        key: *key,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Fetches the lookup tables passed with `--lookup-table`.
+pub async fn fetch_lookup_tables(
+    rpc_client: &dyn Rpc,
+    addresses: &[Pubkey],
+) -> Result<Vec<AddressLookupTableAccount>, Box<dyn Error>> {
+    if addresses.is_empty() {
+        return Ok(Vec::new());
+    }
+    let accounts = rpc_client.get_multiple_accounts(addresses).await?;
+    addresses
+        .iter()
+        .zip(accounts)
+        .map(|(address, account)| {
+            let account = account.ok_or_else(|| format!("Lookup table {} not found", address))?;
+            decode_lookup_table(address, &account)
+        })
+        .collect()
+}
+
+/// Every account key of `message`: the static keys, then the writable and
+/// then the readonly addresses it loads from lookup tables, which is the
+/// order instructions index them in.
+pub fn account_keys(
+    message: &VersionedMessage,
+    tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Pubkey>, TransactionError> {
+    let mut keys = message.static_account_keys().to_vec();
+    let Some(lookups) = message.address_table_lookups() else {
+        return Ok(keys);
+    };
+    let mut writable = Vec::new();
+    let mut readonly = Vec::new();
+    for lookup in lookups {
+        let table = tables
+            .iter()
+            .find(|table| table.key == lookup.account_key)
+            .ok_or(TransactionError::AddressLookupTableNotFound)?;
+        let load = |indexes: &[u8]| {
+            indexes
+                .iter()
+                .map(|&index| table.addresses.get(index as usize).copied())
+                .collect::<Option<Vec<Pubkey>>>()
+                .ok_or(TransactionError::InvalidAddressLookupTableIndex)
+        };
+        writable.extend(load(&lookup.writable_indexes)?);
+        readonly.extend(load(&lookup.readonly_indexes)?);
+    }
+    keys.extend(writable);
+    keys.extend(readonly);
+    Ok(keys)
+}
+
+/// Fetches the lookup tables `message` uses and returns all of its account
+/// keys, as `account_keys` orders them.
+pub async fn resolve_account_keys(
+    rpc_client: &dyn Rpc,
+    message: &VersionedMessage,
+) -> Result<Vec<Pubkey>, Box<dyn Error>> {
+    let addresses: Vec<Pubkey> = message
+        .address_table_lookups()
+        .unwrap_or_default()
+        .iter()
+        .map(|lookup| lookup.account_key)
+        .collect();
+    let tables = fetch_lookup_tables(rpc_client, &addresses).await?;
+    Ok(account_keys(message, &tables)?)
+}
+
+/// Creates a lookup table owned by `authority`. Its address is derived from
+/// the authority and a recent slot.
+pub async fn create_lookup_table(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    authority: &Pubkey,
+    options: &SendOptions,
+) -> Result<(Pubkey, Receipt), Box<dyn Error>> {
+    let recent_slot = rpc_client.get_slot().await?;
+    let (instruction, address) =
+        instruction::create_lookup_table(*authority, payer.pubkey(), recent_slot);
+    let receipt = transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await?;
+    Ok((address, receipt))
+}
+
+/// Appends the addresses the table does not hold yet, in chunks of
+/// `EXTEND_CHUNK` per transaction. Returns the addresses added and the
+/// receipt of each transaction.
+pub async fn extend_lookup_table(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    table: &Pubkey,
+    authority: &Keypair,
+    addresses: &[Pubkey],
+    options: &SendOptions,
+) -> Result<(Vec<Pubkey>, Vec<Receipt>), Box<dyn Error>> {
+    let current = fetch_lookup_tables(rpc_client, &[*table]).await?.remove(0);
+    let mut seen: HashSet<Pubkey> = current.addresses.iter().copied().collect();
+    let new_addresses: Vec<Pubkey> = addresses
+        .iter()
+        .filter(|address| seen.insert(**address))
+        .copied()
+        .collect();
+    if current.addresses.len() + new_addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(format!(
+            "Lookup table {} holds {} addresses; adding {} would exceed the limit of {}",
+            table,
+            current.addresses.len(),
+            new_addresses.len(),
+            LOOKUP_TABLE_MAX_ADDRESSES
+        )
+        .into());
+    }
+    let mut receipts = Vec::new();
+    let chunks = new_addresses.chunks(EXTEND_CHUNK);
+    let count = chunks.len();
+    for (index, chunk) in chunks.enumerate() {
+        let instruction = instruction::extend_lookup_table(
+            *table,
+            authority.pubkey(),
+            Some(payer.pubkey()),
+            chunk.to_vec(),
+        );
+        let receipt = transaction::send_instructions(
+            rpc_client,
+            &[instruction],
+            &[payer, authority],
+            &BlockhashSource::default(),
+            options,
+        )
+        .await?;
+        if count > 1 {
+            eprintln!(
+                "Extended with {} addresses ({}/{}): Signature {}",
+                chunk.len(),
+                index + 1,
+                count,
+                receipt.signature
+            );
+        }
+        receipts.push(receipt);
+    }
+    Ok((new_addresses, receipts))
+}
+
+/// Starts the cooldown after which the table can be closed. A deactivated
+/// table can no longer be extended or used by new transactions.
+pub async fn deactivate_lookup_table(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    table: &Pubkey,
+    authority: &Keypair,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction = instruction::deactivate_lookup_table(*table, authority.pubkey());
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, authority],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Closes a deactivated table, sending its rent to `recipient`.
+pub async fn close_lookup_table(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    table: &Pubkey,
+    authority: &Keypair,
+    recipient: &Pubkey,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction = instruction::close_lookup_table(*table, authority.pubkey(), *recipient);
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, authority],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// State of a lookup table as shown by `alt show`.
+#[derive(Serialize, Debug)]
+pub struct LookupTableInfo {
+    pub address: String,
+    pub lamports: u64,
+    /// `None` once the table is frozen.
+    pub authority: Option<String>,
+    pub status: String,
+    pub deactivation_slot: Option<Slot>,
+    pub last_extended_slot: Slot,
+    pub addresses: Vec<String>,
+}
+
+impl fmt::Display for LookupTableInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Lookup table: {}", self.address)?;
+        writeln!(f, "Balance: {} SOL", format_sol(self.lamports))?;
+        writeln!(
+            f,
+            "Authority: {}",
+            self.authority.as_deref().unwrap_or("none (frozen)")
+        )?;
+        writeln!(f, "Status: {}", self.status)?;
+        writeln!(f, "Last extended slot: {}", self.last_extended_slot)?;
+        write!(f, "Addresses: {}", self.addresses.len())?;
+        for (index, address) in self.addresses.iter().enumerate() {
+            write!(f, "\n  {:>3}  {}", index, address)?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn get_lookup_table_info(
+    rpc_client: &dyn Rpc,
+    table: &Pubkey,
+) -> Result<LookupTableInfo, Box<dyn Error>> {
+    let account = rpc_client
+        .get_account(table)
+        .await?
+        .ok_or_else(|| format!("Lookup table {} not found", table))?;
+    decode_lookup_table(table, &account)?;
+    let state = AddressLookupTable::deserialize(&account.data)?;
+    let meta = &state.meta;
+    let deactivation_slot = (meta.deactivation_slot != Slot::MAX).then_some(meta.deactivation_slot);
+    let status = match deactivation_slot {
+        None => "active".to_string(),
+        Some(slot) => {
+            // The table can be closed once its deactivation slot has left
+            // the slot hashes sysvar.
+            let closable_at = slot + MAX_ENTRIES as Slot;
+            let current = rpc_client.get_slot().await?;
+            if current > closable_at {
+                format!("deactivated at slot {}, can be closed", slot)
+            } else {
+                format!(
+                    "deactivating since slot {}, can be closed in about {} slots",
+                    slot,
+                    closable_at - current + 1
+                )
+            }
+        }
+    };
+    Ok(LookupTableInfo {
+        address: table.to_string(),
+        lamports: account.lamports,
+        authority: meta.authority.map(|authority| authority.to_string()),
+        status,
+        deactivation_slot,
+        last_extended_slot: meta.last_extended_slot,
+        addresses: state.addresses.iter().map(|a| a.to_string()).collect(),
+    })
+}
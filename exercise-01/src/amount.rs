@@ -0,0 +1,105 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// Decimal places of SOL.
+pub const SOL_DECIMALS: u8 = 9;
+
+/// An amount as typed on the command line. The digits are kept as text so
+/// they can be scaled to lamports or token base units without going through
+/// floating point.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Amount {
+    /// Everything available, e.g. the balance minus fees.
+    All,
+    Decimal {
+        whole: String,
+        fraction: String,
+    },
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("all") {
+            return Ok(Amount::All);
+        }
+        let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+            return Err(format!(
+                "Invalid amount '{}': expected a decimal number or ALL",
+                value
+            ));
+        }
+        Ok(Amount::Decimal {
+            whole: whole.to_string(),
+            fraction: fraction.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Amount::All => write!(f, "ALL"),
+            Amount::Decimal { whole, fraction } if fraction.is_empty() => write!(f, "{}", whole),
+            Amount::Decimal { whole, fraction } => write!(f, "{}.{}", whole, fraction),
+        }
+    }
+}
+
+impl Amount {
+    pub fn is_all(&self) -> bool {
+        matches!(self, Amount::All)
+    }
+
+    /// Scales the amount to base units of a currency with `decimals` places.
+    /// Inputs with more precision than the currency has are rejected.
+    pub fn to_units(&self, decimals: u8) -> Result<u64, Box<dyn Error>> {
+        let (whole, fraction) = match self {
+            Amount::All => return Err("ALL is not supported for this command".into()),
+            Amount::Decimal { whole, fraction } => (whole, fraction.trim_end_matches('0')),
+        };
+        let decimals = decimals as usize;
+        if fraction.len() > decimals {
+            return Err(
+                format!("Amount {} has more than {} decimal places", self, decimals).into(),
+            );
+        }
+        let digits = format!("{}{:0<decimals$}", whole, fraction);
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Ok(0);
+        }
+        digits
+            .parse()
+            .map_err(|_| format!("Amount {} is too large", self).into())
+    }
+
+    /// Converts to lamports, or takes the amount as lamports already when
+    /// `in_lamports` is set.
+    pub fn to_lamports(&self, in_lamports: bool) -> Result<u64, Box<dyn Error>> {
+        if in_lamports {
+            self.to_units(0)
+        } else {
+            self.to_units(SOL_DECIMALS)
+        }
+    }
+}
+
+/// Formats base units as an exact decimal with trailing zeros removed.
+pub fn format_units(units: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", units, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+pub fn format_sol(lamports: u64) -> String {
+    format_units(lamports, SOL_DECIMALS)
+}
@@ -0,0 +1,568 @@
+use crate::{
+    alt,
+    amount::{format_sol, Amount},
+    preview,
+    rpc::Rpc,
+    simulate::{self, ProgramErrors, Simulation},
+    transaction::{self, BlockhashSource, ComputeBudget, Confirmation, SendOptions},
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction,
+};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    str::FromStr,
+};
+
+/// One row of the payouts CSV. Rows are numbered from 1, not counting the
+/// header.
+#[derive(Debug)]
+pub struct Payout {
+    pub row: usize,
+    pub recipient: Pubkey,
+    pub lamports: u64,
+}
+
+/// Reads `recipient,amount` rows. Recipients are looked up with `resolve`,
+/// so they can be contacts or wallet names as well as addresses. All rows
+/// are validated before anything is sent.
+pub fn read_payouts(
+    path: &str,
+    in_lamports: bool,
+    resolve: impl Fn(&str) -> Result<Pubkey, Box<dyn Error>>,
+) -> Result<Vec<Payout>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or(format!("'{}' has no '{}' column", path, name))
+    };
+    let (recipient_column, amount_column) = (column("recipient")?, column("amount")?);
+
+    let mut payouts = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let row = index + 1;
+        let recipient = record.get(recipient_column).unwrap_or_default();
+        let recipient = resolve(recipient)
+            .map_err(|e| format!("Row {}: invalid recipient '{}': {}", row, recipient, e))?;
+        let amount = Amount::from_str(record.get(amount_column).unwrap_or_default())
+            .map_err(|e| format!("Row {}: {}", row, e))?;
+        let lamports = amount
+            .to_lamports(in_lamports)
+            .map_err(|e| format!("Row {}: {}", row, e))?;
+        if lamports == 0 {
+            return Err(format!("Row {}: amount must be greater than zero", row).into());
+        }
+        payouts.push(Payout {
+            row,
+            recipient,
+            lamports,
+        });
+    }
+    Ok(payouts)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    /// Signed and about to be sent; the outcome is not known yet.
+    Sent,
+    Confirmed,
+    /// Executed on chain with an error, or rejected by the RPC node.
+    Failed,
+    /// The blockhash expired without the transaction landing.
+    Expired,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Sent => "sent",
+            Status::Confirmed => "confirmed",
+            Status::Failed => "failed",
+            Status::Expired => "expired",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct JournalRow {
+    row: usize,
+    recipient: String,
+    lamports: u64,
+}
+
+/// One line of the journal. A batch is journaled as `sent` before it is
+/// submitted and again once its outcome is known.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct JournalEntry {
+    rows: Vec<JournalRow>,
+    signature: String,
+    last_valid_block_height: u64,
+    status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Append-only record of every batch sent, used to resume a run without
+/// paying a row twice.
+struct Journal {
+    file: File,
+    /// Latest entry for each row.
+    rows: BTreeMap<usize, JournalEntry>,
+}
+
+impl Journal {
+    fn open(path: &str, payouts: &[Payout]) -> Result<Self, Box<dyn Error>> {
+        // A missing journal has no entries.
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        let (rows, complete) = Self::read_rows(path, &contents, payouts)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                eprintln!("Failed to open journal '{}': {}", path, e);
+                e
+            })?;
+        // Drop a half-written last line and terminate the last entry, so the
+        // next entry starts on a line of its own.
+        if complete < contents.len() {
+            file.set_len(complete as u64)?;
+        }
+        if complete > 0 && !contents[..complete].ends_with('\n') {
+            writeln!(file)?;
+        }
+        Ok(Journal { file, rows })
+    }
+
+    /// Reads the latest entry for each row, checking that the journal
+    /// belongs to `payouts`. Also returns the length of `contents` up to the
+    /// end of the last entry that was written completely.
+    fn read_rows(
+        path: &str,
+        contents: &str,
+        payouts: &[Payout],
+    ) -> Result<(BTreeMap<usize, JournalEntry>, usize), Box<dyn Error>> {
+        let mut rows = BTreeMap::new();
+        let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+        let mut complete = 0;
+        for (index, line) in lines.iter().enumerate() {
+            let entry: JournalEntry = match serde_json::from_str(line.trim_end()) {
+                Ok(entry) => entry,
+                // A crash can leave the last line half written.
+                Err(_) if index + 1 == lines.len() => break,
+                Err(e) => return Err(format!("Corrupt journal '{}': {}", path, e).into()),
+            };
+            complete += line.len();
+            for journal_row in &entry.rows {
+                let payout = journal_row.row.checked_sub(1).and_then(|i| payouts.get(i));
+                let matches = payout.is_some_and(|payout| {
+                    payout.recipient.to_string() == journal_row.recipient
+                        && payout.lamports == journal_row.lamports
+                });
+                if !matches {
+                    return Err(format!(
+                        "Journal '{}' does not match the CSV at row {}",
+                        path, journal_row.row
+                    )
+                    .into());
+                }
+                rows.insert(journal_row.row, entry.clone());
+            }
+        }
+        Ok((rows, complete))
+    }
+
+    fn record(&mut self, entry: &JournalEntry) -> Result<(), Box<dyn Error>> {
+        writeln!(self.file, "{}", serde_json::to_string(entry)?)?;
+        self.file.sync_data()?;
+        for journal_row in &entry.rows {
+            self.rows.insert(journal_row.row, entry.clone());
+        }
+        Ok(())
+    }
+
+    fn status(&self, row: usize) -> Option<Status> {
+        self.rows.get(&row).map(|entry| entry.status)
+    }
+
+    /// Batches whose outcome was not recorded before the previous run stopped.
+    fn unsettled(&self) -> Vec<JournalEntry> {
+        let by_signature: BTreeMap<&str, &JournalEntry> = self
+            .rows
+            .values()
+            .filter(|entry| entry.status == Status::Sent)
+            .map(|entry| (entry.signature.as_str(), entry))
+            .collect();
+        by_signature.into_values().cloned().collect()
+    }
+}
+
+/// Waits until a sent transaction is confirmed, fails, or can no longer land
+/// because its blockhash expired.
+async fn wait_for_outcome(
+    rpc_client: &dyn Rpc,
+    entry: &JournalEntry,
+) -> Result<(Status, Option<String>), Box<dyn Error>> {
+    let signature = Signature::from_str(&entry.signature)?;
+    let confirmation = transaction::wait_for_confirmation(
+        rpc_client,
+        &signature,
+        Some(entry.last_valid_block_height),
+        None,
+    )
+    .await?;
+    Ok(match confirmation {
+        Confirmation::Confirmed(_) => (Status::Confirmed, None),
+        Confirmation::Failed(err) => (Status::Failed, Some(err.to_string())),
+        Confirmation::Expired => (Status::Expired, None),
+    })
+}
+
+fn transfer_instructions(sender: &Pubkey, batch: &[&Payout]) -> Vec<Instruction> {
+    batch
+        .iter()
+        .map(|payout| system_instruction::transfer(sender, &payout.recipient, payout.lamports))
+        .collect()
+}
+
+/// Size of the batch's transaction once signed, including compute budget
+/// instructions. Recipients found in the lookup tables take one byte each
+/// instead of 32.
+fn transaction_size(
+    sender: &Pubkey,
+    batch: &[&Payout],
+    budget: &ComputeBudget,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> usize {
+    let mut instructions = Vec::new();
+    if budget.unit_limit.is_some() {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(0));
+    }
+    if budget.unit_price.is_some() {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(0));
+    }
+    instructions.extend(transfer_instructions(sender, batch));
+    match transaction::compile_message(&instructions, sender, &Hash::default(), lookup_tables) {
+        Ok(message) => bincode::serialized_size(&transaction::unsigned_transaction(message))
+            .unwrap_or(u64::MAX) as usize,
+        Err(_) => usize::MAX,
+    }
+}
+
+/// Packs payouts into as few transactions as fit in a packet.
+fn pack<'a>(
+    sender: &Pubkey,
+    payouts: Vec<&'a Payout>,
+    budget: &ComputeBudget,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Vec<Vec<&'a Payout>> {
+    let mut batches = Vec::new();
+    let mut current: Vec<&Payout> = Vec::new();
+    for payout in payouts {
+        current.push(payout);
+        if current.len() > 1
+            && transaction_size(sender, &current, budget, lookup_tables) > PACKET_DATA_SIZE
+        {
+            current.pop();
+            batches.push(std::mem::replace(&mut current, vec![payout]));
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// Signs, journals and sends one batch, returning its outcome.
+async fn send_batch(
+    rpc_client: &dyn Rpc,
+    journal: &mut Journal,
+    sender: &Keypair,
+    batch: &[&Payout],
+    budget: &ComputeBudget,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<JournalEntry, Box<dyn Error>> {
+    let (blockhash, last_valid_block_height) = rpc_client.get_latest_blockhash().await?;
+    let source = BlockhashSource {
+        blockhash: Some(blockhash),
+        nonce: None,
+    };
+    let mut transaction = transaction::build_transaction(
+        rpc_client,
+        &transfer_instructions(&sender.pubkey(), batch),
+        &sender.pubkey(),
+        &source,
+        budget,
+        lookup_tables,
+    )
+    .await?;
+    transaction::sign_transaction(&mut transaction, &[sender])?;
+
+    let mut entry = JournalEntry {
+        rows: batch
+            .iter()
+            .map(|payout| JournalRow {
+                row: payout.row,
+                recipient: payout.recipient.to_string(),
+                lamports: payout.lamports,
+            })
+            .collect(),
+        signature: transaction.signatures[0].to_string(),
+        last_valid_block_height,
+        status: Status::Sent,
+        error: None,
+    };
+    // The signature is journaled before sending so a crash mid-send can be
+    // settled on the next run instead of paying again.
+    journal.record(&entry)?;
+    let (status, error) = match rpc_client.send_transaction(&transaction).await {
+        // Rejected in preflight, so it was never forwarded to the leader.
+        Err(e) if e.get_transaction_error().is_some() => (Status::Failed, Some(e.to_string())),
+        // Other send errors leave it unknown whether the transaction went
+        // out, so wait for it either way.
+        _ => wait_for_outcome(rpc_client, &entry).await?,
+    };
+    entry.status = status;
+    entry.error = error;
+    journal.record(&entry)?;
+    Ok(entry)
+}
+
+/// Simulates the batches `run_batch` would send, without sending or
+/// journaling anything. Rows the journal shows as confirmed are skipped.
+pub async fn simulate_batches(
+    rpc_client: &dyn Rpc,
+    sender: &Keypair,
+    payouts: &[Payout],
+    journal_path: &str,
+    options: &SendOptions,
+) -> Result<Vec<Simulation>, Box<dyn Error>> {
+    let budget = &options.budget;
+    let lookup_tables = alt::fetch_lookup_tables(rpc_client, &options.lookup_tables).await?;
+    let contents = fs::read_to_string(journal_path).unwrap_or_default();
+    let (journal, _) = Journal::read_rows(journal_path, &contents, payouts)?;
+    let remaining: Vec<&Payout> = payouts
+        .iter()
+        .filter(|payout| {
+            journal.get(&payout.row).map(|entry| entry.status) != Some(Status::Confirmed)
+        })
+        .collect();
+    let batches = pack(&sender.pubkey(), remaining, budget, &lookup_tables);
+    let mut simulations = Vec::new();
+    for (index, batch) in batches.iter().enumerate() {
+        let mut transaction = transaction::build_transaction(
+            rpc_client,
+            &transfer_instructions(&sender.pubkey(), batch),
+            &sender.pubkey(),
+            &BlockhashSource::default(),
+            budget,
+            &lookup_tables,
+        )
+        .await?;
+        transaction::sign_transaction(&mut transaction, &[sender])?;
+        let simulation = simulate::simulate(rpc_client, &transaction).await?;
+        let outcome = match &simulation.error {
+            Some(error) => format!(
+                "failed: {}",
+                simulate::describe_error(error, &simulation.program_ids, &ProgramErrors::default())
+            ),
+            None => "ok".to_string(),
+        };
+        eprintln!(
+            "Batch {}/{} ({} transfers, {} SOL, fee {} SOL): {}",
+            index + 1,
+            batches.len(),
+            batch.len(),
+            format_sol(batch.iter().map(|payout| payout.lamports).sum()),
+            format_sol(simulation.fee_lamports),
+            outcome
+        );
+        simulations.push(simulation);
+    }
+    Ok(simulations)
+}
+
+/// Summarizes the fees of the batches about to be sent, and warns about
+/// recipients or a sender the payouts would leave below the rent-exempt
+/// minimum.
+async fn preview_batches(
+    rpc_client: &dyn Rpc,
+    sender: &Keypair,
+    batches: &[Vec<&Payout>],
+    budget: &ComputeBudget,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<String, Box<dyn Error>> {
+    let mut fee = 0;
+    let mut totals: BTreeMap<Pubkey, u64> = BTreeMap::new();
+    for batch in batches {
+        let transaction = transaction::build_transaction(
+            rpc_client,
+            &transfer_instructions(&sender.pubkey(), batch),
+            &sender.pubkey(),
+            &BlockhashSource::default(),
+            budget,
+            lookup_tables,
+        )
+        .await?;
+        fee += rpc_client.get_fee_for_message(&transaction.message).await?;
+        for payout in batch {
+            *totals.entry(payout.recipient).or_default() += payout.lamports;
+        }
+    }
+    let total: u64 = totals.values().sum();
+    let before = rpc_client.get_balance(&sender.pubkey()).await?;
+    let after = before.saturating_sub(total + fee);
+    let mut summary = format!(
+        "Total fee: {} SOL\nSender {}: {} -> {} SOL",
+        format_sol(fee),
+        sender.pubkey(),
+        format_sol(before),
+        format_sol(after)
+    );
+    let mut warnings = Vec::new();
+    if before < total + fee {
+        warnings.push(format!(
+            "Sender balance does not cover the payouts and fees ({} SOL)",
+            format_sol(total + fee)
+        ));
+    } else {
+        warnings
+            .extend(preview::rent_warning(rpc_client, "Sender", &sender.pubkey(), after, 0).await?);
+    }
+    let recipients: Vec<Pubkey> = totals.keys().copied().collect();
+    for chunk in recipients.chunks(100) {
+        let accounts = rpc_client.get_multiple_accounts(chunk).await?;
+        for (recipient, account) in chunk.iter().zip(accounts) {
+            let (lamports, data_len) =
+                account.map_or((0, 0), |account| (account.lamports, account.data.len()));
+            let after = lamports + totals[recipient];
+            warnings.extend(
+                preview::rent_warning(rpc_client, "Recipient", recipient, after, data_len).await?,
+            );
+        }
+    }
+    for warning in warnings {
+        summary.push_str(&format!("\nWarning: {}", warning));
+    }
+    Ok(summary)
+}
+
+/// Pays every row that the journal does not already show as confirmed.
+/// Batches whose blockhash expired are resent up to `retries` times. The
+/// fees are previewed first and must be confirmed unless `--yes` is given.
+pub async fn run_batch(
+    rpc_client: &dyn Rpc,
+    sender: &Keypair,
+    payouts: &[Payout],
+    journal_path: &str,
+    options: &SendOptions,
+    retries: u32,
+) -> Result<(), Box<dyn Error>> {
+    let budget = &options.budget;
+    let lookup_tables = alt::fetch_lookup_tables(rpc_client, &options.lookup_tables).await?;
+    let mut journal = Journal::open(journal_path, payouts)?;
+    for mut entry in journal.unsettled() {
+        eprintln!("Checking batch from previous run: {}", entry.signature);
+        (entry.status, entry.error) = wait_for_outcome(rpc_client, &entry).await?;
+        journal.record(&entry)?;
+    }
+
+    let remaining: Vec<&Payout> = payouts
+        .iter()
+        .filter(|payout| journal.status(payout.row) != Some(Status::Confirmed))
+        .collect();
+    if remaining.len() < payouts.len() {
+        eprintln!(
+            "Skipping {} rows already paid according to the journal",
+            payouts.len() - remaining.len()
+        );
+    }
+    let total: u64 = remaining.iter().map(|payout| payout.lamports).sum();
+    let batches = pack(&sender.pubkey(), remaining, budget, &lookup_tables);
+    eprintln!(
+        "Sending {} SOL to {} recipients in {} transactions",
+        format_sol(total),
+        batches.iter().map(Vec::len).sum::<usize>(),
+        batches.len()
+    );
+    if !batches.is_empty() {
+        let summary = preview_batches(rpc_client, sender, &batches, budget, &lookup_tables).await?;
+        preview::confirm(&summary, options.yes)?;
+    }
+
+    for (index, batch) in batches.iter().enumerate() {
+        let mut attempt = 0;
+        loop {
+            let entry = send_batch(
+                rpc_client,
+                &mut journal,
+                sender,
+                batch,
+                budget,
+                &lookup_tables,
+            )
+            .await?;
+            eprintln!(
+                "Batch {}/{} ({} transfers): {} {}",
+                index + 1,
+                batches.len(),
+                batch.len(),
+                entry.status.as_str(),
+                entry.signature
+            );
+            if entry.status != Status::Expired || attempt == retries {
+                break;
+            }
+            attempt += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Writes one line per payout with its latest journaled outcome.
+pub fn write_results(
+    path: &str,
+    payouts: &[Payout],
+    journal_path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let journal = Journal::open(journal_path, payouts)?;
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["row", "recipient", "amount", "status", "signature", "error"])?;
+    for payout in payouts {
+        let entry = journal.rows.get(&payout.row);
+        writer.write_record([
+            payout.row.to_string(),
+            payout.recipient.to_string(),
+            format_sol(payout.lamports),
+            entry
+                .map_or("unsent", |entry| entry.status.as_str())
+                .to_string(),
+            entry.map_or(String::new(), |entry| entry.signature.clone()),
+            entry
+                .and_then(|entry| entry.error.clone())
+                .unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
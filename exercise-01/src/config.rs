@@ -0,0 +1,260 @@
+use crate::registry::config_dir;
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use std::{
+    collections::BTreeMap, env, error::Error, fs, io, path::Path, path::PathBuf, time::Duration,
+};
+use url::Url;
+
+const DEFAULT_CONFIG: &str = r#"{
+    "active_profile": "default",
+    "profiles": {
+        "default": {
+            "rpc_url": "http://127.0.0.1:8899"
+        }
+    }
+}"#;
+
+/// Config written by older versions into the working directory.
+const LEGACY_CONFIG_FILE: &str = "./config.json";
+
+/// Environment overrides, applied after the profile and before command-line flags.
+pub const PROFILE_ENV: &str = "SCW_PROFILE";
+pub const RPC_URL_ENV: &str = "SCW_RPC_URL";
+pub const WS_URL_ENV: &str = "SCW_WS_URL";
+pub const COMMITMENT_ENV: &str = "SCW_COMMITMENT";
+pub const TIMEOUT_ENV: &str = "SCW_TIMEOUT";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Parser, ValueEnum, Clone, Debug)]
+pub enum Network {
+    Localnet,
+    Devnet,
+    Testnet,
+    MainnetBeta,
+}
+
+impl From<Network> for String {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Localnet => "http://127.0.0.1:8899".to_string(),
+            Network::Devnet => "https://api.devnet.solana.com".to_string(),
+            Network::Testnet => "https://api.testnet.solana.com".to_string(),
+            Network::MainnetBeta => "https://api.mainnet-beta.solana.com".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl From<Commitment> for CommitmentConfig {
+    fn from(commitment: Commitment) -> Self {
+        let commitment = match commitment {
+            Commitment::Processed => CommitmentLevel::Processed,
+            Commitment::Confirmed => CommitmentLevel::Confirmed,
+            Commitment::Finalized => CommitmentLevel::Finalized,
+        };
+        CommitmentConfig { commitment }
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Profile {
+    pub rpc_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ws_url: Option<String>,
+    #[serde(default)]
+    pub commitment: Commitment,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_wallet: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            rpc_url: Network::Localnet.into(),
+            ws_url: None,
+            commitment: Commitment::default(),
+            default_wallet: None,
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LegacyConfig {
+    network: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AppConfig {
+    pub active_profile: String,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Cluster settings after applying profile, environment and flag overrides.
+#[derive(Debug, Clone)]
+pub struct ClusterSettings {
+    pub profile: String,
+    pub rpc_url: String,
+    pub ws_url: String,
+    pub commitment: CommitmentConfig,
+    pub timeout: Duration,
+    pub default_wallet: Option<String>,
+}
+
+/// Per-invocation overrides taken from global command-line flags.
+#[derive(Default)]
+pub struct Overrides<'a> {
+    pub profile: Option<&'a str>,
+    pub url: Option<&'a str>,
+    pub ws_url: Option<&'a str>,
+    pub commitment: Option<Commitment>,
+}
+
+/// Accepts either a full URL or a network moniker such as `devnet`.
+pub fn normalize_url(url: &str) -> String {
+    match Network::from_str(url, true) {
+        Ok(network) => network.into(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Derives the websocket endpoint the same way the Solana CLI does: switch
+/// the scheme and, when a port is given, use the next port up.
+pub fn websocket_url(rpc_url: &str) -> Result<String, Box<dyn Error>> {
+    let mut url = Url::parse(rpc_url)?;
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    url.set_scheme(scheme)
+        .map_err(|_| format!("Invalid RPC URL: {}", rpc_url))?;
+    if let Some(port) = url.port() {
+        let port = port.checked_add(1).ok_or_else(|| {
+            format!(
+                "RPC URL {} uses the highest port, so there is no next port for the websocket; set --ws-url",
+                rpc_url
+            )
+        })?;
+        url.set_port(Some(port))
+            .map_err(|_| format!("Invalid RPC URL: {}", rpc_url))?;
+    }
+    Ok(url.to_string())
+}
+
+impl AppConfig {
+    pub fn config_path() -> PathBuf {
+        config_dir().join("config.json")
+    }
+
+    /// Reads the config, creating it from the legacy file or the defaults
+    /// when it does not exist yet.
+    pub fn load_config(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = match fs::read_to_string(file_path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let default_config = match Self::migrate_legacy() {
+                    Some(config) => config,
+                    None => serde_json::from_str(DEFAULT_CONFIG)?,
+                };
+                default_config.save_config(file_path)?;
+                return Ok(default_config);
+            }
+            Err(e) => return Err(format!("Failed to read {}: {}", file_path.display(), e).into()),
+        };
+        serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid config file {}: {}", file_path.display(), e).into())
+    }
+
+    pub fn save_config(&self, file_path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let data = serde_json::to_string_pretty(&self)?;
+        fs::write(file_path, data)
+            .map_err(|e| format!("Failed to write {}: {}", file_path.display(), e))?;
+        Ok(())
+    }
+
+    /// Turns a `{ "network": ... }` config from the working directory into
+    /// the default profile.
+    fn migrate_legacy() -> Option<Self> {
+        let contents = fs::read_to_string(LEGACY_CONFIG_FILE).ok()?;
+        let legacy: LegacyConfig = serde_json::from_str(&contents).ok()?;
+        let profile = Profile {
+            rpc_url: legacy.network,
+            ..Profile::default()
+        };
+        Some(AppConfig {
+            active_profile: "default".to_string(),
+            profiles: BTreeMap::from([("default".to_string(), profile)]),
+        })
+    }
+
+    /// Name of the profile selected by `--profile`, `SCW_PROFILE` or the config.
+    pub fn selected_profile(&self, profile: Option<&str>) -> String {
+        profile
+            .map(str::to_string)
+            .or_else(|| env::var(PROFILE_ENV).ok())
+            .unwrap_or_else(|| self.active_profile.clone())
+    }
+
+    pub fn resolve(&self, overrides: &Overrides) -> Result<ClusterSettings, Box<dyn Error>> {
+        let name = self.selected_profile(overrides.profile);
+        let profile = self
+            .profiles
+            .get(&name)
+            .ok_or_else(|| format!("Unknown profile: {}", name))?;
+
+        let rpc_url = match overrides.url {
+            Some(url) => normalize_url(url),
+            None => env::var(RPC_URL_ENV)
+                .map(|url| normalize_url(&url))
+                .unwrap_or_else(|_| profile.rpc_url.clone()),
+        };
+        // A profile websocket URL only applies to the profile's own RPC URL.
+        let ws_url = match overrides.ws_url {
+            Some(url) => url.to_string(),
+            None => match (env::var(WS_URL_ENV), &profile.ws_url) {
+                (Ok(url), _) => url,
+                (Err(_), Some(url)) if rpc_url == profile.rpc_url => url.clone(),
+                _ => websocket_url(&rpc_url)?,
+            },
+        };
+        let commitment = match (overrides.commitment, env::var(COMMITMENT_ENV)) {
+            (Some(commitment), _) => commitment,
+            (None, Ok(value)) => Commitment::from_str(&value, true)
+                .map_err(|_| format!("Invalid {}: {}", COMMITMENT_ENV, value))?,
+            (None, Err(_)) => profile.commitment,
+        };
+        let timeout_secs = match env::var(TIMEOUT_ENV) {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", TIMEOUT_ENV, value))?,
+            Err(_) => profile.timeout_secs,
+        };
+
+        Ok(ClusterSettings {
+            profile: name,
+            rpc_url,
+            ws_url,
+            commitment: commitment.into(),
+            timeout: Duration::from_secs(timeout_secs),
+            default_wallet: profile.default_wallet.clone(),
+        })
+    }
+}
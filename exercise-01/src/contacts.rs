@@ -0,0 +1,147 @@
+use crate::registry::{config_dir, is_wallet_name};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fs,
+    path::PathBuf,
+    str::FromStr,
+};
+
+const CONTACTS_FILE: &str = "contacts.json";
+
+/// Prefix that marks a contact name in an address argument, as in `@alice`.
+pub const ALIAS_PREFIX: char = '@';
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Contact {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub created_at: String,
+}
+
+/// Named addresses stored in `<config dir>/contacts.json`, along with every
+/// address this machine has sent to.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AddressBook {
+    #[serde(default)]
+    pub contacts: BTreeMap<String, Contact>,
+    #[serde(default)]
+    sent_to: BTreeSet<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl AddressBook {
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = config_dir().join(CONTACTS_FILE);
+        let mut book = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Invalid address book {}: {}", path.display(), e))?,
+            Err(_) => AddressBook::default(),
+        };
+        book.path = path;
+        Ok(book)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let data = serde_json::to_string_pretty(&self)?;
+        fs::write(&self.path, data).map_err(|e| {
+            eprintln!("Failed to write address book: {}", e);
+            e.into()
+        })
+    }
+
+    /// Adds a contact. The name may be given with or without the `@`.
+    pub fn add(
+        &mut self,
+        name: &str,
+        address: &Pubkey,
+        label: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        let name = name.strip_prefix(ALIAS_PREFIX).unwrap_or(name);
+        if !is_wallet_name(name) {
+            return Err(format!(
+                "Invalid contact name '{}': use letters, digits, '-' and '_'",
+                name
+            )
+            .into());
+        }
+        if self.contacts.contains_key(name) {
+            return Err(format!("Contact '@{}' already exists", name).into());
+        }
+        self.contacts.insert(
+            name.to_string(),
+            Contact {
+                address: address.to_string(),
+                label: label.map(str::to_string),
+                created_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            },
+        );
+        Ok(name.to_string())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<Contact, Box<dyn Error>> {
+        let name = name.strip_prefix(ALIAS_PREFIX).unwrap_or(name);
+        self.contacts
+            .remove(name)
+            .ok_or_else(|| format!("Unknown contact: @{}", name).into())
+    }
+
+    /// Parses a base58 address or an `@alias` from the address book.
+    pub fn pubkey(&self, value: &str) -> Result<Pubkey, Box<dyn Error>> {
+        let Some(name) = value.strip_prefix(ALIAS_PREFIX) else {
+            return Ok(Pubkey::from_str(value)?);
+        };
+        let contact = self
+            .contacts
+            .get(name)
+            .ok_or_else(|| format!("Unknown contact: @{}", name))?;
+        Ok(Pubkey::from_str(&contact.address)?)
+    }
+
+    /// Whether `address` is a contact or has been sent to before.
+    pub fn is_known(&self, address: &Pubkey) -> bool {
+        let address = address.to_string();
+        self.sent_to.contains(&address)
+            || self
+                .contacts
+                .values()
+                .any(|contact| contact.address == address)
+    }
+
+    /// Remembers a recipient so later transfers to it are not flagged.
+    pub fn record_recipient(&mut self, address: &Pubkey) -> Result<(), Box<dyn Error>> {
+        if self.sent_to.insert(address.to_string()) {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+pub fn format_contacts(book: &AddressBook) -> String {
+    let name_width = book
+        .contacts
+        .keys()
+        .map(|name| name.len() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let mut table = format!("{:<name_width$}  {:<44}  LABEL", "NAME", "ADDRESS");
+    for (name, contact) in &book.contacts {
+        let row = format!(
+            "\n{:<name_width$}  {:<44}  {}",
+            format!("{}{}", ALIAS_PREFIX, name),
+            contact.address,
+            contact.label.as_deref().unwrap_or("")
+        );
+        table.push_str(row.trim_end());
+    }
+    table
+}
@@ -0,0 +1,206 @@
+use crate::mnemonic::{self, DerivationInfo, WordCount, DEFAULT_DERIVATION_PATH};
+use bip39::Mnemonic;
+use solana_sdk::{signature::Keypair, signer::Signer};
+use std::{
+    error::Error,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Attempts a worker makes between updates of the shared counter.
+const REPORT_EVERY: u64 = 1000;
+
+/// Address prefix and suffix to search for.
+pub struct Pattern {
+    prefix: String,
+    suffix: String,
+    ignore_case: bool,
+}
+
+impl Pattern {
+    /// Rejects characters that can never appear in a base58 address, such as
+    /// `0`, `O`, `I` and `l`. With `ignore_case`, a letter is accepted when
+    /// either of its cases is valid.
+    pub fn new(prefix: &str, suffix: &str, ignore_case: bool) -> Result<Self, Box<dyn Error>> {
+        if prefix.is_empty() && suffix.is_empty() {
+            return Err("Give --starts-with, --ends-with or both".into());
+        }
+        for c in prefix.chars().chain(suffix.chars()) {
+            let valid = if ignore_case {
+                BASE58_ALPHABET.contains(c.to_ascii_lowercase())
+                    || BASE58_ALPHABET.contains(c.to_ascii_uppercase())
+            } else {
+                BASE58_ALPHABET.contains(c)
+            };
+            if !valid {
+                return Err(format!("'{}' is not a base58 character", c).into());
+            }
+        }
+        let normalize = |value: &str| {
+            if ignore_case {
+                value.to_lowercase()
+            } else {
+                value.to_string()
+            }
+        };
+        Ok(Pattern {
+            prefix: normalize(prefix),
+            suffix: normalize(suffix),
+            ignore_case,
+        })
+    }
+
+    fn matches(&self, address: &str) -> bool {
+        if self.ignore_case {
+            let address = address.to_lowercase();
+            address.starts_with(&self.prefix) && address.ends_with(&self.suffix)
+        } else {
+            address.starts_with(&self.prefix) && address.ends_with(&self.suffix)
+        }
+    }
+
+    /// Average number of keys to try before one matches.
+    pub fn expected_attempts(&self) -> f64 {
+        self.prefix
+            .chars()
+            .chain(self.suffix.chars())
+            .map(|c| {
+                let cases = if self.ignore_case {
+                    let mut variants = vec![c.to_ascii_lowercase(), c.to_ascii_uppercase()];
+                    variants.dedup();
+                    variants
+                        .into_iter()
+                        .filter(|c| BASE58_ALPHABET.contains(*c))
+                        .count()
+                } else {
+                    1
+                };
+                58.0 / cases as f64
+            })
+            .product()
+    }
+}
+
+pub struct GrindResult {
+    pub keypair: Keypair,
+    pub mnemonic: Option<Mnemonic>,
+    pub derivation: Option<DerivationInfo>,
+}
+
+fn generate(word_count: Option<WordCount>) -> Option<GrindResult> {
+    match word_count {
+        Some(word_count) => {
+            let phrase = mnemonic::generate_mnemonic(word_count);
+            let (keypair, info) =
+                mnemonic::derive_keypair(&phrase, "", DEFAULT_DERIVATION_PATH).ok()?;
+            Some(GrindResult {
+                keypair,
+                mnemonic: Some(phrase),
+                derivation: Some(info),
+            })
+        }
+        None => Some(GrindResult {
+            keypair: Keypair::new(),
+            mnemonic: None,
+            derivation: None,
+        }),
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    match seconds {
+        s if !s.is_finite() => "unknown".to_string(),
+        s if s < 60.0 => format!("{:.0}s", s),
+        s if s < 3600.0 => format!("{:.0}m", s / 60.0),
+        s if s < 86400.0 => format!("{:.1}h", s / 3600.0),
+        s => format!("{:.1}d", s / 86400.0),
+    }
+}
+
+/// Generates keys on `threads` threads until one matches `pattern`, printing
+/// the rate and expected remaining time. With `word_count`, each key is
+/// derived from a fresh seed phrase, which is much slower.
+pub fn grind(
+    pattern: Pattern,
+    threads: usize,
+    word_count: Option<WordCount>,
+) -> Result<GrindResult, Box<dyn Error>> {
+    let expected = pattern.expected_attempts();
+    eprintln!(
+        "Searching on {} threads, expecting about {:.0} attempts",
+        threads, expected
+    );
+
+    let pattern = Arc::new(pattern);
+    let found = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicU64::new(0));
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let (pattern, found, attempts, sender) = (
+                pattern.clone(),
+                found.clone(),
+                attempts.clone(),
+                sender.clone(),
+            );
+            thread::spawn(move || {
+                let mut local = 0;
+                while !found.load(Ordering::Relaxed) {
+                    let Some(result) = generate(word_count) else {
+                        continue;
+                    };
+                    local += 1;
+                    if local == REPORT_EVERY {
+                        attempts.fetch_add(local, Ordering::Relaxed);
+                        local = 0;
+                    }
+                    if pattern.matches(&result.keypair.pubkey().to_string()) {
+                        found.store(true, Ordering::Relaxed);
+                        let _ = sender.send(result);
+                    }
+                }
+                attempts.fetch_add(local, Ordering::Relaxed);
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let start = Instant::now();
+    let result = loop {
+        match receiver.recv_timeout(Duration::from_secs(1)) {
+            Ok(result) => break result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let tried = attempts.load(Ordering::Relaxed) as f64;
+                let rate = tried / start.elapsed().as_secs_f64();
+                // Each attempt is independent, so the expected time does not
+                // shrink as attempts accumulate.
+                eprint!(
+                    "\r{:.0} attempts, {:.0} keys/s, expected time {}   ",
+                    tried,
+                    rate,
+                    format_duration(expected / rate)
+                );
+                io::stderr().flush()?;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err("All grinding threads stopped".into())
+            }
+        }
+    };
+    for worker in workers {
+        let _ = worker.join();
+    }
+    eprintln!(
+        "\rFound after {} attempts in {}",
+        attempts.load(Ordering::Relaxed),
+        format_duration(start.elapsed().as_secs_f64())
+    );
+    Ok(result)
+}
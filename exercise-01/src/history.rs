@@ -0,0 +1,247 @@
+use crate::{amount::format_sol, rpc::Rpc};
+use chrono::DateTime;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    parse_instruction::ParsedInstruction, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction,
+};
+use std::{error::Error, io::Write, str::FromStr};
+
+/// Largest page `getSignaturesForAddress` returns.
+const PAGE_SIZE: usize = 1000;
+
+/// Output formats for `scw history`.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum HistoryFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+/// One transaction in an address's history.
+#[derive(Serialize, Debug)]
+pub struct HistoryEntry {
+    pub signature: String,
+    pub slot: u64,
+    pub time: Option<String>,
+    pub status: String,
+    pub fee_lamports: u64,
+    /// Change of the address's SOL balance, fee included.
+    pub change_lamports: i64,
+    pub instructions: Vec<String>,
+}
+
+/// Pages through the signatures of `address`, newest first, until `limit`
+/// signatures are collected or the history is exhausted.
+async fn get_signatures(
+    rpc_client: &dyn Rpc,
+    address: &Pubkey,
+    limit: usize,
+    before: Option<Signature>,
+) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, Box<dyn Error>> {
+    let mut signatures = Vec::new();
+    let mut before = before;
+    while signatures.len() < limit {
+        let requested = (limit - signatures.len()).min(PAGE_SIZE);
+        let page = rpc_client
+            .get_signatures_for_address(address, before, requested)
+            .await?;
+        let complete = page.len() < requested;
+        signatures.extend(page);
+        match signatures.last() {
+            Some(last) if !complete => before = Some(Signature::from_str(&last.signature)?),
+            _ => break,
+        }
+    }
+    Ok(signatures)
+}
+
+fn field<'a>(info: &'a Value, key: &str) -> &'a str {
+    info[key].as_str().unwrap_or("?")
+}
+
+/// Describes an instruction the RPC node was able to parse.
+fn describe_parsed(instruction: &ParsedInstruction) -> String {
+    let program = instruction.program.as_str();
+    let parsed = &instruction.parsed;
+    let info = &parsed["info"];
+    match (program, parsed["type"].as_str()) {
+        ("system", Some("transfer")) => format!(
+            "system transfer {} SOL from {} to {}",
+            format_sol(info["lamports"].as_u64().unwrap_or(0)),
+            field(info, "source"),
+            field(info, "destination")
+        ),
+        ("spl-token", Some("transferChecked")) => format!(
+            "spl-token transferChecked {} of {} from {} to {}",
+            field(&info["tokenAmount"], "uiAmountString"),
+            field(info, "mint"),
+            field(info, "source"),
+            field(info, "destination")
+        ),
+        ("spl-token", Some("transfer")) => format!(
+            "spl-token transfer {} base units from {} to {}",
+            field(info, "amount"),
+            field(info, "source"),
+            field(info, "destination")
+        ),
+        ("spl-token", Some(kind @ ("mintToChecked" | "burnChecked"))) => format!(
+            "spl-token {} {} of {}",
+            kind,
+            field(&info["tokenAmount"], "uiAmountString"),
+            field(info, "mint")
+        ),
+        ("spl-memo", _) => format!("memo \"{}\"", parsed.as_str().unwrap_or("")),
+        (_, Some(kind)) => format!("{} {}", program, kind),
+        (_, None) => program.to_string(),
+    }
+}
+
+fn describe_instruction(instruction: &UiInstruction) -> String {
+    match instruction {
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed)) => describe_parsed(parsed),
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(decoded)) => format!(
+            "program {} ({} accounts)",
+            decoded.program_id,
+            decoded.accounts.len()
+        ),
+        UiInstruction::Compiled(compiled) => format!(
+            "program #{} ({} accounts)",
+            compiled.program_id_index,
+            compiled.accounts.len()
+        ),
+    }
+}
+
+async fn get_entry(
+    rpc_client: &dyn Rpc,
+    address: &Pubkey,
+    status: &RpcConfirmedTransactionStatusWithSignature,
+) -> Result<HistoryEntry, Box<dyn Error>> {
+    let signature = Signature::from_str(&status.signature)?;
+    let confirmed = rpc_client.get_transaction(&signature).await?;
+    let message = match &confirmed.transaction.transaction {
+        EncodedTransaction::Json(transaction) => match &transaction.message {
+            UiMessage::Parsed(message) => message,
+            UiMessage::Raw(_) => return Err("RPC node returned an unparsed message".into()),
+        },
+        _ => return Err("RPC node returned an undecoded transaction".into()),
+    };
+    let meta = confirmed
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or("RPC node returned no transaction metadata")?;
+
+    let address = address.to_string();
+    let change_lamports = message
+        .account_keys
+        .iter()
+        .position(|key| key.pubkey == address)
+        .and_then(|i| Some((*meta.pre_balances.get(i)?, *meta.post_balances.get(i)?)))
+        .map(|(pre, post)| post as i64 - pre as i64)
+        .unwrap_or(0);
+    let result = match &meta.err {
+        Some(err) => format!("failed: {}", err),
+        None => "ok".to_string(),
+    };
+    Ok(HistoryEntry {
+        signature: signature.to_string(),
+        slot: confirmed.slot,
+        time: confirmed
+            .block_time
+            .or(status.block_time)
+            .and_then(|time| DateTime::from_timestamp(time, 0))
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string()),
+        status: result,
+        fee_lamports: meta.fee,
+        change_lamports,
+        instructions: message
+            .instructions
+            .iter()
+            .map(describe_instruction)
+            .collect(),
+    })
+}
+
+/// Fetches and decodes up to `limit` transactions of `address`, newest first.
+pub async fn get_history(
+    rpc_client: &dyn Rpc,
+    address: &Pubkey,
+    limit: usize,
+    before: Option<Signature>,
+) -> Result<Vec<HistoryEntry>, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    for status in get_signatures(rpc_client, address, limit, before).await? {
+        entries.push(get_entry(rpc_client, address, &status).await?);
+    }
+    Ok(entries)
+}
+
+fn format_change(lamports: i64) -> String {
+    let sol = format_sol(lamports.unsigned_abs());
+    if lamports < 0 {
+        format!("-{}", sol)
+    } else {
+        format!("+{}", sol)
+    }
+}
+
+pub fn write_history(
+    entries: &[HistoryEntry],
+    format: HistoryFormat,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        HistoryFormat::Text => {
+            for entry in entries {
+                writeln!(
+                    out,
+                    "{}  slot {}  {}  fee {} SOL  {} SOL",
+                    entry.time.as_deref().unwrap_or("unknown time"),
+                    entry.slot,
+                    entry.status,
+                    format_sol(entry.fee_lamports),
+                    format_change(entry.change_lamports)
+                )?;
+                writeln!(out, "  {}", entry.signature)?;
+                for (index, instruction) in entry.instructions.iter().enumerate() {
+                    writeln!(out, "  {}. {}", index + 1, instruction)?;
+                }
+            }
+        }
+        HistoryFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record([
+                "signature",
+                "slot",
+                "time",
+                "status",
+                "fee_lamports",
+                "change_lamports",
+                "instructions",
+            ])?;
+            for entry in entries {
+                writer.write_record([
+                    entry.signature.clone(),
+                    entry.slot.to_string(),
+                    entry.time.clone().unwrap_or_default(),
+                    entry.status.clone(),
+                    entry.fee_lamports.to_string(),
+                    entry.change_lamports.to_string(),
+                    entry.instructions.join("; "),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        HistoryFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, entries)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::mnemonic::DerivationInfo;
+use aes_gcm_siv::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng, Payload},
+    Aes256GcmSiv, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{signature::Keypair, signer::Signer};
+use std::{env, error::Error};
+
+/// Current version of the encrypted keystore layout.
+pub const KEYSTORE_VERSION: u8 = 1;
+
+/// Environment variable read before falling back to an interactive prompt.
+pub const PASSWORD_ENV: &str = "SCW_PASSWORD";
+
+const KDF_NAME: &str = "argon2id";
+const CIPHER_NAME: &str = "aes-256-gcm-siv";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+// OWASP recommended minimum for argon2id: 19 MiB of memory, 2 iterations.
+const DEFAULT_M_COST: u32 = 19 * 1024;
+const DEFAULT_T_COST: u32 = 2;
+const DEFAULT_P_COST: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeystoreCrypto {
+    pub kdf: String,
+    pub kdf_params: KdfParams,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Password-protected wallet file. The public key stays in clear text so the
+/// address can be read without unlocking the wallet.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncryptedKeystore {
+    pub version: u8,
+    pub public_key: String,
+    pub crypto: KeystoreCrypto,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation: Option<DerivationInfo>,
+}
+
+fn derive_key(password: &str, params: &KdfParams) -> Result<[u8; KEY_LEN], Box<dyn Error>> {
+    let salt = bs58::decode(&params.salt).into_vec()?;
+    let argon_params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(KEY_LEN))
+        .map_err(|e| format!("Invalid KDF parameters: {}", e))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params);
+
+    let mut key = [0u8; KEY_LEN];
+    argon2
+        .hash_password_into(password.as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+impl EncryptedKeystore {
+    pub fn encrypt(keypair: &Keypair, password: &str) -> Result<Self, Box<dyn Error>> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let public_key = keypair.pubkey().to_string();
+        let kdf_params = KdfParams {
+            m_cost: DEFAULT_M_COST,
+            t_cost: DEFAULT_T_COST,
+            p_cost: DEFAULT_P_COST,
+            salt: bs58::encode(salt).into_string(),
+        };
+
+        let key = derive_key(password, &kdf_params)?;
+        let cipher = Aes256GcmSiv::new_from_slice(&key)?;
+        // The public key is bound as associated data so it cannot be swapped
+        // without failing authentication.
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &keypair.to_bytes(),
+                    aad: public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| "Failed to encrypt keypair")?;
+
+        Ok(EncryptedKeystore {
+            version: KEYSTORE_VERSION,
+            public_key,
+            crypto: KeystoreCrypto {
+                kdf: KDF_NAME.to_string(),
+                kdf_params,
+                cipher: CIPHER_NAME.to_string(),
+                nonce: bs58::encode(nonce).into_string(),
+                ciphertext: bs58::encode(ciphertext).into_string(),
+            },
+            derivation: None,
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<Keypair, Box<dyn Error>> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(format!("Unsupported keystore version: {}", self.version).into());
+        }
+        if self.crypto.kdf != KDF_NAME || self.crypto.cipher != CIPHER_NAME {
+            return Err(format!(
+                "Unsupported keystore algorithms: {}/{}",
+                self.crypto.kdf, self.crypto.cipher
+            )
+            .into());
+        }
+
+        let nonce = bs58::decode(&self.crypto.nonce).into_vec()?;
+        if nonce.len() != NONCE_LEN {
+            return Err("Invalid keystore nonce length".into());
+        }
+        let ciphertext = bs58::decode(&self.crypto.ciphertext).into_vec()?;
+
+        let key = derive_key(password, &self.crypto.kdf_params)?;
+        let cipher = Aes256GcmSiv::new_from_slice(&key)?;
+        let bytes = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: self.public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| "Failed to decrypt wallet: wrong password or corrupted file")?;
+
+        let keypair = Keypair::from_bytes(&bytes)?;
+        if keypair.pubkey().to_string() != self.public_key {
+            return Err("Decrypted keypair does not match the stored public key".into());
+        }
+        Ok(keypair)
+    }
+}
+
+/// Reads the wallet password from `SCW_PASSWORD`, prompting if it is unset.
+pub fn read_password(prompt: &str) -> Result<String, Box<dyn Error>> {
+    if let Ok(password) = env::var(PASSWORD_ENV) {
+        return Ok(password);
+    }
+    Ok(rpassword::prompt_password(prompt)?)
+}
+
+/// Reads a password for a new keystore, asking twice when prompting.
+pub fn read_new_password() -> Result<String, Box<dyn Error>> {
+    if let Ok(password) = env::var(PASSWORD_ENV) {
+        if password.is_empty() {
+            return Err(format!("{} is set but empty", PASSWORD_ENV).into());
+        }
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password("New wallet password: ")?;
+    if password.is_empty() {
+        return Err("Password must not be empty".into());
+    }
+    let confirmation = rpassword::prompt_password("Confirm password: ")?;
+    if password != confirmation {
+        return Err("Passwords do not match".into());
+    }
+    Ok(password)
+}
@@ -0,0 +1,31 @@
+//! Library behind the `scw` wallet CLI. Commands that fund, send and confirm
+//! transactions go through the `rpc::Rpc` trait, so they run against a
+//! cluster through `RpcClient` or against the in-memory `mock::MockRpc`,
+//! which is only built with the `mock` feature.
+
+pub mod alt;
+pub mod amount;
+pub mod batch;
+pub mod config;
+pub mod contacts;
+pub mod grind;
+pub mod history;
+pub mod keystore;
+pub mod mnemonic;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod nonce;
+pub mod offchain;
+pub mod output;
+pub mod pay;
+pub mod pda;
+pub mod preview;
+pub mod registry;
+pub mod rpc;
+pub mod simulate;
+pub mod stake;
+pub mod token;
+pub mod transaction;
+pub mod transfer;
+pub mod wallet;
+pub mod watch;
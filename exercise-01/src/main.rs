@@ -1,61 +1,115 @@
-use clap::{Parser, Subcommand, ValueEnum};
-use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
+use scw::{
+    alt, amount, batch, config, contacts, grind, history, keystore, mnemonic, nonce, offchain,
+    output, pay, pda, preview, registry, simulate, stake, token, transaction, transfer, wallet,
+    watch,
+};
+
+use amount::Amount;
+use clap::{Parser, Subcommand};
+use config::{AppConfig, Commitment, Network, Overrides};
+use contacts::AddressBook;
+use history::HistoryFormat;
+use mnemonic::{DerivationInfo, WordCount, DEFAULT_DERIVATION_PATH};
+use output::{OutputFormat, Report};
+use pay::TransferRequest;
+use registry::{is_wallet_name, WalletRegistry, DEFAULT_WALLET_FILE};
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
-    native_token::LAMPORTS_PER_SOL,
+    hash::Hash,
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use std::{error::Error, fs, str::FromStr};
+use std::{collections::BTreeSet, error::Error, fs, path::Path, process::ExitCode, str::FromStr};
+use transaction::{
+    BlockhashSource, ComputeBudget, NonceSource, PriorityFee, SendOptions, TxEncoding,
+};
+use wallet::{read_keypair, read_wallet_file, save_keypair, KeypairFormat, WalletFile};
 
-const DEFAULT_CONFIG: &str = r#"{
-    "network": "http://127.0.0.1:8899"
-}"#;
+#[derive(Parser)]
+#[clap(author, version, about)]
+struct Cli {
+    /// Path to the wallet keypair file
+    #[clap(
+        long,
+        global = true,
+        help = "Global wallet name or path to the wallet keypair file"
+    )]
+    wallet_file: Option<String>,
 
-#[derive(Serialize, Deserialize, Debug)]
-struct AppConfig {
-    network: String,
-}
+    #[clap(long, global = true, help = "Cluster profile to use")]
+    profile: Option<String>,
 
-impl AppConfig {
-    fn load_config(file_path: &str) -> Self {
-        let contents = match fs::read_to_string(file_path) {
-            Ok(contents) => contents,
-            Err(_) => {
-                let default_config: AppConfig =
-                    serde_json::from_str(DEFAULT_CONFIG).expect("Failed to parse default config");
-                default_config.save_config(file_path);
-                serde_json::to_string(&default_config).expect("Failed to serialize default config")
-            }
-        };
-        serde_json::from_str(&contents).expect("Failed to parse config file")
-    }
+    #[clap(
+        short = 'u',
+        long,
+        global = true,
+        help = "RPC URL or moniker (localnet, devnet, testnet, mainnet-beta)"
+    )]
+    url: Option<String>,
 
-    fn save_config(&self, file_path: &str) {
-        let data: String = serde_json::to_string_pretty(&self).expect("Failed to serialize config");
-        fs::write(file_path, data).expect("Failed to write config file");
-    }
-}
+    #[clap(long, global = true, help = "Websocket URL for the RPC node")]
+    ws_url: Option<String>,
 
-#[derive(Serialize, Deserialize)]
-struct WalletKeypair {
-    public_key: String,
-    secret_key: String,
-}
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        help = "Commitment level for RPC requests"
+    )]
+    commitment: Option<Commitment>,
+
+    #[clap(
+        long,
+        global = true,
+        value_name = "MICRO_LAMPORTS",
+        help = "Compute unit price in micro-lamports, or auto[:PERCENTILE] to derive it from recent fees"
+    )]
+    priority_fee: Option<PriorityFee>,
+
+    #[clap(long, global = true, help = "Compute unit limit for transactions")]
+    compute_limit: Option<u32>,
+
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        default_value = "text",
+        help = "Format of the command's result and errors"
+    )]
+    output: OutputFormat,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Simulate transactions instead of sending them"
+    )]
+    dry_run: bool,
 
-#[derive(Parser)]
-#[clap(author, version, about)]
-struct Cli {
-    /// Path to the wallet keypair file
     #[clap(
         short,
         long,
         global = true,
-        default_value = "my_wallet.json",
-        help = "Global path to the wallet keypair file"
+        help = "Send transactions without asking for confirmation"
+    )]
+    yes: bool,
+
+    #[clap(
+        long,
+        global = true,
+        value_name = "ADDRESS",
+        help = "Address lookup table to compile version 0 transactions against (repeatable)"
     )]
-    wallet_file: String,
+    lookup_table: Vec<String>,
+
+    #[clap(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Anchor IDL used to decode custom program errors in simulations"
+    )]
+    idl: Vec<String>,
 
     #[clap(subcommand)]
     command: Commands,
@@ -65,13 +119,150 @@ struct Cli {
 enum Commands {
     /// Creates a new wallet keypair and saves it locally.
     Create {
-        #[clap(short, long, help = "Optional path to save the keypair file")]
+        #[clap(
+            short,
+            long,
+            help = "Optional wallet name or path to save the keypair file"
+        )]
+        file: Option<String>,
+        #[clap(long, help = "Label stored with a named wallet")]
+        label: Option<String>,
+        #[clap(long, help = "Store the secret key unencrypted")]
+        plaintext: bool,
+        #[clap(long, help = "Overwrite an existing wallet")]
+        force: bool,
+        #[clap(long, help = "Derive the keypair from a new BIP39 seed phrase")]
+        mnemonic: bool,
+        #[clap(
+            long,
+            value_enum,
+            default_value = "12",
+            help = "Number of words in the seed phrase"
+        )]
+        word_count: WordCount,
+        #[clap(long, help = "Protect the seed phrase with an additional passphrase")]
+        passphrase: bool,
+        #[clap(long, default_value = DEFAULT_DERIVATION_PATH, help = "SLIP-0010 derivation path")]
+        derivation_path: String,
+    },
+
+    /// Recovers a wallet keypair from a BIP39 seed phrase.
+    Recover {
+        #[clap(
+            short,
+            long,
+            help = "Optional wallet name or path to save the keypair file"
+        )]
+        file: Option<String>,
+        #[clap(long, help = "Label stored with a named wallet")]
+        label: Option<String>,
+        #[clap(long, help = "Store the secret key unencrypted")]
+        plaintext: bool,
+        #[clap(long, help = "Overwrite an existing wallet")]
+        force: bool,
+        #[clap(long, help = "The seed phrase is protected by a passphrase")]
+        passphrase: bool,
+        #[clap(long, default_value = DEFAULT_DERIVATION_PATH, help = "SLIP-0010 derivation path")]
+        derivation_path: String,
+    },
+
+    /// Generates keypairs until the address has the given prefix or suffix.
+    Grind {
+        #[clap(long, default_value = "", help = "Prefix the address must start with")]
+        starts_with: String,
+        #[clap(long, default_value = "", help = "Suffix the address must end with")]
+        ends_with: String,
+        #[clap(long, help = "Match the prefix and suffix case-insensitively")]
+        ignore_case: bool,
+        #[clap(long, help = "Number of threads. Defaults to the number of cores.")]
+        threads: Option<usize>,
+        #[clap(
+            short,
+            long,
+            help = "Optional wallet name or path to save the keypair file"
+        )]
+        file: Option<String>,
+        #[clap(long, help = "Label stored with a named wallet")]
+        label: Option<String>,
+        #[clap(long, help = "Store the secret key unencrypted")]
+        plaintext: bool,
+        #[clap(long, help = "Overwrite an existing wallet")]
+        force: bool,
+        #[clap(long, help = "Derive each candidate from a new BIP39 seed phrase")]
+        mnemonic: bool,
+        #[clap(
+            long,
+            value_enum,
+            default_value = "12",
+            help = "Number of words in the seed phrase"
+        )]
+        word_count: WordCount,
+    },
+
+    /// Derives a program-derived address from seeds, or finds which of the
+    /// given seeds derive an address.
+    Pda {
+        #[clap(short, long, help = "The program that owns the address.")]
+        program: String,
+        #[clap(
+            short,
+            long,
+            help = "A seed as <encoding>:<value>: str, pubkey, hex, base58, base64, bytes, or an integer such as u8, u32le, u64be or i64. Repeatable, in order."
+        )]
+        seed: Vec<String>,
+        #[clap(
+            long,
+            value_name = "ADDRESS",
+            help = "Search the seeds for the combination and bump that derive this address."
+        )]
+        find: Option<String>,
+    },
+
+    /// Imports a solana-cli, base58 or scw keypair into an scw wallet file.
+    Import {
+        #[clap(
+            short,
+            long,
+            help = "Keypair file to import. Prompts for a secret key if omitted."
+        )]
+        input: Option<String>,
+        #[clap(
+            short,
+            long,
+            help = "Optional wallet name or path to save the keypair file"
+        )]
+        file: Option<String>,
+        #[clap(long, help = "Label stored with a named wallet")]
+        label: Option<String>,
+        #[clap(long, help = "Store the secret key unencrypted")]
+        plaintext: bool,
+        #[clap(long, help = "Overwrite an existing wallet")]
+        force: bool,
+    },
+
+    /// Exports the wallet keypair in another format.
+    Export {
+        #[clap(short, long, help = "Optional path to the keypair file to export")]
+        file: Option<String>,
+        #[clap(long, value_enum, default_value = "solana-cli", help = "Output format")]
+        format: KeypairFormat,
+        #[clap(
+            short = 'o',
+            long,
+            help = "Path to write the keypair to. Prints it if omitted."
+        )]
+        out_file: Option<String>,
+    },
+
+    /// Encrypts an existing plaintext wallet file in place.
+    Encrypt {
+        #[clap(short, long, help = "Optional path to the keypair file to encrypt")]
         file: Option<String>,
     },
 
     /// Gets the address of the wallet.
     Address {
-        #[clap(short, long, help = "The public key of the wallet to get the address.")]
+        #[clap(short, long, help = "Wallet name, @contact or address.")]
         wallet: String,
     },
 
@@ -80,11 +271,13 @@ enum Commands {
         #[clap(
             short,
             long,
-            help = "The public key of the wallet to receive the airdrop."
+            help = "The wallet to receive the airdrop: an address, @contact or wallet name."
         )]
         wallet: String,
         #[clap(short, long, help = "The amount of SOL to request.")]
-        amount: u64,
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as lamports.")]
+        lamports: bool,
     },
 
     /// Transfers SOL from one wallet to another.
@@ -95,10 +288,190 @@ enum Commands {
             help = "The public key of the sender wallet. Defaults to the wallet file."
         )]
         from: Option<String>,
-        #[clap(short, long, help = "The public key of the recipient wallet.")]
+        #[clap(
+            short,
+            long,
+            help = "The recipient: an address, @contact or wallet name."
+        )]
         to: String,
-        #[clap(short, long, help = "The amount of SOL to transfer.")]
-        amount: f64,
+        #[clap(
+            short,
+            long,
+            help = "The amount of SOL to transfer, or ALL for the balance minus fees."
+        )]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as lamports.")]
+        lamports: bool,
+        #[clap(
+            long,
+            help = "Allow a recipient that is off the ed25519 curve, such as a PDA."
+        )]
+        allow_off_curve: bool,
+        #[clap(
+            long,
+            value_name = "FILE",
+            help = "Write the unsigned transaction to a file instead of sending it."
+        )]
+        unsigned: Option<String>,
+        #[clap(
+            long,
+            value_enum,
+            default_value = "base64",
+            help = "Encoding of the unsigned transaction."
+        )]
+        encoding: TxEncoding,
+        #[clap(long, help = "Use this blockhash instead of fetching one.")]
+        blockhash: Option<String>,
+        #[clap(
+            long,
+            help = "Durable nonce account to use instead of a recent blockhash."
+        )]
+        nonce: Option<String>,
+        #[clap(
+            long,
+            help = "Nonce authority wallet, or its public key when building an unsigned transaction. Defaults to the sender."
+        )]
+        nonce_authority: Option<String>,
+    },
+
+    /// Sends SOL to every row of a CSV file with recipient and amount columns.
+    /// Interrupted runs resume from the journal without paying a row twice.
+    BatchTransfer {
+        #[clap(help = "CSV file with recipient and amount columns")]
+        file: String,
+        #[clap(short, long, help = "The sender wallet. Defaults to the wallet file.")]
+        from: Option<String>,
+        #[clap(long, help = "Interpret the amounts as lamports.")]
+        lamports: bool,
+        #[clap(long, help = "Journal of sent batches. Defaults to <FILE>.journal.")]
+        journal: Option<String>,
+        #[clap(
+            long,
+            help = "Where to write the per-row results. Defaults to <FILE>.results.csv."
+        )]
+        results: Option<String>,
+        #[clap(
+            long,
+            default_value_t = 3,
+            help = "Times to resend a batch whose blockhash expired."
+        )]
+        retries: u32,
+        #[clap(
+            long,
+            help = "Allow recipients that are off the ed25519 curve, such as PDAs."
+        )]
+        allow_off_curve: bool,
+    },
+
+    /// Signs a serialized transaction, e.g. on an offline machine.
+    Sign {
+        #[clap(help = "Path to the serialized transaction")]
+        file: String,
+        #[clap(
+            short,
+            long,
+            help = "Additional wallet that must sign, such as a nonce authority."
+        )]
+        signer: Vec<String>,
+        #[clap(
+            short = 'o',
+            long,
+            help = "Where to write the signed transaction. Defaults to the input file."
+        )]
+        out_file: Option<String>,
+    },
+
+    /// Signs an off-chain message with the wallet to prove ownership.
+    SignMessage {
+        #[clap(help = "Message to sign")]
+        message: Option<String>,
+        #[clap(long, help = "Read the message from a file instead.")]
+        message_file: Option<String>,
+    },
+
+    /// Verifies an off-chain message signature.
+    VerifyMessage {
+        #[clap(help = "Message that was signed")]
+        message: Option<String>,
+        #[clap(long, help = "Read the message from a file instead.")]
+        message_file: Option<String>,
+        #[clap(short, long, help = "Base58 signature to verify.")]
+        signature: String,
+        #[clap(
+            long,
+            help = "Public key or wallet of the signer. Defaults to the wallet."
+        )]
+        signer: Option<String>,
+    },
+
+    /// Submits a signed serialized transaction to the cluster.
+    Broadcast {
+        #[clap(help = "Path to the signed transaction")]
+        file: String,
+    },
+
+    /// Creates and parses Solana Pay transfer request URLs.
+    PayUrl {
+        #[clap(subcommand)]
+        command: PayUrlCommand,
+    },
+
+    /// Pays a Solana Pay transfer request URL from the wallet.
+    Pay {
+        #[clap(value_name = "URL", help = "The solana: URL to pay")]
+        request: TransferRequest,
+        #[clap(
+            short,
+            long,
+            help = "The amount to pay, for requests that leave it to the payer."
+        )]
+        amount: Option<Amount>,
+        #[clap(long, help = "Create the recipient's token account if missing.")]
+        fund_recipient: bool,
+        #[clap(
+            long,
+            help = "Allow a recipient that is off the ed25519 curve, such as a PDA."
+        )]
+        allow_off_curve: bool,
+    },
+
+    /// Finds the transaction that paid a Solana Pay request and checks it
+    /// against the request.
+    PayVerify {
+        #[clap(
+            value_name = "URL",
+            help = "The solana: URL that was paid",
+            required_unless_present_all = ["reference", "recipient", "amount"]
+        )]
+        request: Option<TransferRequest>,
+        #[clap(
+            long,
+            help = "Reference key of the request.",
+            conflicts_with = "request"
+        )]
+        reference: Vec<String>,
+        #[clap(long, help = "Expected recipient.", conflicts_with = "request")]
+        recipient: Option<String>,
+        #[clap(long, help = "Expected amount.", conflicts_with = "request")]
+        amount: Option<Amount>,
+        #[clap(long, help = "Expected token mint.", conflicts_with = "request")]
+        spl_token: Option<String>,
+        #[clap(long, help = "Expected memo.", conflicts_with = "request")]
+        memo: Option<String>,
+    },
+
+    /// Streams balance changes of an address until Ctrl-C, reconnecting
+    /// when the websocket drops.
+    Watch {
+        #[clap(help = "Address, @contact or wallet to watch")]
+        address: String,
+        #[clap(
+            long,
+            help = "Also stream the logs of transactions that mention the address, such as a program id."
+        )]
+        logs: bool,
+        #[clap(long, help = "Stop after this many updates.")]
+        count: Option<usize>,
     },
 
     /// Checks the balance of a specified wallet.
@@ -106,162 +479,2379 @@ enum Commands {
         #[clap(
             short,
             long,
-            help = "The public key of the wallet to check the balance."
+            help = "The wallet to check: an address, @contact or wallet name."
         )]
         wallet: String,
     },
 
+    /// Shows the decoded transaction history of an address, newest first.
+    History {
+        #[clap(help = "Address or wallet to show. Defaults to the wallet.")]
+        address: Option<String>,
+        #[clap(
+            short,
+            long,
+            default_value_t = 20,
+            help = "Maximum number of transactions."
+        )]
+        limit: usize,
+        #[clap(long, help = "Start from transactions older than this signature.")]
+        before: Option<String>,
+        #[clap(long, value_enum, default_value = "text", help = "Output format.")]
+        format: HistoryFormat,
+        #[clap(
+            long,
+            value_name = "FILE",
+            help = "Write the history to a file instead of printing it."
+        )]
+        export: Option<String>,
+    },
+
     /// Lists all saved wallets.
+    #[clap(alias = "list")]
     ListWallets {
         #[clap(short, long, help = "Path to the directory where wallets are saved.")]
         file: Option<String>,
+        #[clap(short, long, help = "Fetch the live balance of each wallet.")]
+        balance: bool,
+    },
+
+    /// Sets the wallet used when no wallet file is given.
+    SetDefault {
+        #[clap(help = "Name of a saved wallet")]
+        name: String,
     },
 
-    /// Sets the network configuration.
+    /// Updates the active cluster profile, or the one given by --profile.
+    /// --url, --ws-url and --commitment are saved into the profile.
     SetConfig {
-        #[clap(short, long, help = "Network to set (localnet, devnet, testnet)")]
-        network: Network,
+        #[clap(
+            short,
+            long,
+            help = "Network to set (localnet, devnet, testnet, mainnet-beta)"
+        )]
+        network: Option<Network>,
+        #[clap(long, help = "Wallet used by this profile when none is given")]
+        default_wallet: Option<String>,
+        #[clap(long, help = "RPC request timeout in seconds")]
+        timeout: Option<u64>,
+    },
+
+    /// Manages durable nonce accounts.
+    Nonce {
+        #[clap(subcommand)]
+        command: NonceCommand,
+    },
+
+    /// Manages address lookup tables for version 0 transactions.
+    Alt {
+        #[clap(subcommand)]
+        command: AltCommand,
+    },
+
+    /// Manages SPL tokens owned by the wallet.
+    Token {
+        #[clap(subcommand)]
+        command: TokenCommand,
+    },
+
+    /// Manages stake accounts funded by the wallet.
+    Stake {
+        #[clap(subcommand)]
+        command: StakeCommand,
+    },
+
+    /// Manages the address book of named recipients, used as @name.
+    Contacts {
+        #[clap(subcommand)]
+        command: ContactsCommand,
+    },
+
+    /// Manages cluster profiles.
+    Profile {
+        #[clap(subcommand)]
+        command: ProfileCommand,
     },
 }
 
-#[derive(Parser, ValueEnum, Clone, Debug)]
-pub enum Network {
-    Localnet,
-    Devnet,
-    Testnet,
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// Lists all cluster profiles.
+    List,
+    /// Shows the settings in effect after environment and flag overrides.
+    Show,
+    /// Makes a profile the active one.
+    Use {
+        #[clap(help = "Name of the profile")]
+        name: String,
+    },
+    /// Deletes a profile.
+    Remove {
+        #[clap(help = "Name of the profile")]
+        name: String,
+    },
 }
 
-impl From<Network> for String {
-    fn from(network: Network) -> Self {
-        match network {
-            Network::Localnet => "http://127.0.0.1:8899".to_string(),
-            Network::Devnet => "https://api.devnet.solana.com".to_string(),
-            Network::Testnet => "https://api.testnet.solana.com".to_string(),
-        }
+#[derive(Subcommand)]
+enum ContactsCommand {
+    /// Saves an address under a name.
+    Add {
+        #[clap(help = "Name of the contact, used as @name")]
+        name: String,
+        #[clap(help = "Address of the contact, or a wallet name")]
+        address: String,
+        #[clap(long, help = "Label stored with the contact")]
+        label: Option<String>,
+    },
+    /// Lists all contacts.
+    List,
+    /// Deletes a contact.
+    Remove {
+        #[clap(help = "Name of the contact")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PayUrlCommand {
+    /// Builds a transfer request URL and shows it as a QR code.
+    Create {
+        #[clap(
+            short,
+            long,
+            help = "The recipient: an address, @contact or wallet name."
+        )]
+        recipient: String,
+        #[clap(
+            short,
+            long,
+            help = "Amount in SOL, or in tokens with --spl-token. Omit to let the payer choose."
+        )]
+        amount: Option<Amount>,
+        #[clap(long, help = "Mint of the SPL token to request instead of SOL.")]
+        spl_token: Option<String>,
+        #[clap(
+            long,
+            help = "Reference key to find the payment by. Repeatable; defaults to a new random key."
+        )]
+        reference: Vec<String>,
+        #[clap(long, help = "Who is requesting the payment, such as the store name.")]
+        label: Option<String>,
+        #[clap(long, help = "What the payment is for, shown to the payer.")]
+        message: Option<String>,
+        #[clap(long, help = "Memo recorded on chain with the payment.")]
+        memo: Option<String>,
+        #[clap(long, help = "Print only the URL, without the QR code.")]
+        no_qr: bool,
+    },
+    /// Decodes a transfer request URL.
+    Parse {
+        #[clap(value_name = "URL", help = "The solana: URL to decode")]
+        request: TransferRequest,
+    },
+}
+
+#[derive(Subcommand)]
+enum NonceCommand {
+    /// Creates a durable nonce account funded by the wallet.
+    Create {
+        #[clap(
+            long,
+            help = "Keypair file for the nonce account. A new keypair is generated if omitted."
+        )]
+        keypair: Option<String>,
+        #[clap(long, help = "Nonce authority. Defaults to the wallet.")]
+        authority: Option<String>,
+        #[clap(
+            short,
+            long,
+            default_value = "0",
+            help = "SOL to deposit on top of the rent-exempt minimum."
+        )]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as lamports.")]
+        lamports: bool,
+    },
+    /// Shows the stored nonce and authority of a nonce account.
+    Show {
+        #[clap(help = "Address of the nonce account")]
+        nonce: String,
+    },
+    /// Advances the stored nonce, invalidating transactions that use the old value.
+    Advance {
+        #[clap(help = "Address of the nonce account")]
+        nonce: String,
+        #[clap(long, help = "Wallet of the nonce authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Withdraws SOL from a nonce account.
+    Withdraw {
+        #[clap(help = "Address of the nonce account")]
+        nonce: String,
+        #[clap(
+            short,
+            long,
+            help = "The recipient: an address, @contact or wallet name."
+        )]
+        to: String,
+        #[clap(
+            short,
+            long,
+            help = "The amount of SOL to withdraw, or ALL to close the account."
+        )]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as lamports.")]
+        lamports: bool,
+        #[clap(long, help = "Wallet of the nonce authority. Defaults to the wallet.")]
+        authority: Option<String>,
+        #[clap(
+            long,
+            help = "Allow a recipient that is off the ed25519 curve, such as a PDA."
+        )]
+        allow_off_curve: bool,
+    },
+    /// Assigns a new authority to a nonce account.
+    Authorize {
+        #[clap(help = "Address of the nonce account")]
+        nonce: String,
+        #[clap(long, help = "The public key of the new authority.")]
+        new_authority: String,
+        #[clap(
+            long,
+            help = "Wallet of the current nonce authority. Defaults to the wallet."
+        )]
+        authority: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AltCommand {
+    /// Creates an address lookup table funded by the wallet.
+    Create {
+        #[clap(long, help = "Table authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Adds addresses to a lookup table, skipping those it already holds.
+    Extend {
+        #[clap(help = "Address of the lookup table")]
+        table: String,
+        #[clap(
+            required = true,
+            help = "Addresses to add: addresses, @contacts or wallet names."
+        )]
+        addresses: Vec<String>,
+        #[clap(long, help = "Wallet of the table authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Deactivates a lookup table so it can be closed after a cooldown.
+    Deactivate {
+        #[clap(help = "Address of the lookup table")]
+        table: String,
+        #[clap(long, help = "Wallet of the table authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Closes a deactivated lookup table and reclaims its rent.
+    Close {
+        #[clap(help = "Address of the lookup table")]
+        table: String,
+        #[clap(
+            short,
+            long,
+            help = "Recipient of the rent: an address, @contact or wallet name. Defaults to the wallet."
+        )]
+        to: Option<String>,
+        #[clap(long, help = "Wallet of the table authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Shows the authority, status and addresses of a lookup table.
+    Show {
+        #[clap(help = "Address of the lookup table")]
+        table: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// Creates a new token mint.
+    CreateMint {
+        #[clap(long, default_value_t = 9, help = "Number of decimals of the token.")]
+        decimals: u8,
+        #[clap(
+            long,
+            help = "Keypair file for the mint account. A new keypair is generated if omitted."
+        )]
+        keypair: Option<String>,
+        #[clap(long, help = "Mint authority. Defaults to the wallet.")]
+        mint_authority: Option<String>,
+        #[clap(long, help = "Optional freeze authority.")]
+        freeze_authority: Option<String>,
+    },
+    /// Creates the associated token account for a mint.
+    CreateAccount {
+        #[clap(help = "Address of the token mint")]
+        mint: String,
+        #[clap(long, help = "Owner of the token account. Defaults to the wallet.")]
+        owner: Option<String>,
+    },
+    /// Mints tokens. The wallet must be the mint authority.
+    Mint {
+        #[clap(help = "Address of the token mint")]
+        mint: String,
+        #[clap(help = "Amount of tokens to mint")]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as base units.")]
+        base_units: bool,
+        #[clap(
+            short,
+            long,
+            help = "Owner of the receiving account. Defaults to the wallet."
+        )]
+        to: Option<String>,
+    },
+    /// Transfers tokens to another wallet's associated token account.
+    Transfer {
+        #[clap(help = "Address of the token mint")]
+        mint: String,
+        #[clap(help = "Amount of tokens to transfer, or ALL for the whole balance")]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as base units.")]
+        base_units: bool,
+        #[clap(
+            short,
+            long,
+            help = "The recipient: an address, @contact or wallet name."
+        )]
+        to: String,
+        #[clap(long, help = "Create the recipient's token account if it is missing.")]
+        fund_recipient: bool,
+        #[clap(
+            long,
+            help = "Allow a recipient that is off the ed25519 curve, such as a PDA."
+        )]
+        allow_off_curve: bool,
+    },
+    /// Shows the token balance of a wallet.
+    Balance {
+        #[clap(help = "Address of the token mint")]
+        mint: String,
+        #[clap(long, help = "Owner of the token account. Defaults to the wallet.")]
+        owner: Option<String>,
+    },
+    /// Lists all token accounts of a wallet.
+    Accounts {
+        #[clap(long, help = "Owner of the token accounts. Defaults to the wallet.")]
+        owner: Option<String>,
+    },
+    /// Closes an empty token account and reclaims its rent.
+    Close {
+        #[clap(help = "Address of the token mint")]
+        mint: String,
+        #[clap(
+            long,
+            help = "Token account to close. Defaults to the wallet's associated token account."
+        )]
+        account: Option<String>,
+        #[clap(
+            long,
+            help = "Where to send the reclaimed rent. Defaults to the wallet."
+        )]
+        recipient: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StakeCommand {
+    /// Creates a stake account funded by the wallet, optionally delegating it.
+    Create {
+        #[clap(short, long, help = "SOL to stake on top of the rent-exempt reserve.")]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as lamports.")]
+        lamports: bool,
+        #[clap(
+            long,
+            help = "Keypair file for the stake account. A new keypair is generated if omitted."
+        )]
+        keypair: Option<String>,
+        #[clap(long, help = "Stake authority. Defaults to the wallet.")]
+        staker: Option<String>,
+        #[clap(long, help = "Withdraw authority. Defaults to the wallet.")]
+        withdrawer: Option<String>,
+        #[clap(long, help = "Vote account to delegate the new stake to.")]
+        validator: Option<String>,
+    },
+    /// Delegates a stake account to a validator.
+    Delegate {
+        #[clap(help = "Address of the stake account")]
+        stake: String,
+        #[clap(long, help = "Vote account of the validator.")]
+        validator: String,
+        #[clap(long, help = "Wallet of the stake authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Deactivates a stake account so it can be withdrawn after cooldown.
+    Deactivate {
+        #[clap(help = "Address of the stake account")]
+        stake: String,
+        #[clap(long, help = "Wallet of the stake authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Withdraws SOL from an inactive stake account.
+    Withdraw {
+        #[clap(help = "Address of the stake account")]
+        stake: String,
+        #[clap(
+            short,
+            long,
+            help = "The recipient: an address, @contact or wallet name."
+        )]
+        to: String,
+        #[clap(
+            short,
+            long,
+            help = "The amount of SOL to withdraw, or ALL to close the account."
+        )]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as lamports.")]
+        lamports: bool,
+        #[clap(
+            long,
+            help = "Wallet of the withdraw authority. Defaults to the wallet."
+        )]
+        authority: Option<String>,
+        #[clap(
+            long,
+            help = "Allow a recipient that is off the ed25519 curve, such as a PDA."
+        )]
+        allow_off_curve: bool,
+    },
+    /// Moves part of a stake account into a new stake account.
+    Split {
+        #[clap(help = "Address of the stake account to split")]
+        stake: String,
+        #[clap(short, long, help = "The amount of SOL to move.")]
+        amount: Amount,
+        #[clap(long, help = "Interpret the amount as lamports.")]
+        lamports: bool,
+        #[clap(
+            long,
+            help = "Keypair file for the new stake account. A new keypair is generated if omitted."
+        )]
+        keypair: Option<String>,
+        #[clap(long, help = "Wallet of the stake authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Merges a stake account into another with the same authorities.
+    Merge {
+        #[clap(help = "Stake account that receives the merged stake")]
+        destination: String,
+        #[clap(long, help = "Stake account to merge and close.")]
+        source: String,
+        #[clap(long, help = "Wallet of the stake authority. Defaults to the wallet.")]
+        authority: Option<String>,
+    },
+    /// Shows the authorities, activation state and recent rewards of a stake account.
+    Show {
+        #[clap(help = "Address of the stake account")]
+        stake: String,
+        #[clap(
+            long,
+            default_value_t = 5,
+            help = "Number of past epochs to show rewards for."
+        )]
+        rewards: u64,
+    },
+    /// Lists validators by active stake.
+    Validators {
+        #[clap(
+            short,
+            long,
+            default_value_t = 20,
+            help = "Maximum number of validators."
+        )]
+        limit: usize,
+        #[clap(long, help = "Include delinquent validators.")]
+        delinquent: bool,
+    },
+}
+
+/// Resolves a per-command wallet argument, then the global wallet (from
+/// `--wallet-file` or the profile), then the registry default.
+fn wallet_path(registry: &WalletRegistry, global: Option<&str>, file: Option<&str>) -> String {
+    registry.resolve_or_default(file.or(global))
+}
+
+fn overrides(cli: &Cli) -> Overrides<'_> {
+    Overrides {
+        profile: cli.profile.as_deref(),
+        url: cli.url.as_deref(),
+        ws_url: cli.ws_url.as_deref(),
+        commitment: cli.commitment,
     }
 }
 
-fn save_keypair(keypair: &Keypair, file_path: &str) -> Result<(), Box<dyn Error>> {
-    let wallet = WalletKeypair {
-        public_key: keypair.pubkey().to_string(),
-        secret_key: bs58::encode(keypair.to_bytes()).into_string(),
+/// Handles `set-config` and `profile`, which must work before the selected
+/// profile exists.
+fn run_config_command(
+    cli: &Cli,
+    config: &mut AppConfig,
+    config_path: &Path,
+) -> Result<Report, Box<dyn Error>> {
+    let report = match &cli.command {
+        Commands::SetConfig {
+            network,
+            default_wallet,
+            timeout,
+        } => {
+            let name = config.selected_profile(cli.profile.as_deref());
+            let profile = config.profiles.entry(name.clone()).or_default();
+            if let Some(network) = network {
+                profile.rpc_url = network.clone().into();
+            }
+            if let Some(url) = &cli.url {
+                profile.rpc_url = config::normalize_url(url);
+            }
+            if let Some(ws_url) = &cli.ws_url {
+                profile.ws_url = Some(ws_url.clone());
+            }
+            if let Some(commitment) = cli.commitment {
+                profile.commitment = commitment;
+            }
+            if let Some(wallet) = default_wallet {
+                profile.default_wallet = Some(wallet.clone());
+            }
+            if let Some(timeout) = timeout {
+                profile.timeout_secs = *timeout;
+            }
+            let rpc_url = profile.rpc_url.clone();
+            config.save_config(config_path)?;
+            Report::new()
+                .line(format!("Profile '{}' updated", name))
+                .line(format!("Network set to: {}", rpc_url))
+                .field("profile", name)
+                .field("rpc_url", rpc_url)
+        }
+        Commands::Profile { command } => match command {
+            ProfileCommand::List => {
+                let mut report = Report::new();
+                let mut profiles = Vec::new();
+                for (name, profile) in &config.profiles {
+                    let active = *name == config.active_profile;
+                    let marker = if active { "*" } else { " " };
+                    report = report.line(format!("{} {:<16} {}", marker, name, profile.rpc_url));
+                    profiles.push(serde_json::json!({
+                        "name": name,
+                        "rpc_url": profile.rpc_url,
+                        "active": active,
+                    }));
+                }
+                report.field("profiles", profiles)
+            }
+            ProfileCommand::Show => {
+                let settings = config.resolve(&overrides(cli))?;
+                let mut report = Report::new()
+                    .line(format!("Profile: {}", settings.profile))
+                    .line(format!("RPC URL: {}", settings.rpc_url))
+                    .line(format!("Websocket URL: {}", settings.ws_url))
+                    .line(format!("Commitment: {}", settings.commitment.commitment))
+                    .line(format!("Timeout: {}s", settings.timeout.as_secs()))
+                    .field("profile", &settings.profile)
+                    .field("rpc_url", &settings.rpc_url)
+                    .field("ws_url", &settings.ws_url)
+                    .field("commitment", settings.commitment.commitment.to_string())
+                    .field("timeout_secs", settings.timeout.as_secs())
+                    .field("default_wallet", &settings.default_wallet);
+                if let Some(wallet) = &settings.default_wallet {
+                    report = report.line(format!("Default wallet: {}", wallet));
+                }
+                report
+            }
+            ProfileCommand::Use { name } => {
+                if !config.profiles.contains_key(name) {
+                    return Err(format!("Unknown profile: {}", name).into());
+                }
+                config.active_profile = name.clone();
+                config.save_config(config_path)?;
+                Report::new()
+                    .line(format!("Active profile set to: {}", name))
+                    .field("active_profile", name)
+            }
+            ProfileCommand::Remove { name } => {
+                if *name == config.active_profile {
+                    return Err("Cannot remove the active profile".into());
+                }
+                if config.profiles.remove(name).is_none() {
+                    return Err(format!("Unknown profile: {}", name).into());
+                }
+                config.save_config(config_path)?;
+                Report::new()
+                    .line(format!("Profile removed: {}", name))
+                    .field("removed", name)
+            }
+        },
+        _ => unreachable!("not a config command"),
     };
-    let json = serde_json::to_string_pretty(&wallet)?;
-    fs::write(file_path, json).map_err(|e| {
-        eprintln!("Failed to save keypair at '{}': {}", file_path, e);
-        e.into()
-    })
+    Ok(report)
 }
 
-fn read_keypair(file_path: &str) -> Result<Keypair, Box<dyn Error>> {
-    let data = fs::read_to_string(file_path)
-        .map_err(|e| {
-            eprintln!("Failed to read keypair file '{}': {}", file_path, e);
-        })
-        .unwrap();
-    let wallet: WalletKeypair = serde_json::from_str(&data)?;
-    let bytes = bs58::decode(wallet.secret_key).into_vec()?;
-    Keypair::from_bytes(&bytes).map_err(|e| {
-        eprintln!("Failed to decode keypair from '{}': {}", file_path, e);
-        e.into()
-    })
+/// Accepts either a base58 public key or a wallet name/path, whose public key
+/// is read without unlocking it.
+fn wallet_pubkey(
+    registry: &WalletRegistry,
+    contacts: &AddressBook,
+    value: &str,
+) -> Result<Pubkey, Box<dyn Error>> {
+    if value.starts_with(contacts::ALIAS_PREFIX) {
+        return contacts.pubkey(value);
+    }
+    match Pubkey::from_str(value) {
+        Ok(pubkey) => Ok(pubkey),
+        Err(_) => read_wallet_file(&registry.resolve(value))?.public_key(),
+    }
 }
 
-async fn request_airdrop(
-    rpc_client: &RpcClient,
-    pubkey: &Pubkey,
-    amount_sol: u64,
-) -> Result<Signature, Box<dyn Error>> {
-    let sig = rpc_client.request_airdrop(pubkey, amount_sol * LAMPORTS_PER_SOL)?;
-    loop {
-        let confirmed = rpc_client.confirm_transaction(&sig)?;
-        if confirmed {
-            break;
+/// Checks a transfer destination before anything is signed. Off-curve
+/// addresses have no private key, so funds sent to one can only be moved by
+/// the program that owns it; they are refused unless `allow_off_curve` is set.
+/// Recipients that are neither contacts, saved wallets nor paid before get a
+/// warning.
+fn check_recipient(
+    registry: &WalletRegistry,
+    contacts: &AddressBook,
+    recipient: &Pubkey,
+    allow_off_curve: bool,
+) -> Result<(), Box<dyn Error>> {
+    if !recipient.is_on_curve() {
+        if !allow_off_curve {
+            return Err(format!(
+                "{} is off the ed25519 curve (a program-derived address) and has no private key; pass --allow-off-curve to send to it anyway",
+                recipient
+            )
+            .into());
         }
+        eprintln!(
+            "Warning: {} is off the ed25519 curve; only its owning program can move these funds",
+            recipient
+        );
     }
-    Ok(sig)
+    let own_wallet = registry.wallets.keys().any(|name| {
+        read_wallet_file(&registry.wallet_path(name))
+            .and_then(|wallet| wallet.public_key())
+            .is_ok_and(|address| address == *recipient)
+    });
+    if !own_wallet && !contacts.is_known(recipient) {
+        eprintln!(
+            "Warning: {} is a first-time recipient. Double-check the address.",
+            recipient
+        );
+    }
+    Ok(())
 }
 
-fn transfer_funds(
-    rpc_client: &RpcClient,
-    sender_keypair: &Keypair,
-    receiver_pub_key: &Pubkey,
-    amount_sol: f64,
-) -> Result<Signature, Box<dyn Error>> {
-    let amount_lamports = (amount_sol * LAMPORTS_PER_SOL as f64) as u64;
-
-    let latest_blockhash = match rpc_client.get_latest_blockhash() {
-        Ok(blockhash) => blockhash,
-        Err(e) => return Err(Box::new(e)),
+/// Saves a new keypair to `target`, registering it when `target` is a wallet name.
+fn store_new_wallet(
+    registry: &mut WalletRegistry,
+    target: &str,
+    label: Option<&str>,
+    keypair: &Keypair,
+    plaintext: bool,
+    derivation: Option<&DerivationInfo>,
+    force: bool,
+) -> Result<String, Box<dyn Error>> {
+    let file_path = registry.new_wallet_path(target, force)?;
+    let password = if plaintext {
+        None
+    } else {
+        Some(keystore::read_new_password()?)
     };
+    save_keypair(keypair, &file_path, password.as_deref(), derivation)?;
+    if is_wallet_name(target) {
+        registry.register(target, label);
+        registry.save()?;
+    }
+    Ok(file_path)
+}
+
+/// One saved wallet as listed by `list-wallets`. Wallets whose file cannot
+/// be read are listed with the error instead of an address.
+#[derive(Serialize)]
+struct WalletRow {
+    name: String,
+    address: Option<String>,
+    created_at: String,
+    label: Option<String>,
+    default: bool,
+    lamports: Option<u64>,
+    error: Option<String>,
+}
+
+async fn wallet_rows(
+    registry: &WalletRegistry,
+    rpc_client: &RpcClient,
+    show_balance: bool,
+) -> Result<Vec<WalletRow>, Box<dyn Error>> {
+    let mut rows = Vec::new();
+    for (name, entry) in &registry.wallets {
+        let mut row = WalletRow {
+            name: name.clone(),
+            address: None,
+            created_at: entry.created_at.clone(),
+            label: entry.label.clone(),
+            default: registry.default.as_ref() == Some(name),
+            lamports: None,
+            error: None,
+        };
+        match read_wallet_file(&registry.wallet_path(name)).and_then(|wallet| wallet.public_key()) {
+            Ok(address) => {
+                if show_balance {
+                    row.lamports = Some(rpc_client.get_balance(&address).await?);
+                }
+                row.address = Some(address.to_string());
+            }
+            Err(err) => row.error = Some(err.to_string()),
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
 
-    let transaction = solana_sdk::transaction::Transaction::new_signed_with_payer(
-        &[solana_sdk::system_instruction::transfer(
-            &sender_keypair.pubkey(),
-            receiver_pub_key,
-            amount_lamports,
-        )],
-        Some(&sender_keypair.pubkey()),
-        &[sender_keypair],
-        latest_blockhash,
+fn format_wallet_table(rows: &[WalletRow], show_balance: bool) -> String {
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let mut table = format!(
+        "  {:<name_width$}  {:<44}  {:<19}",
+        "NAME", "ADDRESS", "CREATED"
     );
+    if show_balance {
+        table.push_str(&format!("  {:>14}", "BALANCE (SOL)"));
+    }
+    table.push_str("  LABEL");
+    for row in rows {
+        let marker = if row.default { "*" } else { " " };
+        if let Some(error) = &row.error {
+            table.push_str(&format!(
+                "\n{} {:<name_width$}  error: {}",
+                marker, row.name, error
+            ));
+            continue;
+        }
+        table.push_str(&format!(
+            "\n{} {:<name_width$}  {:<44}  {:<19}",
+            marker,
+            row.name,
+            row.address.as_deref().unwrap_or_default(),
+            row.created_at
+        ));
+        if let Some(lamports) = row.lamports {
+            table.push_str(&format!("  {:>14}", amount::format_sol(lamports)));
+        }
+        if let Some(label) = &row.label {
+            table.push_str(&format!("  {}", label));
+        }
+    }
+    table
+}
 
-    rpc_client
-        .send_and_confirm_transaction(&transaction)
-        .map_err(|e| e.into())
+/// Builds the blockhash source from the `--blockhash`, `--nonce` and
+/// `--nonce-authority` arguments.
+fn blockhash_source(
+    blockhash: Option<&str>,
+    nonce: Option<Pubkey>,
+    nonce_authority: Option<Pubkey>,
+    sender: &Pubkey,
+) -> Result<BlockhashSource, Box<dyn Error>> {
+    Ok(BlockhashSource {
+        blockhash: blockhash.map(Hash::from_str).transpose()?,
+        nonce: nonce.map(|account| NonceSource {
+            account,
+            authority: nonce_authority.unwrap_or(*sender),
+        }),
+    })
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
-    let config = AppConfig::load_config("./config.json");
-    let rpc_client = RpcClient::new(config.network.clone());
+    match run(&cli).await {
+        Ok(report) => {
+            report.print(cli.output);
+            ExitCode::SUCCESS
+        }
+        Err(err) => match err.downcast::<simulate::Simulated>() {
+            Ok(simulated) => {
+                let errors = match simulate::ProgramErrors::load(&cli.idl) {
+                    Ok(errors) => errors,
+                    Err(err) => {
+                        return ExitCode::from(output::report_error(err.as_ref(), cli.output))
+                    }
+                };
+                let failed = simulated.0.error.is_some();
+                simulated.0.report(&errors).print(cli.output);
+                if failed {
+                    ExitCode::from(output::SIMULATION_FAILED)
+                } else {
+                    ExitCode::SUCCESS
+                }
+            }
+            Err(err) => ExitCode::from(output::report_error(err.as_ref(), cli.output)),
+        },
+    }
+}
+
+async fn run(cli: &Cli) -> Result<Report, Box<dyn Error>> {
+    let config_path = AppConfig::config_path();
+    let mut config = AppConfig::load_config(&config_path)?;
+    if let Commands::SetConfig { .. } | Commands::Profile { .. } = cli.command {
+        return run_config_command(cli, &mut config, &config_path);
+    }
+
+    let settings = config.resolve(&overrides(cli))?;
+    let rpc_client = RpcClient::new_with_timeout_and_commitment(
+        settings.rpc_url.clone(),
+        settings.timeout,
+        settings.commitment,
+    );
+    let mut registry = WalletRegistry::load()?;
+    let mut contacts = AddressBook::load()?;
+    let options = SendOptions {
+        budget: ComputeBudget {
+            unit_limit: cli.compute_limit,
+            unit_price: cli.priority_fee,
+        },
+        dry_run: cli.dry_run,
+        yes: cli.yes,
+        lookup_tables: cli
+            .lookup_table
+            .iter()
+            .map(|table| contacts.pubkey(table))
+            .collect::<Result<_, _>>()?,
+    };
+    let global_wallet = cli
+        .wallet_file
+        .clone()
+        .or_else(|| settings.default_wallet.clone());
 
-    match &cli.command {
-        Commands::Create { file } => {
-            let keypair = Keypair::new();
-            let file_path = file.as_deref().unwrap_or(&cli.wallet_file);
-            save_keypair(&keypair, file_path)?;
-            println!("Wallet created and saved to: {}", file_path);
-            println!("Public key: {}", keypair.pubkey());
+    let report = match &cli.command {
+        Commands::Create {
+            file,
+            label,
+            plaintext,
+            mnemonic,
+            word_count,
+            passphrase,
+            derivation_path,
+            force,
+        } => {
+            let target = file
+                .as_deref()
+                .or(cli.wallet_file.as_deref())
+                .unwrap_or(DEFAULT_WALLET_FILE);
+            let (keypair, phrase, derivation) = if *mnemonic {
+                let phrase = mnemonic::generate_mnemonic(*word_count);
+                let seed_passphrase = if *passphrase {
+                    mnemonic::read_new_passphrase()?
+                } else {
+                    String::new()
+                };
+                let (keypair, info) =
+                    mnemonic::derive_keypair(&phrase, &seed_passphrase, derivation_path)?;
+                (keypair, Some(phrase), Some(info))
+            } else {
+                (Keypair::new(), None, None)
+            };
+            let file_path = store_new_wallet(
+                &mut registry,
+                target,
+                label.as_deref(),
+                &keypair,
+                *plaintext,
+                derivation.as_ref(),
+                *force,
+            )?;
+            let mut report = Report::new();
+            if let Some(phrase) = &phrase {
+                report = report
+                    .line("Seed phrase (write it down and keep it safe):")
+                    .line(phrase.phrase());
+            }
+            report = report
+                .line(format!("Wallet created and saved to: {}", file_path))
+                .line(format!("Public key: {}", keypair.pubkey()))
+                .field("path", &file_path)
+                .field("address", keypair.pubkey().to_string())
+                .field("seed_phrase", phrase.as_ref().map(|phrase| phrase.phrase()));
+            if let Some(info) = derivation {
+                report = report
+                    .line(format!("Derivation path: {}", info.path))
+                    .field("derivation_path", info.path);
+            }
+            report
+        }
+        Commands::Recover {
+            file,
+            label,
+            plaintext,
+            passphrase,
+            derivation_path,
+            force,
+        } => {
+            let target = file
+                .as_deref()
+                .or(cli.wallet_file.as_deref())
+                .unwrap_or(DEFAULT_WALLET_FILE);
+            let phrase = mnemonic::parse_mnemonic(&mnemonic::read_seed_phrase()?)?;
+            let seed_passphrase = if *passphrase {
+                mnemonic::read_passphrase()?
+            } else {
+                String::new()
+            };
+            let (keypair, info) =
+                mnemonic::derive_keypair(&phrase, &seed_passphrase, derivation_path)?;
+            let file_path = store_new_wallet(
+                &mut registry,
+                target,
+                label.as_deref(),
+                &keypair,
+                *plaintext,
+                Some(&info),
+                *force,
+            )?;
+            Report::new()
+                .line(format!("Wallet recovered and saved to: {}", file_path))
+                .line(format!("Public key: {}", keypair.pubkey()))
+                .line(format!("Derivation path: {}", info.path))
+                .field("path", &file_path)
+                .field("address", keypair.pubkey().to_string())
+                .field("derivation_path", &info.path)
+        }
+        Commands::Grind {
+            starts_with,
+            ends_with,
+            ignore_case,
+            threads,
+            file,
+            label,
+            plaintext,
+            mnemonic,
+            word_count,
+            force,
+        } => {
+            let pattern = grind::Pattern::new(starts_with, ends_with, *ignore_case)?;
+            let target = file
+                .as_deref()
+                .or(cli.wallet_file.as_deref())
+                .unwrap_or(DEFAULT_WALLET_FILE);
+            // Fail on an existing wallet before spending time grinding.
+            registry.new_wallet_path(target, *force)?;
+            let threads = match threads {
+                Some(threads) => (*threads).max(1),
+                None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            };
+            let word_count = if *mnemonic { Some(*word_count) } else { None };
+            let result = grind::grind(pattern, threads, word_count)?;
+            let file_path = store_new_wallet(
+                &mut registry,
+                target,
+                label.as_deref(),
+                &result.keypair,
+                *plaintext,
+                result.derivation.as_ref(),
+                *force,
+            )?;
+            let mut report = Report::new();
+            if let Some(phrase) = &result.mnemonic {
+                report = report
+                    .line("Seed phrase (write it down and keep it safe):")
+                    .line(phrase.phrase());
+            }
+            report
+                .line(format!("Wallet saved to: {}", file_path))
+                .line(format!("Public key: {}", result.keypair.pubkey()))
+                .field("path", &file_path)
+                .field("address", result.keypair.pubkey().to_string())
+                .field(
+                    "seed_phrase",
+                    result.mnemonic.as_ref().map(|phrase| phrase.phrase()),
+                )
+        }
+        Commands::Import {
+            input,
+            file,
+            label,
+            plaintext,
+            force,
+        } => {
+            let target = file
+                .as_deref()
+                .or(cli.wallet_file.as_deref())
+                .unwrap_or(DEFAULT_WALLET_FILE);
+            let (wallet, source) = match input {
+                Some(path) => (read_wallet_file(path)?, path.as_str()),
+                None => {
+                    let secret = rpassword::prompt_password("Secret key: ")?;
+                    (WalletFile::parse(&secret)?, "secret key")
+                }
+            };
+            let keypair = wallet.unlock(source)?;
+            let file_path = store_new_wallet(
+                &mut registry,
+                target,
+                label.as_deref(),
+                &keypair,
+                *plaintext,
+                wallet.derivation(),
+                *force,
+            )?;
+            Report::new()
+                .line(format!("Wallet imported and saved to: {}", file_path))
+                .line(format!("Public key: {}", keypair.pubkey()))
+                .field("path", &file_path)
+                .field("address", keypair.pubkey().to_string())
+        }
+        Commands::Export {
+            file,
+            format,
+            out_file,
+        } => {
+            let file_path = wallet_path(&registry, global_wallet.as_deref(), file.as_deref());
+            let keypair = read_keypair(&file_path)?;
+            let encoded = wallet::encode_keypair(&keypair, *format)?;
+            let report = Report::new().field("address", keypair.pubkey().to_string());
+            match out_file {
+                Some(path) => {
+                    wallet::write_secret_file(path, &encoded)?;
+                    report
+                        .line(format!("Wallet exported to: {}", path))
+                        .field("path", path)
+                }
+                None => report.line(&encoded).field("keypair", &encoded),
+            }
+        }
+        Commands::Encrypt { file } => {
+            let file_path = wallet_path(&registry, global_wallet.as_deref(), file.as_deref());
+            let wallet = read_wallet_file(&file_path)?;
+            let report = Report::new().field("path", &file_path);
+            if wallet.is_encrypted() {
+                return Ok(report
+                    .line(format!("Wallet is already encrypted: {}", file_path))
+                    .field("already_encrypted", true));
+            }
+            let keypair = read_keypair(&file_path)?;
+            let password = keystore::read_new_password()?;
+            save_keypair(&keypair, &file_path, Some(&password), wallet.derivation())?;
+            report
+                .line(format!("Wallet encrypted: {}", file_path))
+                .field("already_encrypted", false)
         }
         Commands::Address { wallet } => {
-            let pubkey = Pubkey::from_str(wallet)?;
-            println!("Address: {}", pubkey);
-        }
-        Commands::Airdrop { wallet, amount } => {
-            let pubkey = Pubkey::from_str(wallet)?;
-            let signature = request_airdrop(&rpc_client, &pubkey, *amount).await?;
-            println!("Airdrop successful: Signature {}", signature);
-        }
-        Commands::Transfer { from, to, amount } => {
-            let sender_keypair = match from {
-                Some(path) => read_keypair(path)?,
-                None => read_keypair(&cli.wallet_file)?,
+            let pubkey = wallet_pubkey(&registry, &contacts, wallet)?;
+            Report::new()
+                .line(format!("Address: {}", pubkey))
+                .field("address", pubkey.to_string())
+        }
+        Commands::Airdrop {
+            wallet,
+            amount,
+            lamports,
+        } => {
+            if options.dry_run {
+                return Err("Airdrops cannot be simulated with --dry-run".into());
+            }
+            let pubkey = wallet_pubkey(&registry, &contacts, wallet)?;
+            let lamports = amount.to_lamports(*lamports)?;
+            let receipt = transfer::request_airdrop(&rpc_client, &pubkey, lamports).await?;
+            Report::new()
+                .line(format!(
+                    "Airdrop successful: Signature {}",
+                    receipt.signature
+                ))
+                .field("address", pubkey.to_string())
+                .field("lamports", lamports)
+                .receipt(&receipt)
+        }
+        Commands::Transfer {
+            from,
+            to,
+            amount,
+            lamports,
+            unsigned,
+            encoding,
+            blockhash,
+            nonce,
+            nonce_authority,
+            allow_off_curve,
+        } => {
+            let receiver_pubkey = wallet_pubkey(&registry, &contacts, to)?;
+            check_recipient(&registry, &contacts, &receiver_pubkey, *allow_off_curve)?;
+            let nonce = nonce
+                .as_deref()
+                .map(|nonce| contacts.pubkey(nonce))
+                .transpose()?;
+            if let Some(output) = unsigned {
+                // Only the sender's address is needed, so a cold wallet can be
+                // given as a bare public key.
+                let sender = wallet_pubkey(
+                    &registry,
+                    &contacts,
+                    &wallet_path(&registry, global_wallet.as_deref(), from.as_deref()),
+                )?;
+                let authority = nonce_authority
+                    .as_deref()
+                    .map(|authority| wallet_pubkey(&registry, &contacts, authority))
+                    .transpose()?;
+                let source = blockhash_source(blockhash.as_deref(), nonce, authority, &sender)?;
+                let amount_lamports = transfer::transfer_lamports(
+                    &rpc_client,
+                    amount,
+                    *lamports,
+                    &sender,
+                    &receiver_pubkey,
+                    &source,
+                    &options.budget,
+                )
+                .await?;
+                let instruction = solana_sdk::system_instruction::transfer(
+                    &sender,
+                    &receiver_pubkey,
+                    amount_lamports,
+                );
+                let lookup_tables =
+                    alt::fetch_lookup_tables(&rpc_client, &options.lookup_tables).await?;
+                let transaction = transaction::build_transaction(
+                    &rpc_client,
+                    &[instruction],
+                    &sender,
+                    &source,
+                    &options.budget,
+                    &lookup_tables,
+                )
+                .await?;
+                fs::write(
+                    output,
+                    transaction::encode_transaction(&transaction, *encoding)?,
+                )?;
+                return Ok(Report::new()
+                    .line(format!("Unsigned transaction written to: {}", output))
+                    .field("path", output)
+                    .field("from", sender.to_string())
+                    .field("to", receiver_pubkey.to_string())
+                    .field("lamports", amount_lamports));
+            }
+
+            let sender_keypair = read_keypair(&wallet_path(
+                &registry,
+                global_wallet.as_deref(),
+                from.as_deref(),
+            ))?;
+            let authority_keypair = nonce_authority
+                .as_deref()
+                .map(|authority| read_keypair(&registry.resolve(authority)))
+                .transpose()?;
+            let source = blockhash_source(
+                blockhash.as_deref(),
+                nonce,
+                authority_keypair.as_ref().map(|keypair| keypair.pubkey()),
+                &sender_keypair.pubkey(),
+            )?;
+            let amount_lamports = transfer::transfer_lamports(
+                &rpc_client,
+                amount,
+                *lamports,
+                &sender_keypair.pubkey(),
+                &receiver_pubkey,
+                &source,
+                &options.budget,
+            )
+            .await?;
+            let receipt = transfer::transfer_funds(
+                &rpc_client,
+                &sender_keypair,
+                &receiver_pubkey,
+                amount_lamports,
+                &source,
+                authority_keypair.as_ref(),
+                &options,
+            )
+            .await?;
+            contacts.record_recipient(&receiver_pubkey)?;
+            Report::new()
+                .line(format!(
+                    "Transfer successful: Signature {}",
+                    receipt.signature
+                ))
+                .field("from", sender_keypair.pubkey().to_string())
+                .field("to", receiver_pubkey.to_string())
+                .field("lamports", amount_lamports)
+                .receipt(&receipt)
+        }
+        Commands::BatchTransfer {
+            file,
+            from,
+            lamports,
+            journal,
+            results,
+            retries,
+            allow_off_curve,
+        } => {
+            let payouts = batch::read_payouts(file, *lamports, |recipient| {
+                wallet_pubkey(&registry, &contacts, recipient)
+            })?;
+            let recipients: BTreeSet<Pubkey> =
+                payouts.iter().map(|payout| payout.recipient).collect();
+            for recipient in &recipients {
+                check_recipient(&registry, &contacts, recipient, *allow_off_curve)?;
+            }
+            let sender = read_keypair(&wallet_path(
+                &registry,
+                global_wallet.as_deref(),
+                from.as_deref(),
+            ))?;
+            let journal = journal
+                .clone()
+                .unwrap_or_else(|| format!("{}.journal", file));
+            if options.dry_run {
+                let simulations =
+                    batch::simulate_batches(&rpc_client, &sender, &payouts, &journal, &options)
+                        .await?;
+                let failed = simulations.iter().filter(|s| s.error.is_some()).count();
+                if failed > 0 {
+                    return Err(format!(
+                        "{} of {} batches failed in simulation",
+                        failed,
+                        simulations.len()
+                    )
+                    .into());
+                }
+                let fee: u64 = simulations.iter().map(|s| s.fee_lamports).sum();
+                return Ok(Report::new()
+                    .line(format!(
+                        "Dry run: {} transactions simulated, total fee {} SOL",
+                        simulations.len(),
+                        amount::format_sol(fee)
+                    ))
+                    .field("dry_run", true)
+                    .field("transactions", simulations.len())
+                    .field("fee_lamports", fee));
+            }
+            let results = results
+                .clone()
+                .unwrap_or_else(|| format!("{}.results.csv", file.trim_end_matches(".csv")));
+            let outcome =
+                batch::run_batch(&rpc_client, &sender, &payouts, &journal, &options, *retries)
+                    .await;
+            // Results are written even when the run stops early so the
+            // operator can see what was paid.
+            batch::write_results(&results, &payouts, &journal)?;
+            if let Err(err) = outcome {
+                eprintln!("Results written to: {}", results);
+                return Err(err);
+            }
+            for recipient in &recipients {
+                contacts.record_recipient(recipient)?;
+            }
+            Report::new()
+                .line(format!("Results written to: {}", results))
+                .field("results", &results)
+                .field("journal", &journal)
+        }
+        Commands::Nonce { command } => {
+            let default_wallet = wallet_path(&registry, global_wallet.as_deref(), None);
+            // Only commands that send unlock the wallet. Authority wallets
+            // other than the payer are unlocked on demand.
+            let payer = || read_keypair(&default_wallet);
+            let read_authority = |authority: &Option<String>, payer: &Keypair| match authority {
+                Some(wallet) => read_keypair(&registry.resolve(wallet)),
+                None => Ok(payer.insecure_clone()),
+            };
+            match command {
+                NonceCommand::Create {
+                    keypair,
+                    authority,
+                    amount,
+                    lamports,
+                } => {
+                    let payer = payer()?;
+                    let nonce_keypair = match keypair {
+                        Some(path) => read_keypair(&registry.resolve(path))?,
+                        None => Keypair::new(),
+                    };
+                    let authority = match authority {
+                        Some(authority) => wallet_pubkey(&registry, &contacts, authority)?,
+                        None => payer.pubkey(),
+                    };
+                    let extra_lamports = amount.to_lamports(*lamports)?;
+                    let receipt = nonce::create_nonce_account(
+                        &rpc_client,
+                        &payer,
+                        &nonce_keypair,
+                        &authority,
+                        extra_lamports,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!("Nonce account created: {}", nonce_keypair.pubkey()))
+                        .line(format!("Authority: {}", authority))
+                        .line(format!("Signature: {}", receipt.signature))
+                        .field("nonce_account", nonce_keypair.pubkey().to_string())
+                        .field("authority", authority.to_string())
+                        .receipt(&receipt)
+                }
+                NonceCommand::Show { nonce } => {
+                    let info = nonce::get_nonce_info(&rpc_client, &contacts.pubkey(nonce)?).await?;
+                    Report::new().line(info.to_string()).field("nonce", info)
+                }
+                NonceCommand::Advance { nonce, authority } => {
+                    let nonce = contacts.pubkey(nonce)?;
+                    let payer = payer()?;
+                    let authority = read_authority(authority, &payer)?;
+                    let receipt =
+                        nonce::advance_nonce(&rpc_client, &payer, &nonce, &authority, &options)
+                            .await?;
+                    let new_nonce = nonce::get_nonce_data(&rpc_client, &nonce)
+                        .await?
+                        .blockhash();
+                    Report::new()
+                        .line(format!("Nonce advanced: Signature {}", receipt.signature))
+                        .line(format!("New nonce: {}", new_nonce))
+                        .field("nonce_account", nonce.to_string())
+                        .field("nonce", new_nonce.to_string())
+                        .receipt(&receipt)
+                }
+                NonceCommand::Withdraw {
+                    nonce,
+                    to,
+                    amount,
+                    lamports,
+                    authority,
+                    allow_off_curve,
+                } => {
+                    let nonce = contacts.pubkey(nonce)?;
+                    let lamports = if amount.is_all() {
+                        rpc_client.get_balance(&nonce).await?
+                    } else {
+                        amount.to_lamports(*lamports)?
+                    };
+                    let to = wallet_pubkey(&registry, &contacts, to)?;
+                    check_recipient(&registry, &contacts, &to, *allow_off_curve)?;
+                    let payer = payer()?;
+                    let receipt = nonce::withdraw_from_nonce(
+                        &rpc_client,
+                        &payer,
+                        &nonce,
+                        &read_authority(authority, &payer)?,
+                        &to,
+                        lamports,
+                        &options,
+                    )
+                    .await?;
+                    contacts.record_recipient(&to)?;
+                    Report::new()
+                        .line(format!(
+                            "Withdrawal successful: Signature {}",
+                            receipt.signature
+                        ))
+                        .field("nonce_account", nonce.to_string())
+                        .field("to", to.to_string())
+                        .field("lamports", lamports)
+                        .receipt(&receipt)
+                }
+                NonceCommand::Authorize {
+                    nonce,
+                    new_authority,
+                    authority,
+                } => {
+                    let nonce = contacts.pubkey(nonce)?;
+                    let new_authority = wallet_pubkey(&registry, &contacts, new_authority)?;
+                    let payer = payer()?;
+                    let receipt = nonce::authorize_nonce(
+                        &rpc_client,
+                        &payer,
+                        &nonce,
+                        &read_authority(authority, &payer)?,
+                        &new_authority,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!(
+                            "Nonce authority set to {}: Signature {}",
+                            new_authority, receipt.signature
+                        ))
+                        .field("nonce_account", nonce.to_string())
+                        .field("authority", new_authority.to_string())
+                        .receipt(&receipt)
+                }
+            }
+        }
+        Commands::Alt { command } => {
+            let default_wallet = wallet_path(&registry, global_wallet.as_deref(), None);
+            // Only commands that send unlock the wallet.
+            let payer = || read_keypair(&default_wallet);
+            let read_authority = |authority: &Option<String>, payer: &Keypair| match authority {
+                Some(wallet) => read_keypair(&registry.resolve(wallet)),
+                None => Ok(payer.insecure_clone()),
             };
-            let receiver_pubkey = Pubkey::from_str(to)?;
-            let signature =
-                transfer_funds(&rpc_client, &sender_keypair, &receiver_pubkey, *amount)?;
-            println!("Transfer successful: Signature {}", signature);
+            match command {
+                AltCommand::Create { authority } => {
+                    let payer = payer()?;
+                    let authority = match authority {
+                        Some(authority) => wallet_pubkey(&registry, &contacts, authority)?,
+                        None => payer.pubkey(),
+                    };
+                    let (table, receipt) =
+                        alt::create_lookup_table(&rpc_client, &payer, &authority, &options).await?;
+                    Report::new()
+                        .line(format!("Lookup table created: {}", table))
+                        .line(format!("Authority: {}", authority))
+                        .line(format!("Signature: {}", receipt.signature))
+                        .field("lookup_table", table.to_string())
+                        .field("authority", authority.to_string())
+                        .receipt(&receipt)
+                }
+                AltCommand::Extend {
+                    table,
+                    addresses,
+                    authority,
+                } => {
+                    let payer = payer()?;
+                    let table = contacts.pubkey(table)?;
+                    let addresses = addresses
+                        .iter()
+                        .map(|address| wallet_pubkey(&registry, &contacts, address))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let (added, receipts) = alt::extend_lookup_table(
+                        &rpc_client,
+                        &payer,
+                        &table,
+                        &read_authority(authority, &payer)?,
+                        &addresses,
+                        &options,
+                    )
+                    .await?;
+                    let mut report = Report::new()
+                        .line(format!(
+                            "Added {} addresses to {} ({} already present)",
+                            added.len(),
+                            table,
+                            addresses.len() - added.len()
+                        ))
+                        .field("lookup_table", table.to_string())
+                        .field(
+                            "added",
+                            added.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                        )
+                        .field(
+                            "signatures",
+                            receipts
+                                .iter()
+                                .map(|r| r.signature.to_string())
+                                .collect::<Vec<_>>(),
+                        );
+                    if let [receipt] = receipts.as_slice() {
+                        report = report.line(format!("Signature: {}", receipt.signature));
+                    }
+                    report
+                }
+                AltCommand::Deactivate { table, authority } => {
+                    let payer = payer()?;
+                    let table = contacts.pubkey(table)?;
+                    let receipt = alt::deactivate_lookup_table(
+                        &rpc_client,
+                        &payer,
+                        &table,
+                        &read_authority(authority, &payer)?,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!(
+                            "Lookup table deactivated: Signature {}",
+                            receipt.signature
+                        ))
+                        .line("It can be closed once the deactivation slot is about 512 slots old")
+                        .field("lookup_table", table.to_string())
+                        .receipt(&receipt)
+                }
+                AltCommand::Close {
+                    table,
+                    to,
+                    authority,
+                } => {
+                    let payer = payer()?;
+                    let table = contacts.pubkey(table)?;
+                    let to = match to {
+                        Some(to) => wallet_pubkey(&registry, &contacts, to)?,
+                        None => payer.pubkey(),
+                    };
+                    let lamports = rpc_client.get_balance(&table).await?;
+                    let receipt = alt::close_lookup_table(
+                        &rpc_client,
+                        &payer,
+                        &table,
+                        &read_authority(authority, &payer)?,
+                        &to,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!(
+                            "Lookup table closed, {} SOL sent to {}: Signature {}",
+                            amount::format_sol(lamports),
+                            to,
+                            receipt.signature
+                        ))
+                        .field("lookup_table", table.to_string())
+                        .field("to", to.to_string())
+                        .field("lamports", lamports)
+                        .receipt(&receipt)
+                }
+                AltCommand::Show { table } => {
+                    let info =
+                        alt::get_lookup_table_info(&rpc_client, &contacts.pubkey(table)?).await?;
+                    Report::new()
+                        .line(info.to_string())
+                        .field("lookup_table", info)
+                }
+            }
+        }
+        Commands::Token { command } => {
+            let default_wallet = wallet_path(&registry, global_wallet.as_deref(), None);
+            // Read-only commands only need the owner's address.
+            let owner_pubkey = |owner: &Option<String>| match owner {
+                Some(owner) => wallet_pubkey(&registry, &contacts, owner),
+                None => wallet_pubkey(&registry, &contacts, &default_wallet),
+            };
+            match command {
+                TokenCommand::CreateMint {
+                    decimals,
+                    keypair,
+                    mint_authority,
+                    freeze_authority,
+                } => {
+                    let payer = read_keypair(&default_wallet)?;
+                    let mint_keypair = match keypair {
+                        Some(path) => read_keypair(&registry.resolve(path))?,
+                        None => Keypair::new(),
+                    };
+                    let mint_authority = match mint_authority {
+                        Some(authority) => wallet_pubkey(&registry, &contacts, authority)?,
+                        None => payer.pubkey(),
+                    };
+                    let freeze_authority = freeze_authority
+                        .as_deref()
+                        .map(|authority| wallet_pubkey(&registry, &contacts, authority))
+                        .transpose()?;
+                    let receipt = token::create_mint(
+                        &rpc_client,
+                        &payer,
+                        &mint_keypair,
+                        &mint_authority,
+                        freeze_authority.as_ref(),
+                        *decimals,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!("Mint created: {}", mint_keypair.pubkey()))
+                        .line(format!("Decimals: {}", decimals))
+                        .line(format!("Mint authority: {}", mint_authority))
+                        .line(format!("Signature: {}", receipt.signature))
+                        .field("mint", mint_keypair.pubkey().to_string())
+                        .field("decimals", decimals)
+                        .field("mint_authority", mint_authority.to_string())
+                        .field(
+                            "freeze_authority",
+                            freeze_authority.map(|authority| authority.to_string()),
+                        )
+                        .receipt(&receipt)
+                }
+                TokenCommand::CreateAccount { mint, owner } => {
+                    let payer = read_keypair(&default_wallet)?;
+                    let owner = match owner {
+                        Some(owner) => wallet_pubkey(&registry, &contacts, owner)?,
+                        None => payer.pubkey(),
+                    };
+                    let (account, receipt) = token::create_token_account(
+                        &rpc_client,
+                        &payer,
+                        &owner,
+                        &contacts.pubkey(mint)?,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!("Token account: {}", account))
+                        .line(format!("Signature: {}", receipt.signature))
+                        .field("account", account.to_string())
+                        .field("owner", owner.to_string())
+                        .receipt(&receipt)
+                }
+                TokenCommand::Mint {
+                    mint,
+                    amount,
+                    base_units,
+                    to,
+                } => {
+                    let authority = read_keypair(&default_wallet)?;
+                    let recipient = match to {
+                        Some(to) => wallet_pubkey(&registry, &contacts, to)?,
+                        None => authority.pubkey(),
+                    };
+                    let receipt = token::mint_tokens(
+                        &rpc_client,
+                        &authority,
+                        &contacts.pubkey(mint)?,
+                        &recipient,
+                        amount,
+                        *base_units,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!(
+                            "Minted {} tokens to {}: Signature {}",
+                            amount, recipient, receipt.signature
+                        ))
+                        .field("mint", mint)
+                        .field("to", recipient.to_string())
+                        .field("amount", amount.to_string())
+                        .receipt(&receipt)
+                }
+                TokenCommand::Transfer {
+                    mint,
+                    amount,
+                    base_units,
+                    to,
+                    fund_recipient,
+                    allow_off_curve,
+                } => {
+                    let sender = read_keypair(&default_wallet)?;
+                    let recipient = wallet_pubkey(&registry, &contacts, to)?;
+                    check_recipient(&registry, &contacts, &recipient, *allow_off_curve)?;
+                    let receipt = token::transfer_tokens(
+                        &rpc_client,
+                        &sender,
+                        &contacts.pubkey(mint)?,
+                        &recipient,
+                        amount,
+                        *base_units,
+                        *fund_recipient,
+                        &options,
+                    )
+                    .await?;
+                    contacts.record_recipient(&recipient)?;
+                    Report::new()
+                        .line(format!(
+                            "Transfer successful: Signature {}",
+                            receipt.signature
+                        ))
+                        .field("mint", mint)
+                        .field("to", recipient.to_string())
+                        .field("amount", amount.to_string())
+                        .receipt(&receipt)
+                }
+                TokenCommand::Balance { mint, owner } => {
+                    let owner = owner_pubkey(owner)?;
+                    let balance =
+                        token::get_token_balance(&rpc_client, &owner, &contacts.pubkey(mint)?)
+                            .await?;
+                    Report::new()
+                        .line(format!("Balance: {}", balance))
+                        .field("mint", mint)
+                        .field("owner", owner.to_string())
+                        .field("balance", balance)
+                }
+                TokenCommand::Accounts { owner } => {
+                    let owner = owner_pubkey(owner)?;
+                    let accounts = token::get_token_accounts(&rpc_client, &owner).await?;
+                    let text = if accounts.is_empty() {
+                        format!("No token accounts found for: {}", owner)
+                    } else {
+                        token::format_token_accounts(&accounts)
+                    };
+                    Report::new()
+                        .line(text)
+                        .field("owner", owner.to_string())
+                        .field("accounts", accounts)
+                }
+                TokenCommand::Close {
+                    mint,
+                    account,
+                    recipient,
+                } => {
+                    let owner = read_keypair(&default_wallet)?;
+                    let account = match account {
+                        Some(account) => contacts.pubkey(account)?,
+                        None => spl_associated_token_account::get_associated_token_address(
+                            &owner.pubkey(),
+                            &contacts.pubkey(mint)?,
+                        ),
+                    };
+                    let recipient = match recipient {
+                        Some(recipient) => wallet_pubkey(&registry, &contacts, recipient)?,
+                        None => owner.pubkey(),
+                    };
+                    let receipt = token::close_token_account(
+                        &rpc_client,
+                        &owner,
+                        &account,
+                        &recipient,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!("Token account closed: {}", account))
+                        .line(format!("Signature: {}", receipt.signature))
+                        .field("account", account.to_string())
+                        .field("recipient", recipient.to_string())
+                        .receipt(&receipt)
+                }
+            }
+        }
+        Commands::Stake { command } => {
+            let default_wallet = wallet_path(&registry, global_wallet.as_deref(), None);
+            // Only commands that send unlock the wallet.
+            let payer = || read_keypair(&default_wallet);
+            let read_authority = |authority: &Option<String>, payer: &Keypair| match authority {
+                Some(wallet) => read_keypair(&registry.resolve(wallet)),
+                None => Ok(payer.insecure_clone()),
+            };
+            match command {
+                StakeCommand::Create {
+                    amount,
+                    lamports,
+                    keypair,
+                    staker,
+                    withdrawer,
+                    validator,
+                } => {
+                    let payer = payer()?;
+                    let stake_keypair = match keypair {
+                        Some(path) => read_keypair(&registry.resolve(path))?,
+                        None => Keypair::new(),
+                    };
+                    let authority = |authority: &Option<String>| match authority {
+                        Some(authority) => wallet_pubkey(&registry, &contacts, authority),
+                        None => Ok(payer.pubkey()),
+                    };
+                    let authorized = solana_sdk::stake::state::Authorized {
+                        staker: authority(staker)?,
+                        withdrawer: authority(withdrawer)?,
+                    };
+                    let validator = validator
+                        .as_deref()
+                        .map(|validator| contacts.pubkey(validator))
+                        .transpose()?;
+                    let lamports = amount.to_lamports(*lamports)?;
+                    let receipt = stake::create_stake_account(
+                        &rpc_client,
+                        &payer,
+                        &stake_keypair,
+                        &authorized,
+                        validator.as_ref(),
+                        lamports,
+                        &options,
+                    )
+                    .await?;
+                    let mut report = Report::new()
+                        .line(format!("Stake account created: {}", stake_keypair.pubkey()))
+                        .line(format!("Stake authority: {}", authorized.staker))
+                        .line(format!("Withdraw authority: {}", authorized.withdrawer));
+                    if let Some(validator) = validator {
+                        report = report.line(format!("Delegated to: {}", validator));
+                    }
+                    report
+                        .line(format!("Signature: {}", receipt.signature))
+                        .field("stake_account", stake_keypair.pubkey().to_string())
+                        .field("lamports", lamports)
+                        .field("staker", authorized.staker.to_string())
+                        .field("withdrawer", authorized.withdrawer.to_string())
+                        .field("validator", validator.map(|v| v.to_string()))
+                        .receipt(&receipt)
+                }
+                StakeCommand::Delegate {
+                    stake,
+                    validator,
+                    authority,
+                } => {
+                    let payer = payer()?;
+                    let stake = contacts.pubkey(stake)?;
+                    let validator = contacts.pubkey(validator)?;
+                    let receipt = stake::delegate_stake(
+                        &rpc_client,
+                        &payer,
+                        &stake,
+                        &read_authority(authority, &payer)?,
+                        &validator,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!(
+                            "Stake delegated to {}: Signature {}",
+                            validator, receipt.signature
+                        ))
+                        .field("stake_account", stake.to_string())
+                        .field("validator", validator.to_string())
+                        .receipt(&receipt)
+                }
+                StakeCommand::Deactivate { stake, authority } => {
+                    let payer = payer()?;
+                    let stake = contacts.pubkey(stake)?;
+                    let receipt = stake::deactivate_stake(
+                        &rpc_client,
+                        &payer,
+                        &stake,
+                        &read_authority(authority, &payer)?,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!(
+                            "Stake deactivated: Signature {}",
+                            receipt.signature
+                        ))
+                        .field("stake_account", stake.to_string())
+                        .receipt(&receipt)
+                }
+                StakeCommand::Withdraw {
+                    stake,
+                    to,
+                    amount,
+                    lamports,
+                    authority,
+                    allow_off_curve,
+                } => {
+                    let payer = payer()?;
+                    let stake = contacts.pubkey(stake)?;
+                    let to = wallet_pubkey(&registry, &contacts, to)?;
+                    check_recipient(&registry, &contacts, &to, *allow_off_curve)?;
+                    let lamports = if amount.is_all() {
+                        rpc_client.get_balance(&stake).await?
+                    } else {
+                        amount.to_lamports(*lamports)?
+                    };
+                    let receipt = stake::withdraw_stake(
+                        &rpc_client,
+                        &payer,
+                        &stake,
+                        &read_authority(authority, &payer)?,
+                        &to,
+                        lamports,
+                        &options,
+                    )
+                    .await?;
+                    contacts.record_recipient(&to)?;
+                    Report::new()
+                        .line(format!(
+                            "Withdrawal successful: Signature {}",
+                            receipt.signature
+                        ))
+                        .field("stake_account", stake.to_string())
+                        .field("to", to.to_string())
+                        .field("lamports", lamports)
+                        .receipt(&receipt)
+                }
+                StakeCommand::Split {
+                    stake,
+                    amount,
+                    lamports,
+                    keypair,
+                    authority,
+                } => {
+                    let payer = payer()?;
+                    let stake = contacts.pubkey(stake)?;
+                    let split_keypair = match keypair {
+                        Some(path) => read_keypair(&registry.resolve(path))?,
+                        None => Keypair::new(),
+                    };
+                    let lamports = amount.to_lamports(*lamports)?;
+                    let receipt = stake::split_stake(
+                        &rpc_client,
+                        &payer,
+                        &stake,
+                        &read_authority(authority, &payer)?,
+                        &split_keypair,
+                        lamports,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!("Stake split into: {}", split_keypair.pubkey()))
+                        .line(format!("Signature: {}", receipt.signature))
+                        .field("stake_account", stake.to_string())
+                        .field("split_stake_account", split_keypair.pubkey().to_string())
+                        .field("lamports", lamports)
+                        .receipt(&receipt)
+                }
+                StakeCommand::Merge {
+                    destination,
+                    source,
+                    authority,
+                } => {
+                    let payer = payer()?;
+                    let destination = contacts.pubkey(destination)?;
+                    let source = contacts.pubkey(source)?;
+                    let receipt = stake::merge_stake(
+                        &rpc_client,
+                        &payer,
+                        &destination,
+                        &source,
+                        &read_authority(authority, &payer)?,
+                        &options,
+                    )
+                    .await?;
+                    Report::new()
+                        .line(format!(
+                            "Merged {} into {}: Signature {}",
+                            source, destination, receipt.signature
+                        ))
+                        .field("stake_account", destination.to_string())
+                        .field("merged", source.to_string())
+                        .receipt(&receipt)
+                }
+                StakeCommand::Show { stake, rewards } => {
+                    let info =
+                        stake::get_stake_info(&rpc_client, &contacts.pubkey(stake)?, *rewards)
+                            .await?;
+                    Report::new().line(info.to_string()).field("stake", info)
+                }
+                StakeCommand::Validators { limit, delinquent } => {
+                    let validators =
+                        stake::get_validators(&rpc_client, *limit, *delinquent).await?;
+                    Report::new()
+                        .line(stake::format_validators(&validators))
+                        .field("validators", validators)
+                }
+            }
+        }
+        Commands::Sign {
+            file,
+            signer,
+            out_file,
+        } => {
+            let data = fs::read_to_string(file)?;
+            let (mut transaction, encoding) = transaction::decode_transaction(&data)?;
+            // Lookup tables are only fetched for version 0 transactions that
+            // use them, so legacy transactions are still signed offline.
+            let account_keys = alt::resolve_account_keys(&rpc_client, &transaction.message)
+                .await
+                .map_err(|e| format!("Failed to resolve lookup table addresses: {}", e))?;
+            // Shown before any password prompt so the transaction can be
+            // reviewed first.
+            let summary = transaction::summarize_transaction(&transaction, &account_keys)?;
+            if cli.output == OutputFormat::Text {
+                println!("{}", summary);
+            } else {
+                eprintln!("{}", summary);
+            }
+
+            // Only wallets whose public key is a required signer are
+            // unlocked. The default wallet is optional when --signer is given,
+            // so signing for other wallets works without it.
+            let required = transaction::required_signers(&transaction);
+            let default_wallet = wallet_path(&registry, global_wallet.as_deref(), None);
+            let mut paths: Vec<String> = signer.iter().map(|w| registry.resolve(w)).collect();
+            if signer.is_empty() || wallet::expand_path(&default_wallet).exists() {
+                paths.insert(0, default_wallet);
+            }
+            let mut keypairs = Vec::new();
+            for path in &paths {
+                let address = read_wallet_file(path)?.public_key()?;
+                if required.contains(&address) {
+                    keypairs.push(read_keypair(path)?);
+                }
+            }
+            if keypairs.is_empty() {
+                return Err("None of the given wallets signs this transaction".into());
+            }
+            let signers: Vec<&Keypair> = keypairs.iter().collect();
+            transaction::sign_transaction(&mut transaction, &signers)
+                .map_err(|e| format!("Failed to sign transaction: {}", e))?;
+
+            let output = out_file.as_deref().unwrap_or(file);
+            fs::write(
+                output,
+                transaction::encode_transaction(&transaction, encoding)?,
+            )?;
+            let fully_signed = transaction::is_signed(&transaction);
+            let state = if fully_signed { "fully" } else { "partially" };
+            Report::new()
+                .line(format!(
+                    "Transaction {} signed and written to: {}",
+                    state, output
+                ))
+                .field("path", output)
+                .field("fully_signed", fully_signed)
+                .field(
+                    "transaction",
+                    transaction::summarize_transaction(&transaction, &account_keys)?,
+                )
+        }
+        Commands::SignMessage {
+            message,
+            message_file,
+        } => {
+            let message = offchain::read_message(message.as_deref(), message_file.as_deref())?;
+            let keypair = read_keypair(&wallet_path(&registry, global_wallet.as_deref(), None))?;
+            let (signature, format) = offchain::sign_message(&keypair, &message)?;
+            Report::new()
+                .line(format!("Format: {}", format))
+                .line(format!("Signer: {}", keypair.pubkey()))
+                .line(format!("Signature: {}", signature))
+                .field("format", format)
+                .field("signer", keypair.pubkey().to_string())
+                .field("signature", signature.to_string())
+        }
+        Commands::VerifyMessage {
+            message,
+            message_file,
+            signature,
+            signer,
+        } => {
+            let message = offchain::read_message(message.as_deref(), message_file.as_deref())?;
+            let signer = match signer {
+                Some(signer) => wallet_pubkey(&registry, &contacts, signer)?,
+                None => wallet_pubkey(
+                    &registry,
+                    &contacts,
+                    &wallet_path(&registry, global_wallet.as_deref(), None),
+                )?,
+            };
+            let signature = Signature::from_str(signature)?;
+            if !offchain::verify_message(&signer, &message, &signature)? {
+                return Err(format!("Signature is not valid for signer {}", signer).into());
+            }
+            Report::new()
+                .line(format!("Signature is valid for signer {}", signer))
+                .field("signer", signer.to_string())
+                .field("valid", true)
+        }
+        Commands::Broadcast { file } => {
+            let (transaction, _) = transaction::decode_transaction(&fs::read_to_string(file)?)?;
+            if !transaction::is_signed(&transaction) {
+                return Err("Transaction is missing signatures".into());
+            }
+            if !transaction.verify_with_results().iter().all(|valid| *valid) {
+                return Err("Transaction signatures do not match its message".into());
+            }
+            if options.dry_run {
+                let simulation = simulate::simulate(&rpc_client, &transaction).await?;
+                return Err(simulate::Simulated(simulation).into());
+            }
+            let preview = preview::preview(&rpc_client, &transaction).await?;
+            preview::confirm(&preview, options.yes)?;
+            let receipt = transaction::send_and_confirm(&rpc_client, &transaction, None).await?;
+            Report::new()
+                .line(format!(
+                    "Broadcast successful: Signature {}",
+                    receipt.signature
+                ))
+                .receipt(&receipt)
+        }
+        Commands::PayUrl { command } => match command {
+            PayUrlCommand::Create {
+                recipient,
+                amount,
+                spl_token,
+                reference,
+                label,
+                message,
+                memo,
+                no_qr,
+            } => {
+                if let Some(amount) = amount {
+                    if amount.is_all() {
+                        return Err("ALL is not valid in a payment request".into());
+                    }
+                    if spl_token.is_none() {
+                        amount.to_units(amount::SOL_DECIMALS)?;
+                    }
+                }
+                let mut references = reference
+                    .iter()
+                    .map(|reference| contacts.pubkey(reference))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // A fresh key per request is what lets pay-verify find the
+                // payment without matching on amounts.
+                if references.is_empty() {
+                    references.push(Keypair::new().pubkey());
+                }
+                let request = TransferRequest {
+                    recipient: wallet_pubkey(&registry, &contacts, recipient)?,
+                    amount: amount.clone(),
+                    spl_token: spl_token
+                        .as_deref()
+                        .map(|mint| contacts.pubkey(mint))
+                        .transpose()?,
+                    references,
+                    label: label.clone(),
+                    message: message.clone(),
+                    memo: memo.clone(),
+                };
+                let url = request.to_string();
+                let mut report = Report::new();
+                if !no_qr {
+                    report = report.line(pay::render_qr(&url)?);
+                }
+                report.line(url.clone()).field("url", url).field(
+                    "references",
+                    request
+                        .references
+                        .iter()
+                        .map(|reference| reference.to_string())
+                        .collect::<Vec<_>>(),
+                )
+            }
+            PayUrlCommand::Parse { request } => Report::new()
+                .line(request.describe())
+                .field("recipient", request.recipient.to_string())
+                .field(
+                    "amount",
+                    request.amount.as_ref().map(|amount| amount.to_string()),
+                )
+                .field("spl_token", request.spl_token.map(|mint| mint.to_string()))
+                .field(
+                    "references",
+                    request
+                        .references
+                        .iter()
+                        .map(|reference| reference.to_string())
+                        .collect::<Vec<_>>(),
+                )
+                .field("label", &request.label)
+                .field("message", &request.message)
+                .field("memo", &request.memo),
+        },
+        Commands::Pay {
+            request,
+            amount,
+            fund_recipient,
+            allow_off_curve,
+        } => {
+            let payer = read_keypair(&wallet_path(&registry, global_wallet.as_deref(), None))?;
+            check_recipient(&registry, &contacts, &request.recipient, *allow_off_curve)?;
+            eprintln!("{}", request.describe());
+            let receipt = pay::pay(
+                &rpc_client,
+                &payer,
+                request,
+                amount.as_ref(),
+                *fund_recipient,
+                &options,
+            )
+            .await?;
+            contacts.record_recipient(&request.recipient)?;
+            Report::new()
+                .line(format!("Payment sent: Signature {}", receipt.signature))
+                .field("from", payer.pubkey().to_string())
+                .field("to", request.recipient.to_string())
+                .field("url", request.to_string())
+                .receipt(&receipt)
+        }
+        Commands::PayVerify {
+            request,
+            reference,
+            recipient,
+            amount,
+            spl_token,
+            memo,
+        } => {
+            let request = match request {
+                Some(request) => request.clone(),
+                None => TransferRequest {
+                    recipient: wallet_pubkey(
+                        &registry,
+                        &contacts,
+                        recipient.as_deref().ok_or("--recipient is required")?,
+                    )?,
+                    amount: amount.clone(),
+                    spl_token: spl_token
+                        .as_deref()
+                        .map(|mint| contacts.pubkey(mint))
+                        .transpose()?,
+                    references: reference
+                        .iter()
+                        .map(|reference| contacts.pubkey(reference))
+                        .collect::<Result<_, _>>()?,
+                    label: None,
+                    message: None,
+                    memo: memo.clone(),
+                },
+            };
+            let reference = request
+                .references
+                .first()
+                .ok_or("The request has no reference to search for")?;
+            let signature = pay::find_reference(&rpc_client, reference).await?;
+            let payment = pay::validate_transfer(&rpc_client, &signature, &request).await?;
+            Report::new()
+                .line(payment.to_string())
+                .field("payment", payment)
+        }
+        Commands::History {
+            address,
+            limit,
+            before,
+            format,
+            export,
+        } => {
+            let address = match address {
+                Some(address) => wallet_pubkey(&registry, &contacts, address)?,
+                None => wallet_pubkey(
+                    &registry,
+                    &contacts,
+                    &wallet_path(&registry, global_wallet.as_deref(), None),
+                )?,
+            };
+            let before = before.as_deref().map(Signature::from_str).transpose()?;
+            let entries = history::get_history(&rpc_client, &address, *limit, before).await?;
+            let report = Report::new().field("address", address.to_string());
+            match export {
+                Some(path) => {
+                    let mut file = fs::File::create(path)?;
+                    history::write_history(&entries, *format, &mut file)?;
+                    report
+                        .line(format!(
+                            "{} transactions written to: {}",
+                            entries.len(),
+                            path
+                        ))
+                        .field("path", path)
+                        .field("count", entries.len())
+                }
+                None => {
+                    let mut text = Vec::new();
+                    history::write_history(&entries, *format, &mut text)?;
+                    let text = String::from_utf8(text)?;
+                    let report = report.field("transactions", entries);
+                    if text.is_empty() {
+                        report
+                    } else {
+                        report.line(text.trim_end())
+                    }
+                }
+            }
+        }
+        Commands::Pda {
+            program,
+            seed,
+            find,
+        } => {
+            let program = contacts.pubkey(program)?;
+            let seeds = seed
+                .iter()
+                .map(|seed| pda::Seed::parse(seed, |key| wallet_pubkey(&registry, &contacts, key)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let info = match find {
+                Some(address) => pda::find_seeds(&program, &seeds, &contacts.pubkey(address)?)?,
+                None => pda::derive(&program, &seeds)?,
+            };
+            Report::new().line(info.to_string()).field("pda", info)
+        }
+        Commands::Watch {
+            address,
+            logs,
+            count,
+        } => {
+            let address = wallet_pubkey(&registry, &contacts, address)?;
+            let events = watch::watch(
+                &rpc_client,
+                &settings.ws_url,
+                &address,
+                *logs,
+                *count,
+                cli.output,
+            )
+            .await?;
+            eprintln!("Stopped after {} updates", events);
+            Report::new()
         }
         Commands::Balance { wallet } => {
-            let pubkey = Pubkey::from_str(wallet)?;
-            let balance = rpc_client.get_balance(&pubkey)?;
-            println!("Balance: {} SOL", balance as f64 / LAMPORTS_PER_SOL as f64);
-        }
-        Commands::SetConfig { network } => {
-            let mut config = AppConfig::load_config("./config.json");
-            config.network = network.clone().into();
-            config.save_config("./config.json");
-            println!("Network set to: {}", config.network);
-        }
-        Commands::ListWallets { file } => {
-            let file_path = file.as_deref().unwrap_or(&cli.wallet_file);
-            let data = fs::read_to_string(file_path)?;
-            let wallet: WalletKeypair = serde_json::from_str(&data)?;
-            println!("Public key: {}", wallet.public_key);
+            let pubkey = wallet_pubkey(&registry, &contacts, wallet)?;
+            let balance = rpc_client.get_balance(&pubkey).await?;
+            Report::new()
+                .line(format!("Balance: {} SOL", amount::format_sol(balance)))
+                .field("address", pubkey.to_string())
+                .field("lamports", balance)
         }
-    }
+        Commands::SetConfig { .. } | Commands::Profile { .. } => {
+            unreachable!("handled by run_config_command")
+        }
+        Commands::ListWallets { file, balance } => {
+            let dir = match file {
+                Some(path) => wallet::expand_path(path),
+                None => registry.dir().clone(),
+            };
+            if dir.is_file() {
+                let address = read_wallet_file(&dir.to_string_lossy())?.public_key()?;
+                return Ok(Report::new()
+                    .line(format!("Public key: {}", address))
+                    .field("address", address.to_string()));
+            }
+            let registry = WalletRegistry::load_from(dir)?;
+            let rows = wallet_rows(&registry, &rpc_client, *balance).await?;
+            let text = if rows.is_empty() {
+                format!("No wallets saved in: {}", registry.dir().display())
+            } else {
+                format_wallet_table(&rows, *balance)
+            };
+            Report::new().line(text).field("wallets", rows)
+        }
+        Commands::Contacts { command } => match command {
+            ContactsCommand::Add {
+                name,
+                address,
+                label,
+            } => {
+                let address = wallet_pubkey(&registry, &contacts, address)?;
+                let name = contacts.add(name, &address, label.as_deref())?;
+                contacts.save()?;
+                if !address.is_on_curve() {
+                    eprintln!(
+                        "Note: {} is off the ed25519 curve (a program-derived address)",
+                        address
+                    );
+                }
+                Report::new()
+                    .line(format!("Contact added: @{} ({})", name, address))
+                    .field("name", name)
+                    .field("address", address.to_string())
+                    .field("on_curve", address.is_on_curve())
+            }
+            ContactsCommand::List => {
+                let text = if contacts.contacts.is_empty() {
+                    "No contacts saved".to_string()
+                } else {
+                    contacts::format_contacts(&contacts)
+                };
+                Report::new()
+                    .line(text)
+                    .field("contacts", &contacts.contacts)
+            }
+            ContactsCommand::Remove { name } => {
+                let contact = contacts.remove(name)?;
+                contacts.save()?;
+                Report::new()
+                    .line(format!(
+                        "Contact removed: @{}",
+                        name.trim_start_matches(contacts::ALIAS_PREFIX)
+                    ))
+                    .field("address", contact.address)
+            }
+        },
+        Commands::SetDefault { name } => {
+            registry.set_default(name)?;
+            registry.save()?;
+            Report::new()
+                .line(format!("Default wallet set to: {}", name))
+                .field("default_wallet", name)
+        }
+    };
 
-    Ok(())
+    Ok(report)
 }
@@ -0,0 +1,99 @@
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    derivation_path::DerivationPath, signature::Keypair,
+    signer::keypair::keypair_from_seed_and_derivation_path,
+};
+use std::{env, error::Error};
+
+/// BIP44 path used by Phantom and `solana-keygen` for the first account.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/501'/0'/0'";
+
+/// Environment variables read before falling back to an interactive prompt.
+pub const SEED_PHRASE_ENV: &str = "SCW_SEED_PHRASE";
+pub const PASSPHRASE_ENV: &str = "SCW_PASSPHRASE";
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum WordCount {
+    #[value(name = "12")]
+    Twelve,
+    #[value(name = "24")]
+    TwentyFour,
+}
+
+impl From<WordCount> for MnemonicType {
+    fn from(word_count: WordCount) -> Self {
+        match word_count {
+            WordCount::Twelve => MnemonicType::Words12,
+            WordCount::TwentyFour => MnemonicType::Words24,
+        }
+    }
+}
+
+/// How a keypair was derived from its seed phrase. The phrase itself is never
+/// stored; this only records what is needed to recover the same address.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DerivationInfo {
+    pub path: String,
+    pub word_count: usize,
+    pub passphrase: bool,
+}
+
+pub fn generate_mnemonic(word_count: WordCount) -> Mnemonic {
+    Mnemonic::new(word_count.into(), Language::English)
+}
+
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, Box<dyn Error>> {
+    let normalized = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    Mnemonic::from_phrase(&normalized, Language::English)
+        .map_err(|e| format!("Invalid seed phrase: {}", e).into())
+}
+
+/// Derives an ed25519 keypair from a mnemonic using SLIP-0010 along `path`.
+pub fn derive_keypair(
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+    path: &str,
+) -> Result<(Keypair, DerivationInfo), Box<dyn Error>> {
+    let derivation_path = DerivationPath::from_absolute_path_str(path)?;
+    let seed = Seed::new(mnemonic, passphrase);
+    let keypair = keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(derivation_path))?;
+    let info = DerivationInfo {
+        path: path.to_string(),
+        word_count: mnemonic.phrase().split_whitespace().count(),
+        passphrase: !passphrase.is_empty(),
+    };
+    Ok((keypair, info))
+}
+
+/// Reads the seed phrase from `SCW_SEED_PHRASE`, prompting if it is unset.
+pub fn read_seed_phrase() -> Result<String, Box<dyn Error>> {
+    if let Ok(phrase) = env::var(SEED_PHRASE_ENV) {
+        return Ok(phrase);
+    }
+    Ok(rpassword::prompt_password("Seed phrase: ")?)
+}
+
+/// Reads the optional BIP39 passphrase from `SCW_PASSPHRASE`, prompting if it is unset.
+pub fn read_passphrase() -> Result<String, Box<dyn Error>> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    Ok(rpassword::prompt_password("Seed passphrase: ")?)
+}
+
+/// Reads the BIP39 passphrase for a new seed phrase, asking twice when
+/// prompting. A mistyped passphrase would derive a wallet the seed phrase
+/// can never restore.
+pub fn read_new_passphrase() -> Result<String, Box<dyn Error>> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password("New seed passphrase: ")?;
+    let confirmation = rpassword::prompt_password("Confirm seed passphrase: ")?;
+    if passphrase != confirmation {
+        return Err("Passphrases do not match".into());
+    }
+    Ok(passphrase)
+}
@@ -0,0 +1,1099 @@
+use crate::{alt, rpc::Rpc};
+use async_trait::async_trait;
+use solana_account_decoder::{parse_token::UiTokenAmount, UiAccount, UiAccountEncoding};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_response::{
+        RpcConfirmedTransactionStatusWithSignature, RpcInflationReward,
+        RpcSimulateTransactionResult, RpcVoteAccountStatus,
+    },
+};
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    clock::{Epoch, Slot, MAX_PROCESSING_AGE},
+    commitment_config::CommitmentConfig,
+    compute_budget,
+    entrypoint::{ProgramResult, SUCCESS},
+    epoch_info::EpochInfo,
+    epoch_schedule::EpochSchedule,
+    hash::Hash,
+    instruction::InstructionError,
+    message::{v0::LoadedAddresses, VersionedMessage},
+    nonce::{
+        state::{Data, DurableNonce, Versions},
+        State as NonceState,
+    },
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Signature,
+    system_instruction::{SystemError, SystemInstruction},
+    system_program, sysvar,
+    sysvar::stake_history::{self, StakeHistory},
+    transaction::{TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::{
+    ConfirmedTransactionWithStatusMeta, EncodedConfirmedTransactionWithStatusMeta,
+    TransactionConfirmationStatus, TransactionStatus, TransactionStatusMeta,
+    TransactionTokenBalance, TransactionWithStatusMeta, UiTransactionEncoding,
+    VersionedTransactionWithStatusMeta,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, Once},
+};
+
+/// Fee charged for each signature, as on the public clusters.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Slots in each epoch of the mock cluster, the smallest the runtime allows.
+pub const SLOTS_PER_EPOCH: u64 = 32;
+
+/// Compute units reported per instruction by simulations.
+const UNITS_PER_INSTRUCTION: u64 = 150;
+
+thread_local! {
+    /// Lines logged by the program running on this thread.
+    static PROGRAM_LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+static INSTALL_STUBS: Once = Once::new();
+
+/// Syscalls for the on-chain programs the mock runs natively: their logs go
+/// into the transaction's logs and the rent sysvar is the default one.
+struct Syscalls;
+
+impl SyscallStubs for Syscalls {
+    fn sol_log(&self, message: &str) {
+        PROGRAM_LOGS.with(|logs| logs.borrow_mut().push(format!("Program log: {}", message)));
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // `Rent::get` passes a pointer to a `Rent` on its stack.
+        unsafe { std::ptr::write(var_addr as *mut Rent, Rent::default()) };
+        SUCCESS
+    }
+}
+
+fn take_program_logs() -> Vec<String> {
+    PROGRAM_LOGS.with(|logs| std::mem::take(&mut *logs.borrow_mut()))
+}
+
+fn epoch_schedule() -> EpochSchedule {
+    EpochSchedule::custom(SLOTS_PER_EPOCH, SLOTS_PER_EPOCH, false)
+}
+
+/// A transaction that landed, kept for `get_signatures_for_address` and
+/// `get_transaction`.
+struct Landed {
+    signature: Signature,
+    slot: u64,
+    transaction: VersionedTransaction,
+    account_keys: Vec<Pubkey>,
+    meta: TransactionStatusMeta,
+}
+
+#[derive(Default)]
+struct Ledger {
+    accounts: HashMap<Pubkey, Account>,
+    statuses: HashMap<Signature, TransactionStatus>,
+    /// Blockhashes that can still be used, with their last valid block height.
+    blockhashes: HashMap<Hash, u64>,
+    latest_blockhash: Hash,
+    slot: u64,
+    block_height: u64,
+    prioritization_fees: Vec<u64>,
+    vote_accounts: Option<RpcVoteAccountStatus>,
+    inflation_rewards: HashMap<Epoch, HashMap<Pubkey, RpcInflationReward>>,
+    /// Epochs before this one have been pruned, so their rewards are gone.
+    first_available_epoch: Epoch,
+    drop_transactions: bool,
+    /// Transactions still to drop before landing them again.
+    drop_next: usize,
+    land_at_expiry: bool,
+    /// Accepted transactions waiting for `land_at_expiry` to land them.
+    pending: Vec<VersionedTransaction>,
+    sent: Vec<VersionedTransaction>,
+    landed: Vec<Landed>,
+}
+
+/// Result of executing a transaction against a copy of the accounts.
+struct Execution {
+    accounts: HashMap<Pubkey, Account>,
+    account_keys: Vec<Pubkey>,
+    fee: u64,
+    logs: Vec<String>,
+    units_consumed: u64,
+}
+
+/// An account passed to an instruction.
+struct InstructionAccount {
+    pubkey: Pubkey,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+impl Ledger {
+    fn new_blockhash(&mut self) {
+        self.latest_blockhash = Hash::new_unique();
+        self.blockhashes.insert(
+            self.latest_blockhash,
+            self.block_height + MAX_PROCESSING_AGE as u64,
+        );
+    }
+
+    /// Records a finalized transaction in a new slot.
+    fn finalize(&mut self, signature: Signature) {
+        self.slot += 1;
+        self.block_height += 1;
+        self.statuses.insert(
+            signature,
+            TransactionStatus {
+                slot: self.slot,
+                confirmations: None,
+                status: Ok(()),
+                err: None,
+                confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+            },
+        );
+        self.new_blockhash();
+    }
+
+    /// Applies an execution of `transaction` and records it with the
+    /// metadata an RPC node returns for it.
+    fn land(&mut self, transaction: &VersionedTransaction, execution: Execution) {
+        let keys = execution.account_keys;
+        let message = &transaction.message;
+        let static_keys = message.static_account_keys().len();
+        let loaded_writable: usize = message
+            .address_table_lookups()
+            .unwrap_or_default()
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len())
+            .sum();
+        let meta = TransactionStatusMeta {
+            status: Ok(()),
+            fee: execution.fee,
+            pre_balances: keys.iter().map(|key| self.lamports(key)).collect(),
+            post_balances: keys
+                .iter()
+                .map(|key| execution.accounts.get(key).map_or(0, |a| a.lamports))
+                .collect(),
+            inner_instructions: Some(Vec::new()),
+            log_messages: Some(execution.logs),
+            pre_token_balances: Some(token_balances(&self.accounts, &keys)),
+            post_token_balances: Some(token_balances(&execution.accounts, &keys)),
+            rewards: Some(Vec::new()),
+            loaded_addresses: LoadedAddresses {
+                writable: keys[static_keys..static_keys + loaded_writable].to_vec(),
+                readonly: keys[static_keys + loaded_writable..].to_vec(),
+            },
+            return_data: None,
+            compute_units_consumed: Some(execution.units_consumed),
+        };
+        self.accounts = execution.accounts;
+        let signature = transaction.signatures[0];
+        self.finalize(signature);
+        self.landed.push(Landed {
+            signature,
+            slot: self.slot,
+            transaction: transaction.clone(),
+            account_keys: keys,
+            meta,
+        });
+    }
+
+    /// Blocks past the expiry of every blockhash handed out so far.
+    fn expired_height(&self) -> u64 {
+        self.blockhashes.values().max().copied().unwrap_or(0) + 1
+    }
+
+    /// Lands the pending transactions and moves past the expiry of their
+    /// blockhashes in the same step.
+    fn land_pending(&mut self) {
+        let expired_height = self.expired_height();
+        for transaction in std::mem::take(&mut self.pending) {
+            if let Ok(execution) = self.execute(&transaction) {
+                self.land(&transaction, execution);
+            }
+        }
+        self.block_height = self.block_height.max(expired_height);
+        self.new_blockhash();
+    }
+
+    fn lamports(&self, pubkey: &Pubkey) -> u64 {
+        self.accounts
+            .get(pubkey)
+            .map_or(0, |account| account.lamports)
+    }
+
+    fn epoch(&self) -> Epoch {
+        epoch_schedule().get_epoch(self.slot)
+    }
+
+    /// Whether `message` uses an unexpired blockhash, or the durable nonce
+    /// of the account its first instruction advances.
+    fn has_valid_blockhash(&self, message: &VersionedMessage, account_keys: &[Pubkey]) -> bool {
+        let blockhash = message.recent_blockhash();
+        if let Some(last_valid) = self.blockhashes.get(blockhash) {
+            return *last_valid >= self.block_height;
+        }
+        let Some(instruction) = message.instructions().first() else {
+            return false;
+        };
+        let advances_nonce = account_keys[instruction.program_id_index as usize]
+            == system_program::id()
+            && matches!(
+                limited_deserialize(&instruction.data),
+                Ok(SystemInstruction::AdvanceNonceAccount)
+            );
+        advances_nonce
+            && instruction
+                .accounts
+                .first()
+                .and_then(|&index| self.accounts.get(&account_keys[index as usize]))
+                .and_then(|account| nonce_data(account).ok())
+                .is_some_and(|data| data.blockhash() == *blockhash)
+    }
+
+    /// Runs `transaction` the way the runtime would, for the system, compute
+    /// budget, SPL Token, memo and associated token account instructions the
+    /// wallet builds. Addresses are loaded from lookup tables stored in the ledger,
+    /// fees are charged to the fee payer and nothing changes if any
+    /// instruction fails.
+    fn execute(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Execution, (TransactionError, Vec<String>)> {
+        let message = &transaction.message;
+        if transaction.sanitize().is_err() {
+            return Err((TransactionError::SanitizeFailure, Vec::new()));
+        }
+        if !transaction.verify_with_results().iter().all(|valid| *valid) {
+            return Err((TransactionError::SignatureFailure, Vec::new()));
+        }
+        let account_keys = self
+            .account_keys(message)
+            .map_err(|err| (err, Vec::new()))?;
+        if !self.has_valid_blockhash(message, &account_keys) {
+            return Err((TransactionError::BlockhashNotFound, Vec::new()));
+        }
+
+        let mut accounts = self.accounts.clone();
+        let fee = LAMPORTS_PER_SIGNATURE * message.header().num_required_signatures as u64;
+        let payer = accounts
+            .get_mut(&account_keys[0])
+            .ok_or((TransactionError::AccountNotFound, Vec::new()))?;
+        payer.lamports = payer
+            .lamports
+            .checked_sub(fee)
+            .ok_or((TransactionError::InsufficientFundsForFee, Vec::new()))?;
+
+        let mut logs = Vec::new();
+        let mut units_consumed = 0;
+        for (index, instruction) in message.instructions().iter().enumerate() {
+            let program_id = account_keys[instruction.program_id_index as usize];
+            logs.push(format!("Program {} invoke [1]", program_id));
+            units_consumed += UNITS_PER_INSTRUCTION;
+            let keys: Vec<InstructionAccount> = instruction
+                .accounts
+                .iter()
+                .map(|&i| InstructionAccount {
+                    pubkey: account_keys[i as usize],
+                    is_signer: message.is_signer(i as usize),
+                    is_writable: message.is_maybe_writable(i as usize, None),
+                })
+                .collect();
+            let data = &instruction.data;
+            let result = if program_id == compute_budget::id() {
+                Ok(())
+            } else if program_id == system_program::id() {
+                execute_system_instruction(&mut accounts, &keys, data, &self.latest_blockhash)
+            } else if program_id == spl_token::id() {
+                execute_program(
+                    &mut accounts,
+                    &program_id,
+                    &keys,
+                    data,
+                    spl_token::processor::Processor::process,
+                )
+            } else if program_id == spl_memo::id() {
+                execute_program(
+                    &mut accounts,
+                    &program_id,
+                    &keys,
+                    data,
+                    spl_memo::processor::process_instruction,
+                )
+            } else if program_id == spl_associated_token_account::id() {
+                execute_associated_token_instruction(&mut accounts, &keys, data)
+            } else {
+                Err(InstructionError::UnsupportedProgramId)
+            };
+            logs.extend(take_program_logs());
+            if let Err(err) = result {
+                logs.push(format!("Program {} failed: {}", program_id, err));
+                return Err((TransactionError::InstructionError(index as u8, err), logs));
+            }
+            logs.push(format!("Program {} success", program_id));
+        }
+        // Accounts left without lamports are removed, as closed accounts are.
+        accounts.retain(|_, account| account.lamports > 0);
+        Ok(Execution {
+            accounts,
+            account_keys,
+            fee,
+            logs,
+            units_consumed,
+        })
+    }
+
+    /// Every account key of `message`, loading addresses from the lookup
+    /// tables in the ledger.
+    fn account_keys(&self, message: &VersionedMessage) -> Result<Vec<Pubkey>, TransactionError> {
+        let tables = message
+            .address_table_lookups()
+            .unwrap_or_default()
+            .iter()
+            .map(|lookup| {
+                self.accounts
+                    .get(&lookup.account_key)
+                    .and_then(|account| alt::decode_lookup_table(&lookup.account_key, account).ok())
+                    .ok_or(TransactionError::AddressLookupTableNotFound)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        alt::account_keys(message, &tables)
+    }
+}
+
+/// Balances of the SPL Token accounts among `keys`, as transaction metadata
+/// reports them.
+fn token_balances(
+    accounts: &HashMap<Pubkey, Account>,
+    keys: &[Pubkey],
+) -> Vec<TransactionTokenBalance> {
+    keys.iter()
+        .enumerate()
+        .filter_map(|(index, key)| {
+            let account = accounts.get(key)?;
+            if account.owner != spl_token::id() {
+                return None;
+            }
+            let token_account = TokenAccount::unpack(&account.data).ok()?;
+            let mint = Mint::unpack(&accounts.get(&token_account.mint)?.data).ok()?;
+            Some(TransactionTokenBalance {
+                account_index: index as u8,
+                mint: token_account.mint.to_string(),
+                ui_token_amount: UiTokenAmount {
+                    ui_amount: Some(spl_token::amount_to_ui_amount(
+                        token_account.amount,
+                        mint.decimals,
+                    )),
+                    decimals: mint.decimals,
+                    amount: token_account.amount.to_string(),
+                    ui_amount_string: spl_token::amount_to_ui_amount_string_trimmed(
+                        token_account.amount,
+                        mint.decimals,
+                    ),
+                },
+                owner: token_account.owner.to_string(),
+                program_id: spl_token::id().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn nonce_data(account: &Account) -> Result<Data, InstructionError> {
+    if account.owner != system_program::id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+    let versions: Versions =
+        bincode::deserialize(&account.data).map_err(|_| InstructionError::InvalidAccountData)?;
+    match versions.state() {
+        NonceState::Initialized(data) => Ok(data.clone()),
+        NonceState::Uninitialized => Err(InstructionError::InvalidAccountData),
+    }
+}
+
+fn set_nonce_state(account: &mut Account, state: NonceState) -> Result<(), InstructionError> {
+    let data = bincode::serialize(&Versions::new(state))
+        .map_err(|_| InstructionError::InvalidAccountData)?;
+    account.data = data;
+    Ok(())
+}
+
+fn custom_system_error(err: SystemError) -> InstructionError {
+    InstructionError::Custom(err as u32)
+}
+
+/// Runs the system program instructions behind transfers, account creation
+/// and durable nonces. `blockhash` is the one a new durable nonce derives
+/// from.
+fn execute_system_instruction(
+    accounts: &mut HashMap<Pubkey, Account>,
+    keys: &[InstructionAccount],
+    data: &[u8],
+    blockhash: &Hash,
+) -> Result<(), InstructionError> {
+    let account = |index: usize| {
+        keys.get(index)
+            .ok_or(InstructionError::NotEnoughAccountKeys)
+    };
+    let signed = |pubkey: &Pubkey| {
+        keys.iter()
+            .any(|key| key.pubkey == *pubkey && key.is_signer)
+    };
+    let debit = |accounts: &mut HashMap<Pubkey, Account>, from: &Pubkey, lamports: u64| {
+        let from_account = accounts.entry(*from).or_insert_with(system_account);
+        from_account.lamports = from_account
+            .lamports
+            .checked_sub(lamports)
+            .ok_or(custom_system_error(SystemError::ResultWithNegativeLamports))?;
+        Ok::<_, InstructionError>(())
+    };
+    let durable_nonce = DurableNonce::from_blockhash(blockhash);
+    let rent = Rent::default();
+
+    match limited_deserialize(data)? {
+        SystemInstruction::Transfer { lamports } => {
+            let (from, to) = (account(0)?, account(1)?);
+            if !from.is_signer {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            debit(accounts, &from.pubkey, lamports)?;
+            accounts
+                .entry(to.pubkey)
+                .or_insert_with(system_account)
+                .lamports += lamports;
+        }
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let (from, to) = (account(0)?, account(1)?);
+            if !from.is_signer || !to.is_signer {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            let existing = accounts.get(&to.pubkey);
+            if existing.is_some_and(|a| a.lamports > 0 || a.owner != system_program::id()) {
+                return Err(custom_system_error(SystemError::AccountAlreadyInUse));
+            }
+            debit(accounts, &from.pubkey, lamports)?;
+            accounts.insert(to.pubkey, Account::new(lamports, space as usize, &owner));
+        }
+        SystemInstruction::Assign { owner } => {
+            let target = account(0)?;
+            if !target.is_signer {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            let target = accounts.entry(target.pubkey).or_insert_with(system_account);
+            if target.owner != system_program::id() {
+                return Err(InstructionError::ModifiedProgramId);
+            }
+            target.owner = owner;
+        }
+        SystemInstruction::InitializeNonceAccount(authority) => {
+            let nonce = accounts
+                .get_mut(&account(0)?.pubkey)
+                .ok_or(InstructionError::InvalidAccountData)?;
+            if nonce.owner != system_program::id() || nonce.data.len() != NonceState::size() {
+                return Err(InstructionError::InvalidAccountData);
+            }
+            if nonce_data(nonce).is_ok() {
+                return Err(InstructionError::InvalidAccountData);
+            }
+            if nonce.lamports < rent.minimum_balance(NonceState::size()) {
+                return Err(InstructionError::InsufficientFunds);
+            }
+            let data = Data::new(authority, durable_nonce, LAMPORTS_PER_SIGNATURE);
+            set_nonce_state(nonce, NonceState::Initialized(data))?;
+        }
+        SystemInstruction::AdvanceNonceAccount => {
+            let nonce = accounts
+                .get_mut(&account(0)?.pubkey)
+                .ok_or(InstructionError::InvalidAccountData)?;
+            let data = nonce_data(nonce)?;
+            if !signed(&data.authority) {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            if data.durable_nonce == durable_nonce {
+                return Err(custom_system_error(SystemError::NonceBlockhashNotExpired));
+            }
+            let data = Data::new(data.authority, durable_nonce, LAMPORTS_PER_SIGNATURE);
+            set_nonce_state(nonce, NonceState::Initialized(data))?;
+        }
+        SystemInstruction::WithdrawNonceAccount(lamports) => {
+            let (nonce_key, to) = (account(0)?.pubkey, account(1)?.pubkey);
+            let nonce = accounts
+                .get_mut(&nonce_key)
+                .ok_or(InstructionError::InvalidAccountData)?;
+            let data = nonce_data(nonce)?;
+            if !signed(&data.authority) {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            if lamports == nonce.lamports {
+                if data.durable_nonce == durable_nonce {
+                    return Err(custom_system_error(SystemError::NonceBlockhashNotExpired));
+                }
+                set_nonce_state(nonce, NonceState::Uninitialized)?;
+            } else if nonce.lamports.saturating_sub(lamports)
+                < rent.minimum_balance(nonce.data.len())
+            {
+                return Err(InstructionError::InsufficientFunds);
+            }
+            debit(accounts, &nonce_key, lamports)?;
+            accounts.entry(to).or_insert_with(system_account).lamports += lamports;
+        }
+        SystemInstruction::AuthorizeNonceAccount(new_authority) => {
+            let nonce = accounts
+                .get_mut(&account(0)?.pubkey)
+                .ok_or(InstructionError::InvalidAccountData)?;
+            let data = nonce_data(nonce)?;
+            if !signed(&data.authority) {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            let data = Data::new(
+                new_authority,
+                data.durable_nonce,
+                data.get_lamports_per_signature(),
+            );
+            set_nonce_state(nonce, NonceState::Initialized(data))?;
+        }
+        _ => return Err(InstructionError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+/// Creates the associated token account of a wallet, which the real program
+/// does through cross-program calls the mock cannot make.
+fn execute_associated_token_instruction(
+    accounts: &mut HashMap<Pubkey, Account>,
+    keys: &[InstructionAccount],
+    data: &[u8],
+) -> Result<(), InstructionError> {
+    let idempotent = match data {
+        [] | [0] => false,
+        [1] => true,
+        _ => return Err(InstructionError::InvalidInstructionData),
+    };
+    let [payer, address, wallet, mint, ..] = keys else {
+        return Err(InstructionError::NotEnoughAccountKeys);
+    };
+    if address.pubkey != get_associated_token_address(&wallet.pubkey, &mint.pubkey) {
+        return Err(InstructionError::InvalidSeeds);
+    }
+    if !payer.is_signer {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    let mint_account = accounts
+        .get(&mint.pubkey)
+        .filter(|account| account.owner == spl_token::id())
+        .ok_or(InstructionError::IncorrectProgramId)?;
+    Mint::unpack(&mint_account.data).map_err(|_| InstructionError::InvalidAccountData)?;
+
+    if let Some(existing) = accounts
+        .get(&address.pubkey)
+        .filter(|account| account.owner != system_program::id())
+    {
+        let token_account = TokenAccount::unpack(&existing.data)
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+        if !idempotent {
+            return Err(custom_system_error(SystemError::AccountAlreadyInUse));
+        }
+        if token_account.owner != wallet.pubkey || token_account.mint != mint.pubkey {
+            return Err(InstructionError::IllegalOwner);
+        }
+        return Ok(());
+    }
+
+    let rent = Rent::default().minimum_balance(TokenAccount::LEN);
+    let existing = accounts.get(&address.pubkey).map_or(0, |a| a.lamports);
+    let payer_account = accounts
+        .get_mut(&payer.pubkey)
+        .ok_or(InstructionError::InsufficientFunds)?;
+    payer_account.lamports = payer_account
+        .lamports
+        .checked_sub(rent.saturating_sub(existing))
+        .ok_or(InstructionError::InsufficientFunds)?;
+    let lamports = existing.max(rent);
+    let is_native = mint.pubkey == spl_token::native_mint::id();
+    let token_account = TokenAccount {
+        mint: mint.pubkey,
+        owner: wallet.pubkey,
+        amount: if is_native { lamports - rent } else { 0 },
+        state: AccountState::Initialized,
+        is_native: is_native.then_some(rent).into(),
+        ..TokenAccount::default()
+    };
+    let mut account = Account::new(lamports, TokenAccount::LEN, &spl_token::id());
+    TokenAccount::pack(token_account, &mut account.data)
+        .map_err(|_| InstructionError::InvalidAccountData)?;
+    accounts.insert(address.pubkey, account);
+    Ok(())
+}
+
+/// Runs an on-chain program's processor natively over the accounts of one
+/// instruction and writes the accounts back.
+fn execute_program(
+    accounts: &mut HashMap<Pubkey, Account>,
+    program_id: &Pubkey,
+    keys: &[InstructionAccount],
+    data: &[u8],
+    process: fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult,
+) -> Result<(), InstructionError> {
+    let mut loaded: Vec<(Pubkey, Account)> = Vec::new();
+    for key in keys {
+        if !loaded.iter().any(|(pubkey, _)| *pubkey == key.pubkey) {
+            let account = accounts
+                .get(&key.pubkey)
+                .cloned()
+                .unwrap_or_else(system_account);
+            loaded.push((key.pubkey, account));
+        }
+    }
+    let result = {
+        let infos: Vec<AccountInfo> = loaded
+            .iter_mut()
+            .map(|(pubkey, account)| {
+                let flags = keys.iter().filter(|key| key.pubkey == *pubkey);
+                let is_signer = flags.clone().any(|key| key.is_signer);
+                let is_writable = flags.clone().any(|key| key.is_writable);
+                let Account {
+                    lamports,
+                    data,
+                    owner,
+                    executable,
+                    rent_epoch,
+                } = account;
+                AccountInfo::new(
+                    pubkey,
+                    is_signer,
+                    is_writable,
+                    lamports,
+                    data,
+                    owner,
+                    *executable,
+                    *rent_epoch,
+                )
+            })
+            .collect();
+        // Duplicate keys share one `AccountInfo`, as in the runtime.
+        let instruction_infos: Vec<AccountInfo> = keys
+            .iter()
+            .filter_map(|key| infos.iter().find(|info| *info.key == key.pubkey).cloned())
+            .collect();
+        process(program_id, &instruction_infos, data)
+    };
+    result.map_err(|err| InstructionError::from(u64::from(err)))?;
+    for (pubkey, account) in loaded {
+        if accounts.contains_key(&pubkey) || account.lamports > 0 {
+            accounts.insert(pubkey, account);
+        }
+    }
+    Ok(())
+}
+
+fn system_account() -> Account {
+    Account::new(0, 0, &system_program::id())
+}
+
+fn custom_error(message: String) -> ClientError {
+    ClientError::from(ClientErrorKind::Custom(message))
+}
+
+/// An in-memory stand-in for a cluster. It keeps balances, executes the
+/// system, SPL Token, memo and associated token account instructions the
+/// wallet builds, charges signature fees and finalizes every transaction in its own
+/// slot, so commands built on `Rpc` can be tested without a network.
+/// Landed transactions are kept with their metadata for the history calls.
+/// Version 0 transactions load their addresses from lookup tables put in the
+/// ledger with `set_account`. Other programs, such as the stake and lookup
+/// table programs, are rejected. Rent is only checked where those programs
+/// check it, although the rent-exempt minimum is reported as on the public
+/// clusters.
+pub struct MockRpc {
+    ledger: Mutex<Ledger>,
+}
+
+impl Default for MockRpc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockRpc {
+    pub fn new() -> Self {
+        INSTALL_STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Syscalls));
+        });
+        let mut ledger = Ledger::default();
+        ledger.new_blockhash();
+        let history = Account::new_data(
+            Rent::default().minimum_balance(0),
+            &StakeHistory::default(),
+            &sysvar::id(),
+        )
+        .expect("empty stake history serializes");
+        ledger.accounts.insert(stake_history::id(), history);
+        MockRpc {
+            ledger: Mutex::new(ledger),
+        }
+    }
+
+    fn ledger(&self) -> MutexGuard<'_, Ledger> {
+        self.ledger.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Credits `lamports` to `pubkey`, creating the account if needed.
+    pub fn fund(&self, pubkey: &Pubkey, lamports: u64) {
+        self.ledger()
+            .accounts
+            .entry(*pubkey)
+            .or_insert_with(system_account)
+            .lamports += lamports;
+    }
+
+    pub fn set_account(&self, pubkey: &Pubkey, account: Account) {
+        self.ledger().accounts.insert(*pubkey, account);
+    }
+
+    pub fn balance(&self, pubkey: &Pubkey) -> u64 {
+        self.ledger().lamports(pubkey)
+    }
+
+    pub fn set_prioritization_fees(&self, fees: Vec<u64>) {
+        self.ledger().prioritization_fees = fees;
+    }
+
+    pub fn set_vote_accounts(&self, vote_accounts: RpcVoteAccountStatus) {
+        self.ledger().vote_accounts = Some(vote_accounts);
+    }
+
+    /// Records an inflation reward credited to `address` in `reward.epoch`.
+    pub fn set_inflation_reward(&self, address: &Pubkey, reward: RpcInflationReward) {
+        self.ledger()
+            .inflation_rewards
+            .entry(reward.epoch)
+            .or_default()
+            .insert(*address, reward);
+    }
+
+    /// Rewards of epochs before `epoch` are no longer available, as on a node
+    /// that pruned its ledger: asking for them fails.
+    pub fn prune_epochs_before(&self, epoch: Epoch) {
+        self.ledger().first_available_epoch = epoch;
+    }
+
+    /// Moves the cluster to the first slot of `epoch`. Blockhashes handed
+    /// out before expire on the way.
+    pub fn warp_to_epoch(&self, epoch: Epoch) {
+        let mut ledger = self.ledger();
+        let slot = epoch_schedule().get_first_slot_in_epoch(epoch);
+        if slot > ledger.slot {
+            ledger.block_height += slot - ledger.slot;
+            ledger.slot = slot;
+            ledger.new_blockhash();
+        }
+    }
+
+    /// From now on transactions are accepted but never land, and the cluster
+    /// moves past the expiry of every blockhash handed out so far and on to
+    /// a fresh one.
+    pub fn drop_transactions(&self) {
+        self.ledger().drop_transactions = true;
+    }
+
+    /// Like `drop_transactions`, for the next transaction only.
+    pub fn drop_next_transaction(&self) {
+        self.ledger().drop_next += 1;
+    }
+
+    /// From now on transactions are accepted but only land the next time the
+    /// block height is read, which also moves the cluster past the expiry of
+    /// their blockhash. This is the race where a transaction lands between
+    /// a status poll and the expiry check.
+    pub fn land_at_expiry(&self) {
+        self.ledger().land_at_expiry = true;
+    }
+
+    /// Every transaction accepted by `send_transaction`, in order.
+    pub fn sent_transactions(&self) -> Vec<VersionedTransaction> {
+        self.ledger().sent.clone()
+    }
+}
+
+#[async_trait]
+impl Rpc for MockRpc {
+    fn commitment(&self) -> CommitmentConfig {
+        CommitmentConfig::confirmed()
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+        Ok(self.ledger().lamports(pubkey))
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        Ok(self.ledger().accounts.get(pubkey).cloned())
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<Vec<Option<Account>>> {
+        let ledger = self.ledger();
+        Ok(pubkeys
+            .iter()
+            .map(|pubkey| ledger.accounts.get(pubkey).cloned())
+            .collect())
+    }
+
+    async fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> ClientResult<Signature> {
+        self.fund(pubkey, lamports);
+        let signature = Signature::new_unique();
+        self.ledger().finalize(signature);
+        Ok(signature)
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)> {
+        let ledger = self.ledger();
+        let blockhash = ledger.latest_blockhash;
+        Ok((blockhash, ledger.blockhashes[&blockhash]))
+    }
+
+    async fn get_slot(&self) -> ClientResult<Slot> {
+        Ok(self.ledger().slot)
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        let mut ledger = self.ledger();
+        if !ledger.pending.is_empty() {
+            ledger.land_pending();
+        }
+        Ok(ledger.block_height)
+    }
+
+    async fn get_fee_for_message(&self, message: &VersionedMessage) -> ClientResult<u64> {
+        Ok(LAMPORTS_PER_SIGNATURE * message.header().num_required_signatures as u64)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        Ok(Rent::default().minimum_balance(data_len))
+    }
+
+    async fn get_recent_prioritization_fees(
+        &self,
+        _addresses: &[Pubkey],
+    ) -> ClientResult<Vec<u64>> {
+        Ok(self.ledger().prioritization_fees.clone())
+    }
+
+    /// Executes the transaction right away. Failures are returned the way a
+    /// preflight check reports them, leaving the ledger untouched.
+    async fn send_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> ClientResult<Signature> {
+        let mut ledger = self.ledger();
+        let execution = ledger.execute(transaction).map_err(|(err, _)| err)?;
+        let signature = *transaction
+            .signatures
+            .first()
+            .ok_or_else(|| custom_error("Transaction has no signatures".to_string()))?;
+        ledger.sent.push(transaction.clone());
+        if ledger.drop_transactions || ledger.drop_next > 0 {
+            ledger.drop_next = ledger.drop_next.saturating_sub(1);
+            ledger.block_height = ledger.expired_height();
+            ledger.new_blockhash();
+            return Ok(signature);
+        }
+        if ledger.land_at_expiry {
+            ledger.pending.push(transaction.clone());
+            return Ok(signature);
+        }
+        ledger.land(transaction, execution);
+        Ok(signature)
+    }
+
+    async fn get_signature_status(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<Option<TransactionStatus>> {
+        Ok(self.ledger().statuses.get(signature).cloned())
+    }
+
+    async fn simulate_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+        accounts: &[Pubkey],
+    ) -> ClientResult<RpcSimulateTransactionResult> {
+        let ledger = self.ledger();
+        let (err, logs, accounts, units_consumed) = match ledger.execute(transaction) {
+            Ok(execution) => {
+                let accounts = accounts
+                    .iter()
+                    .map(|pubkey| {
+                        execution.accounts.get(pubkey).map(|account| {
+                            UiAccount::encode(
+                                pubkey,
+                                account,
+                                UiAccountEncoding::Base64,
+                                None,
+                                None,
+                            )
+                        })
+                    })
+                    .collect();
+                (
+                    None,
+                    execution.logs,
+                    Some(accounts),
+                    Some(execution.units_consumed),
+                )
+            }
+            Err((err, logs)) => (Some(err), logs, None, None),
+        };
+        Ok(RpcSimulateTransactionResult {
+            err,
+            logs: Some(logs),
+            accounts,
+            units_consumed,
+            return_data: None,
+            inner_instructions: None,
+            replacement_blockhash: None,
+        })
+    }
+
+    async fn get_epoch_info(&self) -> ClientResult<EpochInfo> {
+        let ledger = self.ledger();
+        let (epoch, slot_index) = epoch_schedule().get_epoch_and_slot_index(ledger.slot);
+        Ok(EpochInfo {
+            epoch,
+            slot_index,
+            slots_in_epoch: SLOTS_PER_EPOCH,
+            absolute_slot: ledger.slot,
+            block_height: ledger.block_height,
+            transaction_count: Some(ledger.landed.len() as u64),
+        })
+    }
+
+    async fn get_epoch_schedule(&self) -> ClientResult<EpochSchedule> {
+        Ok(epoch_schedule())
+    }
+
+    /// One lamport, as on the clusters where the 1 SOL minimum is not active.
+    async fn get_stake_minimum_delegation(&self) -> ClientResult<u64> {
+        Ok(1)
+    }
+
+    async fn get_vote_accounts(&self) -> ClientResult<RpcVoteAccountStatus> {
+        Ok(self
+            .ledger()
+            .vote_accounts
+            .clone()
+            .unwrap_or(RpcVoteAccountStatus {
+                current: Vec::new(),
+                delinquent: Vec::new(),
+            }))
+    }
+
+    async fn get_inflation_reward(
+        &self,
+        addresses: &[Pubkey],
+        epoch: Epoch,
+    ) -> ClientResult<Vec<Option<RpcInflationReward>>> {
+        let ledger = self.ledger();
+        if epoch >= ledger.epoch() {
+            return Err(custom_error(format!("Epoch {} has not ended", epoch)));
+        }
+        if epoch < ledger.first_available_epoch {
+            return Err(custom_error(format!(
+                "Block not available for epoch {}",
+                epoch
+            )));
+        }
+        let rewards = ledger.inflation_rewards.get(&epoch);
+        Ok(addresses
+            .iter()
+            .map(|address| rewards.and_then(|rewards| rewards.get(address)).cloned())
+            .collect())
+    }
+
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        program_id: &Pubkey,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        let ledger = self.ledger();
+        let mut accounts: Vec<(Pubkey, Account)> = ledger
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.owner == *program_id)
+            .filter(|(_, account)| {
+                TokenAccount::unpack(&account.data).is_ok_and(|token| token.owner == *owner)
+            })
+            .map(|(pubkey, account)| (*pubkey, account.clone()))
+            .collect();
+        accounts.sort_by_key(|(pubkey, _)| *pubkey);
+        Ok(accounts)
+    }
+
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let ledger = self.ledger();
+        let newest_first = ledger.landed.iter().rev();
+        let start = match before {
+            Some(before) => match newest_first.clone().position(|l| l.signature == before) {
+                Some(position) => position + 1,
+                None => return Ok(Vec::new()),
+            },
+            None => 0,
+        };
+        Ok(newest_first
+            .skip(start)
+            .filter(|landed| landed.account_keys.contains(address))
+            .take(limit)
+            .map(|landed| RpcConfirmedTransactionStatusWithSignature {
+                signature: landed.signature.to_string(),
+                slot: landed.slot,
+                err: None,
+                memo: None,
+                block_time: None,
+                confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+            })
+            .collect())
+    }
+
+    async fn get_transaction(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        let ledger = self.ledger();
+        let landed = ledger
+            .landed
+            .iter()
+            .find(|landed| landed.signature == *signature)
+            .ok_or_else(|| custom_error(format!("Transaction {} not found", signature)))?;
+        let confirmed = ConfirmedTransactionWithStatusMeta {
+            slot: landed.slot,
+            tx_with_meta: TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta {
+                transaction: landed.transaction.clone(),
+                meta: landed.meta.clone(),
+            }),
+            block_time: None,
+        };
+        confirmed
+            .encode(UiTransactionEncoding::JsonParsed, Some(0))
+            .map_err(|e| custom_error(e.to_string()))
+    }
+}
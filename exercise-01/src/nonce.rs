@@ -0,0 +1,150 @@
+use crate::{
+    amount::format_sol,
+    rpc::Rpc,
+    transaction::{self, BlockhashSource, Receipt, SendOptions},
+};
+use serde::Serialize;
+use solana_client::nonblocking::nonce_utils;
+use solana_sdk::{
+    nonce::state::{Data, State},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+};
+use std::{error::Error, fmt};
+
+/// Fetches and decodes an initialized nonce account.
+pub async fn get_nonce_data(rpc_client: &dyn Rpc, nonce: &Pubkey) -> Result<Data, Box<dyn Error>> {
+    let account = rpc_client
+        .get_account(nonce)
+        .await?
+        .ok_or_else(|| format!("Nonce account {} not found", nonce))?;
+    Ok(nonce_utils::data_from_account(&account)?)
+}
+
+/// Creates a nonce account funded by `payer`. The account is rent exempt plus
+/// `extra_lamports`.
+pub async fn create_nonce_account(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    nonce_keypair: &Keypair,
+    authority: &Pubkey,
+    extra_lamports: u64,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let rent = rpc_client
+        .get_minimum_balance_for_rent_exemption(State::size())
+        .await?;
+    let instructions = system_instruction::create_nonce_account(
+        &payer.pubkey(),
+        &nonce_keypair.pubkey(),
+        authority,
+        rent + extra_lamports,
+    );
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[payer, nonce_keypair],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+pub async fn advance_nonce(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    nonce: &Pubkey,
+    authority: &Keypair,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction = system_instruction::advance_nonce_account(nonce, &authority.pubkey());
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, authority],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+pub async fn withdraw_from_nonce(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    nonce: &Pubkey,
+    authority: &Keypair,
+    to: &Pubkey,
+    lamports: u64,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction =
+        system_instruction::withdraw_nonce_account(nonce, &authority.pubkey(), to, lamports);
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, authority],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+pub async fn authorize_nonce(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    nonce: &Pubkey,
+    authority: &Keypair,
+    new_authority: &Pubkey,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction =
+        system_instruction::authorize_nonce_account(nonce, &authority.pubkey(), new_authority);
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, authority],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// State of a nonce account as shown by `nonce show`.
+#[derive(Serialize, Debug)]
+pub struct NonceInfo {
+    pub nonce_account: String,
+    pub lamports: u64,
+    pub authority: String,
+    pub nonce: String,
+    pub lamports_per_signature: u64,
+}
+
+impl fmt::Display for NonceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Nonce account: {}", self.nonce_account)?;
+        writeln!(f, "Balance: {} SOL", format_sol(self.lamports))?;
+        writeln!(f, "Authority: {}", self.authority)?;
+        writeln!(f, "Nonce: {}", self.nonce)?;
+        write!(
+            f,
+            "Fee: {} lamports per signature",
+            self.lamports_per_signature
+        )
+    }
+}
+
+pub async fn get_nonce_info(
+    rpc_client: &dyn Rpc,
+    nonce: &Pubkey,
+) -> Result<NonceInfo, Box<dyn Error>> {
+    let data = get_nonce_data(rpc_client, nonce).await?;
+    Ok(NonceInfo {
+        nonce_account: nonce.to_string(),
+        lamports: rpc_client.get_balance(nonce).await?,
+        authority: data.authority.to_string(),
+        nonce: data.blockhash().to_string(),
+        lamports_per_signature: data.get_lamports_per_signature(),
+    })
+}
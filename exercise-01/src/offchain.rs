@@ -0,0 +1,49 @@
+use solana_sdk::{
+    offchain_message::{MessageFormat, OffchainMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+};
+use std::{error::Error, fs};
+
+/// Only version 0 of the off-chain message format is defined.
+const MESSAGE_VERSION: u8 = 0;
+
+/// Takes the message from the command line, or reads it verbatim from a file.
+pub fn read_message(text: Option<&str>, file: Option<&str>) -> Result<Vec<u8>, Box<dyn Error>> {
+    match (text, file) {
+        (Some(text), None) => Ok(text.as_bytes().to_vec()),
+        (None, Some(path)) => fs::read(path).map_err(|e| {
+            eprintln!("Failed to read message file '{}': {}", path, e);
+            e.into()
+        }),
+        _ => Err("Give either a message or --message-file".into()),
+    }
+}
+
+fn format_name(format: MessageFormat) -> &'static str {
+    match format {
+        MessageFormat::RestrictedAscii => "restricted ASCII",
+        MessageFormat::LimitedUtf8 => "limited UTF-8",
+        MessageFormat::ExtendedUtf8 => "extended UTF-8",
+    }
+}
+
+/// Signs `message` wrapped in the off-chain message header (signing domain,
+/// version and format), so the signature can never be a valid transaction.
+/// Returns the signature and the name of the message format used.
+pub fn sign_message(
+    keypair: &Keypair,
+    message: &[u8],
+) -> Result<(Signature, &'static str), Box<dyn Error>> {
+    let offchain = OffchainMessage::new(MESSAGE_VERSION, message)?;
+    let format = format_name(offchain.get_format());
+    Ok((offchain.sign(keypair)?, format))
+}
+
+pub fn verify_message(
+    signer: &Pubkey,
+    message: &[u8],
+    signature: &Signature,
+) -> Result<bool, Box<dyn Error>> {
+    Ok(OffchainMessage::new(MESSAGE_VERSION, message)?.verify(signer, signature)?)
+}
@@ -0,0 +1,131 @@
+use crate::transaction::{ConfirmationError, Receipt};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use solana_client::client_error::ClientError;
+use solana_sdk::{hash::ParseHashError, pubkey::ParsePubkeyError, signature::ParseSignatureError};
+use std::{error::Error, fmt, io};
+
+/// Formats for the result of a command, selected with `--output`.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    JsonCompact,
+}
+
+/// What a command prints on success: lines for `text` output and an object
+/// for the JSON formats. Progress and prompts go to stderr instead, so stdout
+/// holds nothing but the result.
+#[derive(Default)]
+pub struct Report {
+    lines: Vec<String>,
+    fields: Map<String, Value>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a line to the text output.
+    pub fn line(mut self, line: impl Into<String>) -> Self {
+        self.lines.push(line.into());
+        self
+    }
+
+    /// Adds a field to the JSON output.
+    pub fn field(mut self, key: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or(Value::Null);
+        self.fields.insert(key.to_string(), value);
+        self
+    }
+
+    /// Adds the signature and slot of a confirmed transaction.
+    pub fn receipt(self, receipt: &Receipt) -> Self {
+        self.field("signature", receipt.signature.to_string())
+            .field("slot", receipt.slot)
+    }
+
+    /// Prints the report. An empty report prints nothing, which is how
+    /// streaming commands that `emit` as they go end.
+    pub fn print(self, format: OutputFormat) {
+        if self.lines.is_empty() && self.fields.is_empty() {
+            return;
+        }
+        match format {
+            OutputFormat::Text => {
+                for line in self.lines {
+                    println!("{}", line);
+                }
+            }
+            OutputFormat::Json | OutputFormat::JsonCompact => {
+                print_json(&Value::Object(self.fields), format)
+            }
+        }
+    }
+}
+
+/// Prints one item of a streaming command as soon as it happens: its text,
+/// or in the JSON formats one compact object per line.
+pub fn emit(item: &(impl Serialize + fmt::Display), format: OutputFormat) {
+    match format {
+        OutputFormat::Text => println!("{}", item),
+        OutputFormat::Json | OutputFormat::JsonCompact => {
+            let value = serde_json::to_value(item).unwrap_or(Value::Null);
+            print_json(&value, OutputFormat::JsonCompact)
+        }
+    }
+}
+
+fn print_json(value: &Value, format: OutputFormat) {
+    let text = if format == OutputFormat::JsonCompact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    };
+    println!("{}", text.unwrap_or_default());
+}
+
+/// Exit status of a `--dry-run` whose simulation failed.
+pub const SIMULATION_FAILED: u8 = 8;
+
+/// Machine-readable code and process exit status for an error.
+fn classify(err: &(dyn Error + 'static)) -> (&'static str, u8) {
+    if let Some(err) = err.downcast_ref::<ConfirmationError>() {
+        return match err {
+            ConfirmationError::Failed(..) => ("transaction_failed", 4),
+            ConfirmationError::Expired(_) => ("transaction_expired", 5),
+            ConfirmationError::Timeout(..) => ("confirmation_timeout", 6),
+        };
+    }
+    if let Some(err) = err.downcast_ref::<ClientError>() {
+        if err.get_transaction_error().is_some() {
+            return ("transaction_failed", 4);
+        }
+        return ("rpc_error", 3);
+    }
+    if err.is::<io::Error>() {
+        return ("io_error", 7);
+    }
+    if err.is::<ParsePubkeyError>() || err.is::<ParseSignatureError>() || err.is::<ParseHashError>()
+    {
+        return ("invalid_argument", 2);
+    }
+    ("error", 1)
+}
+
+/// Prints `err` in the selected format and returns the exit status for it.
+pub fn report_error(err: &(dyn Error + 'static), format: OutputFormat) -> u8 {
+    let (code, status) = classify(err);
+    if format == OutputFormat::Text {
+        eprintln!("Error: {}", err);
+    } else {
+        print_json(
+            &json!({ "error": { "code": code, "message": err.to_string() } }),
+            format,
+        );
+    }
+    status
+}
@@ -0,0 +1,438 @@
+use crate::{
+    amount::{format_units, Amount, SOL_DECIMALS},
+    rpc::Rpc,
+    token,
+    transaction::{self, BlockhashSource, Receipt, SendOptions},
+};
+use chrono::DateTime;
+use qrcode::{render::unicode::Dense1x2, QrCode};
+use serde::Serialize;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedTransaction, UiInstruction, UiMessage,
+    UiParsedInstruction, UiTransactionTokenBalance,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::instruction as token_instruction;
+use std::{error::Error, fmt, str::FromStr};
+use url::{form_urlencoded, Url};
+
+/// URL scheme of Solana Pay requests.
+const SCHEME: &str = "solana";
+
+/// Largest page `getSignaturesForAddress` returns.
+const PAGE_SIZE: usize = 1000;
+
+/// A Solana Pay transfer request, as encoded in a `solana:` URL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferRequest {
+    pub recipient: Pubkey,
+    /// In SOL, or in tokens of `spl_token`. Wallets ask for it when missing.
+    pub amount: Option<Amount>,
+    pub spl_token: Option<Pubkey>,
+    /// Keys attached to the transfer so the payment can be found later.
+    pub references: Vec<Pubkey>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+    /// Recorded on chain by an SPL Memo instruction.
+    pub memo: Option<String>,
+}
+
+impl fmt::Display for TransferRequest {
+    /// Formats the request as a `solana:` URL. Parameters are form encoded,
+    /// as the reference implementation does.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        if let Some(amount) = &self.amount {
+            query.append_pair("amount", &amount.to_string());
+        }
+        if let Some(mint) = &self.spl_token {
+            query.append_pair("spl-token", &mint.to_string());
+        }
+        for reference in &self.references {
+            query.append_pair("reference", &reference.to_string());
+        }
+        for (key, value) in [
+            ("label", &self.label),
+            ("message", &self.message),
+            ("memo", &self.memo),
+        ] {
+            if let Some(value) = value {
+                query.append_pair(key, value);
+            }
+        }
+        let query = query.finish();
+        write!(f, "{}:{}", SCHEME, self.recipient)?;
+        if !query.is_empty() {
+            write!(f, "?{}", query)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TransferRequest {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let url = Url::parse(value.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+        if url.scheme() != SCHEME {
+            return Err(format!(
+                "Not a Solana Pay URL: expected the {}: scheme",
+                SCHEME
+            ));
+        }
+        let recipient = url.path();
+        if recipient.starts_with("http") {
+            return Err("Transaction requests are not supported, only transfer requests".into());
+        }
+        let mut request = TransferRequest {
+            recipient: Pubkey::from_str(recipient)
+                .map_err(|_| format!("Invalid recipient '{}'", recipient))?,
+            amount: None,
+            spl_token: None,
+            references: Vec::new(),
+            label: None,
+            message: None,
+            memo: None,
+        };
+        for (key, value) in url.query_pairs() {
+            let duplicate = || format!("Parameter '{}' appears more than once", key);
+            match key.as_ref() {
+                "amount" => {
+                    // The spec asks for a plain decimal with a leading zero
+                    // below one, such as 0.5.
+                    let amount = match Amount::from_str(&value) {
+                        Ok(amount @ Amount::Decimal { .. }) if !value.starts_with('.') => amount,
+                        _ => return Err(format!("Invalid amount '{}'", value)),
+                    };
+                    if request.amount.replace(amount).is_some() {
+                        return Err(duplicate());
+                    }
+                }
+                "spl-token" => {
+                    let mint = Pubkey::from_str(&value)
+                        .map_err(|_| format!("Invalid spl-token '{}'", value))?;
+                    if request.spl_token.replace(mint).is_some() {
+                        return Err(duplicate());
+                    }
+                }
+                "reference" => request.references.push(
+                    Pubkey::from_str(&value)
+                        .map_err(|_| format!("Invalid reference '{}'", value))?,
+                ),
+                "label" | "message" | "memo" => {
+                    let field = match key.as_ref() {
+                        "label" => &mut request.label,
+                        "message" => &mut request.message,
+                        _ => &mut request.memo,
+                    };
+                    if field.replace(value.into_owned()).is_some() {
+                        return Err(duplicate());
+                    }
+                }
+                // Unknown parameters are ignored so newer URLs still parse.
+                _ => {}
+            }
+        }
+        Ok(request)
+    }
+}
+
+impl TransferRequest {
+    /// Describes the request for review before paying.
+    pub fn describe(&self) -> String {
+        let mut lines = vec![format!("Recipient: {}", self.recipient)];
+        match (&self.amount, &self.spl_token) {
+            (Some(amount), Some(mint)) => lines.push(format!("Amount: {} of {}", amount, mint)),
+            (Some(amount), None) => lines.push(format!("Amount: {} SOL", amount)),
+            (None, Some(mint)) => lines.push(format!("Token: {}", mint)),
+            (None, None) => {}
+        }
+        for (name, value) in [
+            ("Label", &self.label),
+            ("Message", &self.message),
+            ("Memo", &self.memo),
+        ] {
+            if let Some(value) = value {
+                lines.push(format!("{}: {}", name, value));
+            }
+        }
+        for reference in &self.references {
+            lines.push(format!("Reference: {}", reference));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Renders `text` as a QR code of unicode half blocks. Colors are inverted
+/// so the code scans on the usual dark terminal background.
+pub fn render_qr(text: &str) -> Result<String, Box<dyn Error>> {
+    let code = QrCode::new(text.as_bytes()).map_err(|e| format!("Cannot encode QR code: {}", e))?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+/// Appends the reference keys to `instruction` as read-only accounts.
+fn attach_references(mut instruction: Instruction, references: &[Pubkey]) -> Instruction {
+    instruction.accounts.extend(
+        references
+            .iter()
+            .map(|reference| AccountMeta::new_readonly(*reference, false)),
+    );
+    instruction
+}
+
+/// Instructions paying a SOL request: the memo, if any, followed by a
+/// transfer carrying the reference keys.
+pub fn sol_transfer_instructions(
+    payer: &Pubkey,
+    request: &TransferRequest,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    if let Some(memo) = &request.memo {
+        instructions.push(spl_memo::build_memo(memo.as_bytes(), &[]));
+    }
+    instructions.push(attach_references(
+        system_instruction::transfer(payer, &request.recipient, lamports),
+        &request.references,
+    ));
+    instructions
+}
+
+/// Builds the instructions that pay `request` from `payer`. `amount` is used
+/// when the request leaves the amount to the payer.
+pub async fn payment_instructions(
+    rpc_client: &dyn Rpc,
+    payer: &Pubkey,
+    request: &TransferRequest,
+    amount: Option<&Amount>,
+    fund_recipient: bool,
+) -> Result<Vec<Instruction>, Box<dyn Error>> {
+    let amount = match (&request.amount, amount) {
+        (Some(_), Some(_)) => return Err("The request already sets the amount".into()),
+        (Some(amount), None) | (None, Some(amount)) => amount,
+        (None, None) => return Err("The request has no amount; pass --amount".into()),
+    };
+    let Some(mint) = &request.spl_token else {
+        let lamports = amount.to_units(SOL_DECIMALS)?;
+        return Ok(sol_transfer_instructions(payer, request, lamports));
+    };
+
+    let decimals = token::get_mint(rpc_client, mint).await?.decimals;
+    let units = amount.to_units(decimals)?;
+    let destination = get_associated_token_address(&request.recipient, mint);
+    let mut instructions = Vec::new();
+    if let Some(memo) = &request.memo {
+        instructions.push(spl_memo::build_memo(memo.as_bytes(), &[]));
+    }
+    if fund_recipient {
+        instructions.push(create_associated_token_account_idempotent(
+            payer,
+            &request.recipient,
+            mint,
+            &spl_token::id(),
+        ));
+    } else if rpc_client.get_account(&destination).await?.is_none() {
+        return Err(format!(
+            "Recipient has no token account for {}; pass --fund-recipient to create it",
+            mint
+        )
+        .into());
+    }
+    let transfer = token_instruction::transfer_checked(
+        &spl_token::id(),
+        &get_associated_token_address(payer, mint),
+        mint,
+        &destination,
+        payer,
+        &[],
+        units,
+        decimals,
+    )?;
+    instructions.push(attach_references(transfer, &request.references));
+    Ok(instructions)
+}
+
+/// Pays `request` from `payer`, attaching its references and memo.
+pub async fn pay(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    request: &TransferRequest,
+    amount: Option<&Amount>,
+    fund_recipient: bool,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instructions =
+        payment_instructions(rpc_client, &payer.pubkey(), request, amount, fund_recipient).await?;
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[payer],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Finds the oldest transaction that mentions `reference`, which is the one
+/// that paid the request it was generated for.
+pub async fn find_reference(
+    rpc_client: &dyn Rpc,
+    reference: &Pubkey,
+) -> Result<Signature, Box<dyn Error>> {
+    let signatures = rpc_client
+        .get_signatures_for_address(reference, None, PAGE_SIZE)
+        .await?;
+    let oldest = signatures
+        .last()
+        .ok_or_else(|| format!("No transaction found for reference {}", reference))?;
+    Ok(Signature::from_str(&oldest.signature)?)
+}
+
+/// A payment that matched its request.
+#[derive(Serialize, Debug)]
+pub struct VerifiedPayment {
+    pub signature: String,
+    pub slot: u64,
+    pub time: Option<String>,
+    pub recipient: String,
+    pub spl_token: Option<String>,
+    /// Amount the recipient received, in SOL or tokens.
+    pub received: String,
+}
+
+impl fmt::Display for VerifiedPayment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Payment verified: Signature {}", self.signature)?;
+        match &self.spl_token {
+            Some(mint) => writeln!(f, "Received: {} of {}", self.received, mint)?,
+            None => writeln!(f, "Received: {} SOL", self.received)?,
+        }
+        write!(f, "Slot: {}", self.slot)?;
+        if let Some(time) = &self.time {
+            write!(f, " ({})", time)?;
+        }
+        Ok(())
+    }
+}
+
+/// Token balance of the account at `index`, in base units.
+fn token_balance(
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+    index: usize,
+    mint: &Pubkey,
+) -> u64 {
+    let balances: Option<&Vec<UiTransactionTokenBalance>> = balances.as_ref().into();
+    balances
+        .into_iter()
+        .flatten()
+        .find(|balance| balance.account_index as usize == index && balance.mint == mint.to_string())
+        .and_then(|balance| balance.ui_token_amount.amount.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Checks that `signature` succeeded, paid the recipient at least the
+/// requested amount in the requested currency, and carries the request's
+/// references and memo.
+pub async fn validate_transfer(
+    rpc_client: &dyn Rpc,
+    signature: &Signature,
+    request: &TransferRequest,
+) -> Result<VerifiedPayment, Box<dyn Error>> {
+    let amount = request
+        .amount
+        .as_ref()
+        .ok_or("The request has no amount to verify")?;
+    let confirmed = rpc_client.get_transaction(signature).await?;
+    let message = match &confirmed.transaction.transaction {
+        EncodedTransaction::Json(transaction) => match &transaction.message {
+            UiMessage::Parsed(message) => message,
+            UiMessage::Raw(_) => return Err("RPC node returned an unparsed message".into()),
+        },
+        _ => return Err("RPC node returned an undecoded transaction".into()),
+    };
+    let meta = confirmed
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or("RPC node returned no transaction metadata")?;
+    if let Some(err) = &meta.err {
+        return Err(format!("Transaction {} failed: {}", signature, err).into());
+    }
+    let position = |key: &Pubkey| {
+        let key = key.to_string();
+        message
+            .account_keys
+            .iter()
+            .position(|account| account.pubkey == key)
+    };
+
+    let (decimals, received) = match &request.spl_token {
+        None => {
+            let index =
+                position(&request.recipient).ok_or("Transaction does not involve the recipient")?;
+            let balance = |balances: &[u64]| balances.get(index).copied().unwrap_or(0);
+            let received = balance(&meta.post_balances).saturating_sub(balance(&meta.pre_balances));
+            (SOL_DECIMALS, received)
+        }
+        Some(mint) => {
+            let account = get_associated_token_address(&request.recipient, mint);
+            let index = position(&account)
+                .ok_or("Transaction does not involve the recipient's token account")?;
+            let received = token_balance(&meta.post_token_balances, index, mint)
+                .saturating_sub(token_balance(&meta.pre_token_balances, index, mint));
+            (token::get_mint(rpc_client, mint).await?.decimals, received)
+        }
+    };
+    let expected = amount.to_units(decimals)?;
+    if received < expected {
+        return Err(format!(
+            "Recipient received {} but {} was requested",
+            format_units(received, decimals),
+            amount
+        )
+        .into());
+    }
+    for reference in &request.references {
+        if position(reference).is_none() {
+            return Err(format!("Transaction does not include reference {}", reference).into());
+        }
+    }
+    if let Some(memo) = &request.memo {
+        let has_memo = message.instructions.iter().any(|instruction| {
+            matches!(
+                instruction,
+                UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed))
+                    if parsed.program == "spl-memo" && parsed.parsed.as_str() == Some(memo)
+            )
+        });
+        if !has_memo {
+            return Err(format!("Transaction does not carry the memo \"{}\"", memo).into());
+        }
+    }
+
+    Ok(VerifiedPayment {
+        signature: signature.to_string(),
+        slot: confirmed.slot,
+        time: confirmed
+            .block_time
+            .and_then(|time| DateTime::from_timestamp(time, 0))
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string()),
+        recipient: request.recipient.to_string(),
+        spl_token: request.spl_token.map(|mint| mint.to_string()),
+        received: format_units(received, decimals),
+    })
+}
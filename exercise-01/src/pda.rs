@@ -0,0 +1,286 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+use solana_sdk::{
+    hash::Hasher,
+    pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN},
+};
+use std::{error::Error, fmt};
+
+/// Appended to the seeds when hashing a program-derived address.
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
+/// Candidate seeds `find_seeds` accepts. Eight give about 110,000 ordered
+/// combinations, each tried with all 256 bumps.
+pub const MAX_CANDIDATES: usize = 8;
+
+/// One `--seed` argument, kept with the spec it was parsed from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Seed {
+    pub spec: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Width in bytes and signedness of an integer seed type.
+fn integer_type(name: &str) -> Option<(usize, bool)> {
+    Some(match name {
+        "u8" => (1, false),
+        "i8" => (1, true),
+        "u16" => (2, false),
+        "i16" => (2, true),
+        "u32" => (4, false),
+        "i32" => (4, true),
+        "u64" => (8, false),
+        "i64" => (8, true),
+        "u128" => (16, false),
+        "i128" => (16, true),
+        _ => return None,
+    })
+}
+
+/// Encodes `value` as a `width`-byte integer, little-endian unless
+/// `big_endian` is set.
+fn integer_bytes(
+    value: &str,
+    width: usize,
+    signed: bool,
+    big_endian: bool,
+) -> Result<Vec<u8>, String> {
+    let bits = width as u32 * 8;
+    let out_of_range = || format!("{} does not fit in {} bits", value, bits);
+    let mut bytes = if signed {
+        let number: i128 = value
+            .parse()
+            .map_err(|_| format!("Invalid integer '{}'", value))?;
+        if bits < 128 && !(-(1i128 << (bits - 1))..1i128 << (bits - 1)).contains(&number) {
+            return Err(out_of_range());
+        }
+        number.to_le_bytes()[..width].to_vec()
+    } else {
+        let number: u128 = value
+            .parse()
+            .map_err(|_| format!("Invalid integer '{}'", value))?;
+        if bits < 128 && number >> bits != 0 {
+            return Err(out_of_range());
+        }
+        number.to_le_bytes()[..width].to_vec()
+    };
+    if big_endian {
+        bytes.reverse();
+    }
+    Ok(bytes)
+}
+
+impl Seed {
+    /// Parses `<encoding>:<value>`. Encodings are `str`, `pubkey`, `hex`,
+    /// `base58`, `base64`, `bytes` (comma-separated decimals) and the
+    /// integers `u8` to `u128` and `i8` to `i128`, with an `le` or `be`
+    /// suffix. Integers are little-endian without one, as `to_le_bytes`
+    /// gives. `resolve` turns a `pubkey:` value into a key, so it can accept
+    /// contacts as well as base58.
+    pub fn parse(
+        spec: &str,
+        resolve: impl Fn(&str) -> Result<Pubkey, Box<dyn Error>>,
+    ) -> Result<Seed, Box<dyn Error>> {
+        let (encoding, value) = spec.split_once(':').ok_or_else(|| {
+            format!(
+                "Invalid seed '{}': expected <encoding>:<value>, such as str:vault",
+                spec
+            )
+        })?;
+        let encoding = encoding.to_lowercase();
+        let invalid = |e: &dyn fmt::Display| format!("Invalid seed '{}': {}", spec, e);
+        let bytes = match encoding.as_str() {
+            "str" | "string" | "utf8" => value.as_bytes().to_vec(),
+            "pubkey" | "key" => resolve(value)?.to_bytes().to_vec(),
+            "hex" => {
+                let digits = value.strip_prefix("0x").unwrap_or(value);
+                if !digits.is_ascii() || digits.len() % 2 != 0 {
+                    return Err(invalid(&"expected an even number of hex digits").into());
+                }
+                (0..digits.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| invalid(&e))?
+            }
+            "base58" | "bs58" => bs58::decode(value).into_vec().map_err(|e| invalid(&e))?,
+            "base64" => BASE64.decode(value).map_err(|e| invalid(&e))?,
+            "bytes" => value
+                .split(',')
+                .map(|byte| byte.trim().parse::<u8>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| invalid(&e))?,
+            _ => {
+                let (name, big_endian) = match encoding.strip_suffix("be") {
+                    Some(name) => (name, true),
+                    None => (encoding.strip_suffix("le").unwrap_or(&encoding), false),
+                };
+                let (width, signed) = integer_type(name)
+                    .ok_or_else(|| format!("Unknown seed encoding '{}' in '{}'", encoding, spec))?;
+                integer_bytes(value, width, signed, big_endian).map_err(|e| invalid(&e))?
+            }
+        };
+        if bytes.len() > MAX_SEED_LEN {
+            return Err(format!(
+                "Seed '{}' is {} bytes; seeds are at most {} bytes",
+                spec,
+                bytes.len(),
+                MAX_SEED_LEN
+            )
+            .into());
+        }
+        Ok(Seed {
+            spec: spec.to_string(),
+            bytes,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SeedInfo {
+    pub seed: String,
+    pub hex: String,
+}
+
+/// A program-derived address and the seeds and bump that produce it.
+#[derive(Serialize, Debug)]
+pub struct PdaInfo {
+    pub address: String,
+    pub program: String,
+    pub bump: u8,
+    /// Whether `bump` is the one `find_program_address` returns. Programs
+    /// almost always expect the canonical bump.
+    pub canonical: bool,
+    pub seeds: Vec<SeedInfo>,
+}
+
+impl PdaInfo {
+    fn new(address: &Pubkey, program: &Pubkey, seeds: &[&Seed], bump: u8, canonical: bool) -> Self {
+        PdaInfo {
+            address: address.to_string(),
+            program: program.to_string(),
+            bump,
+            canonical,
+            seeds: seeds
+                .iter()
+                .map(|seed| SeedInfo {
+                    seed: seed.spec.clone(),
+                    hex: seed
+                        .bytes
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for PdaInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Address: {}", self.address)?;
+        write!(f, "Bump: {}", self.bump)?;
+        if !self.canonical {
+            write!(f, " (not the canonical bump)")?;
+        }
+        write!(f, "\nProgram: {}", self.program)?;
+        write!(f, "\nSeeds: {}", self.seeds.len())?;
+        let width = self.seeds.iter().map(|seed| seed.seed.len()).max();
+        for seed in &self.seeds {
+            write!(
+                f,
+                "\n  {:<width$}  {}",
+                seed.seed,
+                seed.hex,
+                width = width.unwrap_or(0)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Derives the address of `seeds` under `program` with the canonical bump.
+pub fn derive(program: &Pubkey, seeds: &[Seed]) -> Result<PdaInfo, Box<dyn Error>> {
+    // The bump takes the last of the MAX_SEEDS slots.
+    if seeds.len() >= MAX_SEEDS {
+        return Err(format!("At most {} seeds are allowed", MAX_SEEDS - 1).into());
+    }
+    let bytes: Vec<&[u8]> = seeds.iter().map(|seed| seed.bytes.as_slice()).collect();
+    let (address, bump) = Pubkey::try_find_program_address(&bytes, program)
+        .ok_or("No bump yields an address off the ed25519 curve for these seeds")?;
+    let seeds: Vec<&Seed> = seeds.iter().collect();
+    Ok(PdaInfo::new(&address, program, &seeds, bump, true))
+}
+
+/// Searches ordered selections of `candidates`, shortest first, for the
+/// seeds and bump that derive `target` under `program`. Every bump is tried,
+/// so a PDA created with a non-canonical bump is found too.
+pub fn find_seeds(
+    program: &Pubkey,
+    candidates: &[Seed],
+    target: &Pubkey,
+) -> Result<PdaInfo, Box<dyn Error>> {
+    if target.is_on_curve() {
+        return Err(format!(
+            "{} is on the ed25519 curve, so it is not a program-derived address",
+            target
+        )
+        .into());
+    }
+    if candidates.len() > MAX_CANDIDATES {
+        return Err(format!("At most {} candidate seeds are searched", MAX_CANDIDATES).into());
+    }
+    let max_len = candidates.len().min(MAX_SEEDS - 1);
+    let mut chosen = Vec::new();
+    for len in 0..=max_len {
+        if let Some(bump) = search(program, candidates, target, len, &mut chosen) {
+            let seeds: Vec<&Seed> = chosen.iter().map(|&index| &candidates[index]).collect();
+            let bytes: Vec<&[u8]> = seeds.iter().map(|seed| seed.bytes.as_slice()).collect();
+            let canonical = Pubkey::try_find_program_address(&bytes, program)
+                .is_some_and(|(_, canonical)| canonical == bump);
+            return Ok(PdaInfo::new(target, program, &seeds, bump, canonical));
+        }
+    }
+    Err(format!(
+        "No combination of the {} seeds derives {} under {}",
+        candidates.len(),
+        target,
+        program
+    )
+    .into())
+}
+
+/// Extends `chosen` with unused candidates up to `len` seeds and hashes each
+/// full selection with every bump. The hash alone decides a match: `target`
+/// is already known to be off the curve.
+fn search(
+    program: &Pubkey,
+    candidates: &[Seed],
+    target: &Pubkey,
+    len: usize,
+    chosen: &mut Vec<usize>,
+) -> Option<u8> {
+    if chosen.len() == len {
+        let seeds: Vec<&[u8]> = chosen
+            .iter()
+            .map(|&index| candidates[index].bytes.as_slice())
+            .collect();
+        return (0..=u8::MAX).rev().find(|bump| {
+            let mut hasher = Hasher::default();
+            hasher.hashv(&seeds);
+            hasher.hashv(&[&[*bump], program.as_ref(), PDA_MARKER]);
+            hasher.result().to_bytes() == target.to_bytes()
+        });
+    }
+    for index in 0..candidates.len() {
+        if chosen.contains(&index) {
+            continue;
+        }
+        chosen.push(index);
+        if let Some(bump) = search(program, candidates, target, len, chosen) {
+            return Some(bump);
+        }
+        chosen.pop();
+    }
+    None
+}
@@ -0,0 +1,191 @@
+use crate::{
+    amount::format_sol,
+    rpc::Rpc,
+    simulate::{self, BalanceChange, ProgramErrors},
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, IsTerminal, Write},
+};
+
+/// What a signed transaction will cost and change, shown before it is sent.
+#[derive(Debug)]
+pub struct Preview {
+    pub fee_lamports: u64,
+    pub payer: Pubkey,
+    pub payer_before: u64,
+    pub payer_after: u64,
+    /// Balance changes of accounts other than the fee payer.
+    pub balance_changes: Vec<BalanceChange>,
+    /// Why the transaction failed in simulation, if it did.
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Simulates `transaction` to work out its fee and balance changes, and
+/// warns about accounts it would leave below the rent-exempt minimum.
+pub async fn preview(
+    rpc_client: &dyn Rpc,
+    transaction: &VersionedTransaction,
+) -> Result<Preview, Box<dyn Error>> {
+    let payer = *transaction
+        .message
+        .static_account_keys()
+        .first()
+        .ok_or("Transaction has no fee payer")?;
+    let payer_before = rpc_client.get_balance(&payer).await?;
+    let simulation = simulate::simulate(rpc_client, transaction).await?;
+
+    let (payer_changes, balance_changes): (Vec<_>, Vec<_>) = simulation
+        .balance_changes
+        .into_iter()
+        .partition(|change| change.address == payer.to_string());
+    // Failed simulations return no accounts, leaving only the fee.
+    let payer_after = payer_changes.first().map_or(
+        payer_before.saturating_sub(simulation.fee_lamports),
+        |change| change.after,
+    );
+    let payer_data_len = payer_changes.first().map_or(0, |change| change.data_len);
+
+    let mut warnings = Vec::new();
+    if let Some(warning) =
+        rent_warning(rpc_client, "Sender", &payer, payer_after, payer_data_len).await?
+    {
+        warnings.push(warning);
+    }
+    for change in &balance_changes {
+        let role = if change.after > change.before {
+            "Recipient"
+        } else {
+            "Account"
+        };
+        if let Some(warning) = rent_warning(
+            rpc_client,
+            role,
+            &change.address,
+            change.after,
+            change.data_len,
+        )
+        .await?
+        {
+            warnings.push(warning);
+        }
+    }
+    // Clusters reject a transfer that leaves an account short of rent, so
+    // the simulation fails instead of reporting the balance.
+    if let Some(TransactionError::InsufficientFundsForRent { account_index }) = simulation.error {
+        if let Some(account) = simulation.account_keys.get(account_index as usize) {
+            let role = if *account == payer {
+                "Sender"
+            } else {
+                "Recipient"
+            };
+            warnings.push(format!(
+                "{} {} would be left below the rent-exempt minimum",
+                role, account
+            ));
+        }
+    }
+
+    Ok(Preview {
+        fee_lamports: simulation.fee_lamports,
+        payer,
+        payer_before,
+        payer_after,
+        balance_changes,
+        error: simulation.error.as_ref().map(|error| {
+            simulate::describe_error(error, &simulation.program_ids, &ProgramErrors::default())
+        }),
+        warnings,
+    })
+}
+
+/// Warns when `address` would end up holding lamports but fewer than it needs
+/// to be rent-exempt. An account left with nothing is closed, which is fine.
+pub async fn rent_warning(
+    rpc_client: &dyn Rpc,
+    role: &str,
+    address: &dyn fmt::Display,
+    lamports: u64,
+    data_len: usize,
+) -> Result<Option<String>, Box<dyn Error>> {
+    if lamports == 0 {
+        return Ok(None);
+    }
+    let minimum = rpc_client
+        .get_minimum_balance_for_rent_exemption(data_len)
+        .await?;
+    if lamports >= minimum {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "{} {} would hold {} SOL, below the rent-exempt minimum of {} SOL",
+        role,
+        address,
+        format_sol(lamports),
+        format_sol(minimum)
+    )))
+}
+
+impl fmt::Display for Preview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Fee: {} SOL", format_sol(self.fee_lamports))?;
+        write!(
+            f,
+            "Sender {}: {} -> {} SOL",
+            self.payer,
+            format_sol(self.payer_before),
+            format_sol(self.payer_after)
+        )?;
+        for change in &self.balance_changes {
+            let delta = change.after as i128 - change.before as i128;
+            let sign = if delta < 0 { "-" } else { "+" };
+            write!(
+                f,
+                "\n  {:<44}  {} -> {} SOL ({}{})",
+                change.address,
+                format_sol(change.before),
+                format_sol(change.after),
+                sign,
+                format_sol(delta.unsigned_abs() as u64)
+            )?;
+        }
+        if let Some(error) = &self.error {
+            write!(
+                f,
+                "\nWarning: the transaction failed in simulation: {}",
+                error
+            )?;
+        }
+        for warning in &self.warnings {
+            write!(f, "\nWarning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints `summary` to stderr and asks whether to go ahead. `--yes` skips
+/// the question; without it, a non-interactive stdin is an error rather
+/// than a silent send.
+pub fn confirm(summary: &dyn fmt::Display, yes: bool) -> Result<(), Box<dyn Error>> {
+    eprintln!("{}", summary);
+    if yes {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        return Err("Confirmation required: pass --yes to send without prompting".into());
+    }
+    eprint!("Send this transaction? [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err("Cancelled: nothing was sent".into()),
+    }
+}
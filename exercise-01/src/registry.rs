@@ -0,0 +1,161 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Wallet file used when neither `--wallet-file` nor a default wallet is set.
+pub const DEFAULT_WALLET_FILE: &str = "my_wallet.json";
+
+/// Overrides the per-user scw directory (`~/.config/scw`).
+pub const CONFIG_DIR_ENV: &str = "SCW_CONFIG_DIR";
+
+const REGISTRY_FILE: &str = "registry.json";
+
+/// Per-user scw directory, `$SCW_CONFIG_DIR` or `~/.config/scw`.
+pub fn config_dir() -> PathBuf {
+    if let Ok(dir) = env::var(CONFIG_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".config").join("scw")
+}
+
+/// A wallet argument is treated as a registry name when it is a bare
+/// identifier rather than something that looks like a path.
+pub fn is_wallet_name(value: &str) -> bool {
+    !value.is_empty()
+        && !value.ends_with(".json")
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WalletEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub created_at: String,
+}
+
+/// Index of the named wallets stored in `<config dir>/wallets`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct WalletRegistry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub wallets: BTreeMap<String, WalletEntry>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl WalletRegistry {
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        Self::load_from(config_dir().join("wallets"))
+    }
+
+    pub fn load_from(dir: PathBuf) -> Result<Self, Box<dyn Error>> {
+        let mut registry = match fs::read_to_string(dir.join(REGISTRY_FILE)) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(_) => WalletRegistry::default(),
+        };
+        registry.dir = dir;
+        Ok(registry)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        let data = serde_json::to_string_pretty(&self)?;
+        fs::write(self.dir.join(REGISTRY_FILE), data).map_err(|e| {
+            eprintln!("Failed to write wallet registry: {}", e);
+            e.into()
+        })
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    pub fn wallet_path(&self, name: &str) -> String {
+        self.dir
+            .join(format!("{}.json", name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Maps a registered wallet name to its file, leaving paths untouched.
+    pub fn resolve(&self, name_or_path: &str) -> String {
+        if self.wallets.contains_key(name_or_path) {
+            self.wallet_path(name_or_path)
+        } else {
+            name_or_path.to_string()
+        }
+    }
+
+    /// Resolves an explicit wallet argument, falling back to the default
+    /// wallet and then to `my_wallet.json`.
+    pub fn resolve_or_default(&self, name_or_path: Option<&str>) -> String {
+        match (name_or_path, &self.default) {
+            (Some(value), _) => self.resolve(value),
+            (None, Some(name)) => self.wallet_path(name),
+            (None, None) => DEFAULT_WALLET_FILE.to_string(),
+        }
+    }
+
+    /// Returns where a new wallet should be written. Bare names are placed in
+    /// the registry directory, anything else is used as a path. Existing
+    /// wallets are only overwritten when `force` is set.
+    pub fn new_wallet_path(
+        &self,
+        name_or_path: &str,
+        force: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        if !is_wallet_name(name_or_path) {
+            if !force && Path::new(name_or_path).exists() {
+                return Err(format!(
+                    "'{}' already exists; pass --force to overwrite it",
+                    name_or_path
+                )
+                .into());
+            }
+            return Ok(name_or_path.to_string());
+        }
+        let path = self.wallet_path(name_or_path);
+        if !force && (self.wallets.contains_key(name_or_path) || Path::new(&path).exists()) {
+            return Err(format!(
+                "Wallet '{}' already exists; pass --force to overwrite it",
+                name_or_path
+            )
+            .into());
+        }
+        fs::create_dir_all(&self.dir)?;
+        Ok(path)
+    }
+
+    /// Records a wallet written by `new_wallet_path`. The first wallet
+    /// registered becomes the default.
+    pub fn register(&mut self, name: &str, label: Option<&str>) {
+        self.wallets.insert(
+            name.to_string(),
+            WalletEntry {
+                label: label.map(str::to_string),
+                created_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            },
+        );
+        if self.default.is_none() {
+            self.default = Some(name.to_string());
+        }
+    }
+
+    pub fn set_default(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        if !self.wallets.contains_key(name) {
+            return Err(format!("Unknown wallet: {}", name).into());
+        }
+        self.default = Some(name.to_string());
+        Ok(())
+    }
+}
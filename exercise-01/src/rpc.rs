@@ -0,0 +1,322 @@
+use async_trait::async_trait;
+use serde_json::json;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{
+        RpcAccountInfoConfig, RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+        RpcTokenAccountsFilter, RpcTransactionConfig,
+    },
+    rpc_request::RpcRequest,
+    rpc_response::{
+        Response, RpcConfirmedTransactionStatusWithSignature, RpcInflationReward, RpcKeyedAccount,
+        RpcSimulateTransactionResult, RpcVoteAccountStatus,
+    },
+};
+use solana_sdk::{
+    account::Account,
+    clock::{Epoch, Slot},
+    commitment_config::CommitmentConfig,
+    epoch_info::EpochInfo,
+    epoch_schedule::EpochSchedule,
+    hash::Hash,
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, TransactionStatus, UiTransactionEncoding,
+};
+use std::str::FromStr;
+
+/// The RPC calls the wallet's commands are built on. `RpcClient` talks to a
+/// cluster; `mock::MockRpc` keeps the ledger in memory so the commands can be
+/// exercised without a network.
+#[async_trait]
+pub trait Rpc: Send + Sync {
+    /// Commitment used by every request.
+    fn commitment(&self) -> CommitmentConfig;
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64>;
+
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>>;
+
+    async fn get_multiple_accounts(&self, pubkeys: &[Pubkey])
+        -> ClientResult<Vec<Option<Account>>>;
+
+    async fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> ClientResult<Signature>;
+
+    /// Latest blockhash and the last block height at which it is valid.
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)>;
+
+    async fn get_slot(&self) -> ClientResult<Slot>;
+
+    async fn get_block_height(&self) -> ClientResult<u64>;
+
+    async fn get_fee_for_message(&self, message: &VersionedMessage) -> ClientResult<u64>;
+
+    /// Lamports an account holding `data_len` bytes needs to be rent-exempt.
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64>;
+
+    /// Prioritization fees recently paid by transactions writing to `addresses`.
+    async fn get_recent_prioritization_fees(&self, addresses: &[Pubkey]) -> ClientResult<Vec<u64>>;
+
+    async fn send_transaction(&self, transaction: &VersionedTransaction)
+        -> ClientResult<Signature>;
+
+    /// Status of `signature`, searching the transaction history as well as
+    /// the recent status cache.
+    async fn get_signature_status(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<Option<TransactionStatus>>;
+
+    /// Simulates a signed transaction, returning the post-simulation state of
+    /// `accounts` when it succeeds.
+    async fn simulate_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+        accounts: &[Pubkey],
+    ) -> ClientResult<RpcSimulateTransactionResult>;
+
+    async fn get_epoch_info(&self) -> ClientResult<EpochInfo>;
+
+    async fn get_epoch_schedule(&self) -> ClientResult<EpochSchedule>;
+
+    async fn get_stake_minimum_delegation(&self) -> ClientResult<u64>;
+
+    async fn get_vote_accounts(&self) -> ClientResult<RpcVoteAccountStatus>;
+
+    /// Inflation rewards credited to `addresses` in `epoch`, one entry per
+    /// address.
+    async fn get_inflation_reward(
+        &self,
+        addresses: &[Pubkey],
+        epoch: Epoch,
+    ) -> ClientResult<Vec<Option<RpcInflationReward>>>;
+
+    /// Accounts of the token program `program_id` owned by `owner`.
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        program_id: &Pubkey,
+    ) -> ClientResult<Vec<(Pubkey, Account)>>;
+
+    /// Up to `limit` signatures of transactions that mention `address`,
+    /// newest first, starting after `before`.
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>>;
+
+    /// A landed transaction with its metadata, with the instructions of known
+    /// programs parsed.
+    async fn get_transaction(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta>;
+}
+
+#[async_trait]
+impl Rpc for RpcClient {
+    fn commitment(&self) -> CommitmentConfig {
+        RpcClient::commitment(self)
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
+        RpcClient::get_balance(self, pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+        Ok(self
+            .get_account_with_commitment(pubkey, RpcClient::commitment(self))
+            .await?
+            .value)
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> ClientResult<Vec<Option<Account>>> {
+        RpcClient::get_multiple_accounts(self, pubkeys).await
+    }
+
+    async fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> ClientResult<Signature> {
+        RpcClient::request_airdrop(self, pubkey, lamports).await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<(Hash, u64)> {
+        self.get_latest_blockhash_with_commitment(RpcClient::commitment(self))
+            .await
+    }
+
+    async fn get_slot(&self) -> ClientResult<Slot> {
+        RpcClient::get_slot(self).await
+    }
+
+    async fn get_block_height(&self) -> ClientResult<u64> {
+        RpcClient::get_block_height(self).await
+    }
+
+    async fn get_fee_for_message(&self, message: &VersionedMessage) -> ClientResult<u64> {
+        match message {
+            VersionedMessage::Legacy(message) => {
+                RpcClient::get_fee_for_message(self, message).await
+            }
+            VersionedMessage::V0(message) => RpcClient::get_fee_for_message(self, message).await,
+        }
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
+        RpcClient::get_minimum_balance_for_rent_exemption(self, data_len).await
+    }
+
+    async fn get_recent_prioritization_fees(&self, addresses: &[Pubkey]) -> ClientResult<Vec<u64>> {
+        Ok(RpcClient::get_recent_prioritization_fees(self, addresses)
+            .await?
+            .iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect())
+    }
+
+    async fn send_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> ClientResult<Signature> {
+        RpcClient::send_transaction(self, transaction).await
+    }
+
+    async fn get_signature_status(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<Option<TransactionStatus>> {
+        Ok(self
+            .get_signature_statuses_with_history(&[*signature])
+            .await?
+            .value
+            .pop()
+            .flatten())
+    }
+
+    async fn simulate_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+        accounts: &[Pubkey],
+    ) -> ClientResult<RpcSimulateTransactionResult> {
+        Ok(self
+            .simulate_transaction_with_config(
+                transaction,
+                RpcSimulateTransactionConfig {
+                    commitment: Some(RpcClient::commitment(self)),
+                    accounts: Some(RpcSimulateTransactionAccountsConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        addresses: accounts.iter().map(|key| key.to_string()).collect(),
+                    }),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )
+            .await?
+            .value)
+    }
+
+    async fn get_epoch_info(&self) -> ClientResult<EpochInfo> {
+        RpcClient::get_epoch_info(self).await
+    }
+
+    async fn get_epoch_schedule(&self) -> ClientResult<EpochSchedule> {
+        RpcClient::get_epoch_schedule(self).await
+    }
+
+    async fn get_stake_minimum_delegation(&self) -> ClientResult<u64> {
+        RpcClient::get_stake_minimum_delegation(self).await
+    }
+
+    async fn get_vote_accounts(&self) -> ClientResult<RpcVoteAccountStatus> {
+        RpcClient::get_vote_accounts(self).await
+    }
+
+    async fn get_inflation_reward(
+        &self,
+        addresses: &[Pubkey],
+        epoch: Epoch,
+    ) -> ClientResult<Vec<Option<RpcInflationReward>>> {
+        RpcClient::get_inflation_reward(self, addresses, Some(epoch)).await
+    }
+
+    async fn get_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        program_id: &Pubkey,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        // `RpcClient::get_token_accounts_by_owner` asks for parsed JSON; the
+        // raw accounts are decoded here instead.
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(RpcClient::commitment(self)),
+            data_slice: None,
+            min_context_slot: None,
+        };
+        let response: Response<Vec<RpcKeyedAccount>> = self
+            .send(
+                RpcRequest::GetTokenAccountsByOwner,
+                json!([
+                    owner.to_string(),
+                    RpcTokenAccountsFilter::ProgramId(program_id.to_string()),
+                    config
+                ]),
+            )
+            .await?;
+        let mut accounts = Vec::new();
+        for keyed in response.value {
+            let decoded = Pubkey::from_str(&keyed.pubkey)
+                .ok()
+                .zip(keyed.account.decode());
+            let Some(decoded) = decoded else {
+                return Err(ClientError::from(ClientErrorKind::Custom(format!(
+                    "RPC node returned an undecodable account {}",
+                    keyed.pubkey
+                ))));
+            };
+            accounts.push(decoded);
+        }
+        Ok(accounts)
+    }
+
+    async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        limit: usize,
+    ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        self.get_signatures_for_address_with_config(
+            address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(limit),
+                commitment: Some(RpcClient::commitment(self)),
+            },
+        )
+        .await
+    }
+
+    async fn get_transaction(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        self.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::JsonParsed),
+                commitment: Some(RpcClient::commitment(self)),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+    }
+}
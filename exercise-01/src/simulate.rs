@@ -0,0 +1,272 @@
+use crate::{alt, amount::format_sol, output::Report, rpc::Rpc};
+use num_traits::FromPrimitive;
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    system_instruction::SystemError,
+    system_program,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use spl_associated_token_account::error::AssociatedTokenAccountError;
+use spl_token::error::TokenError;
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt, fs,
+    str::FromStr,
+};
+
+/// Returned in place of a receipt under `--dry-run`, which ends the command
+/// before anything is sent.
+#[derive(Debug)]
+pub struct Simulated(pub Simulation);
+
+impl fmt::Display for Simulated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Dry run: the transaction was simulated and not sent")
+    }
+}
+
+impl Error for Simulated {}
+
+#[derive(Serialize, Debug)]
+pub struct BalanceChange {
+    pub address: String,
+    pub before: u64,
+    pub after: u64,
+    #[serde(skip)]
+    pub data_len: usize,
+}
+
+/// Outcome of simulating a signed transaction.
+#[derive(Debug)]
+pub struct Simulation {
+    pub fee_lamports: u64,
+    pub units_consumed: Option<u64>,
+    pub error: Option<TransactionError>,
+    pub logs: Vec<String>,
+    /// Writable accounts whose balance the transaction changes.
+    pub balance_changes: Vec<BalanceChange>,
+    /// Program of each instruction, used to decode custom errors.
+    pub program_ids: Vec<Pubkey>,
+    /// Every account of the transaction, including those loaded from lookup
+    /// tables, in the order errors index them.
+    pub account_keys: Vec<Pubkey>,
+}
+
+pub async fn simulate(
+    rpc_client: &dyn Rpc,
+    transaction: &VersionedTransaction,
+) -> Result<Simulation, Box<dyn Error>> {
+    // Account indexes are only trusted once the message is sanitized.
+    transaction
+        .sanitize()
+        .map_err(|e| format!("Invalid transaction: {}", e))?;
+    let message = &transaction.message;
+    let account_keys = alt::resolve_account_keys(rpc_client, message).await?;
+    let writable: Vec<Pubkey> = account_keys
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| message.is_maybe_writable(*index, None))
+        .map(|(_, key)| *key)
+        .collect();
+    let before = rpc_client.get_multiple_accounts(&writable).await?;
+    let fee_lamports = rpc_client.get_fee_for_message(message).await?;
+    let result = rpc_client
+        .simulate_transaction(transaction, &writable)
+        .await?;
+
+    // Post-simulation accounts are only returned when the simulation succeeds.
+    let balance_changes = match &result.accounts {
+        Some(after) => writable
+            .iter()
+            .zip(&before)
+            .zip(after)
+            .map(|((address, before), after)| BalanceChange {
+                address: address.to_string(),
+                before: before.as_ref().map_or(0, |account| account.lamports),
+                after: after.as_ref().map_or(0, |account| account.lamports),
+                data_len: after
+                    .as_ref()
+                    .and_then(|account| account.space)
+                    .map(|space| space as usize)
+                    .or(before.as_ref().map(|account| account.data.len()))
+                    .unwrap_or(0),
+            })
+            .filter(|change| change.before != change.after)
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(Simulation {
+        fee_lamports,
+        units_consumed: result.units_consumed,
+        error: result.err,
+        logs: result.logs.unwrap_or_default(),
+        balance_changes,
+        program_ids: message
+            .instructions()
+            .iter()
+            .map(|instruction| {
+                account_keys
+                    .get(instruction.program_id_index as usize)
+                    .copied()
+                    .ok_or_else(|| {
+                        format!(
+                            "Instruction program index {} is out of range",
+                            instruction.program_id_index
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?,
+        account_keys,
+    })
+}
+
+/// Custom error names of programs, read from Anchor IDL files.
+#[derive(Default)]
+pub struct ProgramErrors {
+    /// Errors by program; `None` holds IDLs that do not name their program.
+    programs: HashMap<Option<Pubkey>, BTreeMap<u32, String>>,
+}
+
+impl ProgramErrors {
+    /// Reads the `errors` of each IDL. The program address is taken from
+    /// `address` (Anchor 0.30+) or `metadata.address` (older IDLs).
+    pub fn load(paths: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut programs: HashMap<Option<Pubkey>, BTreeMap<u32, String>> = HashMap::new();
+        for path in paths {
+            let idl: Value = serde_json::from_str(&fs::read_to_string(path).map_err(|e| {
+                eprintln!("Failed to read IDL '{}': {}", path, e);
+                e
+            })?)
+            .map_err(|e| format!("Invalid IDL '{}': {}", path, e))?;
+            let address = idl["address"]
+                .as_str()
+                .or(idl["metadata"]["address"].as_str())
+                .map(Pubkey::from_str)
+                .transpose()
+                .map_err(|e| format!("Invalid program address in IDL '{}': {}", path, e))?;
+            let errors = programs.entry(address).or_default();
+            for error in idl["errors"].as_array().into_iter().flatten() {
+                let (Some(code), Some(name)) = (error["code"].as_u64(), error["name"].as_str())
+                else {
+                    continue;
+                };
+                let description = match error["msg"].as_str() {
+                    Some(msg) => format!("{}: {}", name, msg),
+                    None => name.to_string(),
+                };
+                errors.insert(code as u32, description);
+            }
+        }
+        Ok(ProgramErrors { programs })
+    }
+
+    fn lookup(&self, program: &Pubkey, code: u32) -> Option<String> {
+        self.programs
+            .get(&Some(*program))
+            .or_else(|| self.programs.get(&None))
+            .and_then(|errors| errors.get(&code))
+            .cloned()
+    }
+}
+
+/// Names a custom error code of the system, SPL Token and associated token
+/// account programs, or of a program with a loaded IDL.
+fn custom_error_name(program: &Pubkey, code: u32, errors: &ProgramErrors) -> Option<String> {
+    if *program == system_program::id() {
+        return SystemError::from_u32(code).map(|e| e.to_string());
+    }
+    if *program == spl_token::id() {
+        return TokenError::from_u32(code).map(|e| e.to_string());
+    }
+    if *program == spl_associated_token_account::id() {
+        return AssociatedTokenAccountError::from_u32(code).map(|e| e.to_string());
+    }
+    errors.lookup(program, code)
+}
+
+/// Describes a transaction error, naming the failing instruction and its
+/// program, and decoding custom program error codes where possible.
+pub fn describe_error(
+    error: &TransactionError,
+    program_ids: &[Pubkey],
+    errors: &ProgramErrors,
+) -> String {
+    let TransactionError::InstructionError(index, instruction_error) = error else {
+        return error.to_string();
+    };
+    let index = *index as usize;
+    let program = program_ids.get(index);
+    let program_name = program.map_or("unknown program".to_string(), |p| p.to_string());
+    let reason = match (instruction_error, program) {
+        (InstructionError::Custom(code), Some(program)) => {
+            match custom_error_name(program, *code, errors) {
+                Some(name) => format!("{} (custom program error {:#x})", name, code),
+                None => format!("custom program error {:#x} ({})", code, code),
+            }
+        }
+        (instruction_error, _) => instruction_error.to_string(),
+    };
+    format!(
+        "Instruction {} ({}) failed: {}",
+        index + 1,
+        program_name,
+        reason
+    )
+}
+
+impl Simulation {
+    pub fn report(&self, errors: &ProgramErrors) -> Report {
+        let error = self
+            .error
+            .as_ref()
+            .map(|error| describe_error(error, &self.program_ids, errors));
+        let mut report = Report::new()
+            .line("Dry run: the transaction was simulated and not sent")
+            .line(match &error {
+                Some(error) => format!("Result: failed: {}", error),
+                None => "Result: success".to_string(),
+            })
+            .line(format!("Fee: {} SOL", format_sol(self.fee_lamports)))
+            .field("dry_run", true)
+            .field("success", error.is_none())
+            .field("fee_lamports", self.fee_lamports)
+            .field("units_consumed", self.units_consumed)
+            .field("balance_changes", &self.balance_changes)
+            .field("logs", &self.logs);
+        if let Some(error) = error {
+            report = report.field(
+                "error",
+                json!({ "code": "simulation_failed", "message": error }),
+            );
+        }
+        if let Some(units) = self.units_consumed {
+            report = report.line(format!("Compute units consumed: {}", units));
+        }
+        if !self.balance_changes.is_empty() {
+            report = report.line("Balance changes:");
+            for change in &self.balance_changes {
+                let delta = change.after as i128 - change.before as i128;
+                let sign = if delta < 0 { "-" } else { "+" };
+                report = report.line(format!(
+                    "  {:<44}  {} -> {} SOL ({}{})",
+                    change.address,
+                    format_sol(change.before),
+                    format_sol(change.after),
+                    sign,
+                    format_sol(delta.unsigned_abs() as u64)
+                ));
+            }
+        }
+        if !self.logs.is_empty() {
+            report = report.line("Logs:");
+            for log in &self.logs {
+                report = report.line(format!("  {}", log));
+            }
+        }
+        report
+    }
+}
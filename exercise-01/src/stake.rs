@@ -0,0 +1,481 @@
+use crate::{
+    amount::format_sol,
+    rpc::Rpc,
+    transaction::{self, BlockhashSource, Receipt, SendOptions},
+};
+use serde::Serialize;
+use solana_sdk::{
+    clock::Epoch,
+    feature::{self, Feature},
+    feature_set,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    stake::{
+        self, instruction as stake_instruction,
+        state::{Authorized, Lockup, StakeStateV2},
+    },
+    system_instruction,
+    sysvar::stake_history::{self, StakeHistory},
+};
+use std::{cmp::Reverse, error::Error, fmt};
+
+async fn stake_rent(rpc_client: &dyn Rpc) -> Result<u64, Box<dyn Error>> {
+    Ok(rpc_client
+        .get_minimum_balance_for_rent_exemption(StakeStateV2::size_of())
+        .await?)
+}
+
+/// Creates a stake account holding `lamports` of stake on top of the
+/// rent-exempt reserve, delegating it right away when `validator` is given.
+pub async fn create_stake_account(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    stake_keypair: &Keypair,
+    authorized: &Authorized,
+    validator: Option<&Pubkey>,
+    lamports: u64,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    // Delegating in the same transaction needs the staker's signature.
+    if validator.is_some() && authorized.staker != payer.pubkey() {
+        return Err("Delegating on creation requires the wallet to be the staker".into());
+    }
+    let minimum = rpc_client.get_stake_minimum_delegation().await?;
+    if lamports < minimum {
+        return Err(format!(
+            "Stake of {} SOL is below the minimum delegation of {} SOL",
+            format_sol(lamports),
+            format_sol(minimum)
+        )
+        .into());
+    }
+    let total = stake_rent(rpc_client).await? + lamports;
+    let instructions = match validator {
+        Some(validator) => stake_instruction::create_account_and_delegate_stake(
+            &payer.pubkey(),
+            &stake_keypair.pubkey(),
+            validator,
+            authorized,
+            &Lockup::default(),
+            total,
+        ),
+        None => stake_instruction::create_account(
+            &payer.pubkey(),
+            &stake_keypair.pubkey(),
+            authorized,
+            &Lockup::default(),
+            total,
+        ),
+    };
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[payer, stake_keypair],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+pub async fn delegate_stake(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    stake_account: &Pubkey,
+    staker: &Keypair,
+    validator: &Pubkey,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction = stake_instruction::delegate_stake(stake_account, &staker.pubkey(), validator);
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, staker],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+pub async fn deactivate_stake(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    stake_account: &Pubkey,
+    staker: &Keypair,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction = stake_instruction::deactivate_stake(stake_account, &staker.pubkey());
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, staker],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+pub async fn withdraw_stake(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    stake_account: &Pubkey,
+    withdrawer: &Keypair,
+    recipient: &Pubkey,
+    lamports: u64,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction = stake_instruction::withdraw(
+        stake_account,
+        &withdrawer.pubkey(),
+        recipient,
+        lamports,
+        None,
+    );
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer, withdrawer],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Moves `lamports` of `stake_account` into the new account `split_keypair`.
+/// The payer funds the new account's rent-exempt reserve, which the stake
+/// program requires up front.
+pub async fn split_stake(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    stake_account: &Pubkey,
+    staker: &Keypair,
+    split_keypair: &Keypair,
+    lamports: u64,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let mut instructions = vec![system_instruction::transfer(
+        &payer.pubkey(),
+        &split_keypair.pubkey(),
+        stake_rent(rpc_client).await?,
+    )];
+    instructions.extend(stake_instruction::split(
+        stake_account,
+        &staker.pubkey(),
+        lamports,
+        &split_keypair.pubkey(),
+    ));
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[payer, staker, split_keypair],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Merges `source` into `destination`, closing `source`.
+pub async fn merge_stake(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    destination: &Pubkey,
+    source: &Pubkey,
+    staker: &Keypair,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instructions = stake_instruction::merge(destination, source, &staker.pubkey());
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[payer, staker],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Epoch from which stake warms up and cools down at the reduced rate, if
+/// the feature is active on the cluster.
+async fn new_rate_activation_epoch(rpc_client: &dyn Rpc) -> Result<Option<Epoch>, Box<dyn Error>> {
+    let account = rpc_client
+        .get_account(&feature_set::reduce_stake_warmup_cooldown::id())
+        .await?;
+    let activated_at = account
+        .as_ref()
+        .and_then(feature::from_account)
+        .and_then(|feature: Feature| feature.activated_at);
+    match activated_at {
+        Some(slot) => Ok(Some(rpc_client.get_epoch_schedule().await?.get_epoch(slot))),
+        None => Ok(None),
+    }
+}
+
+/// Inflation reward credited to a stake account for one epoch.
+#[derive(Serialize, Debug)]
+pub struct EpochReward {
+    pub epoch: Epoch,
+    pub lamports: u64,
+    pub post_balance: u64,
+    /// Reward as a percentage of the balance before it was credited.
+    pub percent: f64,
+}
+
+/// State of a stake account as shown by `stake show`.
+#[derive(Serialize, Debug)]
+pub struct StakeInfo {
+    pub stake_account: String,
+    pub lamports: u64,
+    pub state: &'static str,
+    pub rent_exempt_reserve: Option<u64>,
+    pub staker: Option<String>,
+    pub withdrawer: Option<String>,
+    pub validator: Option<String>,
+    pub delegated_lamports: Option<u64>,
+    pub active_lamports: u64,
+    pub activating_lamports: u64,
+    pub deactivating_lamports: u64,
+    pub activation_epoch: Option<Epoch>,
+    pub deactivation_epoch: Option<Epoch>,
+    pub rewards: Vec<EpochReward>,
+}
+
+impl fmt::Display for StakeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Stake account: {}", self.stake_account)?;
+        writeln!(f, "Balance: {} SOL", format_sol(self.lamports))?;
+        write!(f, "State: {}", self.state)?;
+        if let Some(reserve) = self.rent_exempt_reserve {
+            write!(f, "\nRent-exempt reserve: {} SOL", format_sol(reserve))?;
+        }
+        if let (Some(staker), Some(withdrawer)) = (&self.staker, &self.withdrawer) {
+            write!(f, "\nStake authority: {}", staker)?;
+            write!(f, "\nWithdraw authority: {}", withdrawer)?;
+        }
+        if let (Some(validator), Some(delegated)) = (&self.validator, self.delegated_lamports) {
+            write!(f, "\nDelegated to: {}", validator)?;
+            write!(f, "\nDelegated stake: {} SOL", format_sol(delegated))?;
+            write!(
+                f,
+                "\nActive stake: {} SOL",
+                format_sol(self.active_lamports)
+            )?;
+            if self.activating_lamports > 0 {
+                write!(
+                    f,
+                    "\nActivating stake: {} SOL",
+                    format_sol(self.activating_lamports)
+                )?;
+            }
+            if self.deactivating_lamports > 0 {
+                write!(
+                    f,
+                    "\nDeactivating stake: {} SOL",
+                    format_sol(self.deactivating_lamports)
+                )?;
+            }
+        }
+        if let Some(epoch) = self.activation_epoch {
+            write!(f, "\nActivation epoch: {}", epoch)?;
+        }
+        if let Some(epoch) = self.deactivation_epoch {
+            write!(f, "\nDeactivation epoch: {}", epoch)?;
+        }
+        if !self.rewards.is_empty() {
+            write!(
+                f,
+                "\n{:<8}  {:>16}  {:>16}  {:>9}",
+                "EPOCH", "REWARD (SOL)", "BALANCE (SOL)", "PERCENT"
+            )?;
+            for reward in &self.rewards {
+                write!(
+                    f,
+                    "\n{:<8}  {:>16}  {:>16}  {:>8.4}%",
+                    reward.epoch,
+                    format_sol(reward.lamports),
+                    format_sol(reward.post_balance),
+                    reward.percent
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Inflation rewards of `stake_account` for up to `epochs` completed epochs,
+/// newest first. Epochs without a reward are skipped. Nodes only keep the
+/// rewards of recent epochs, so a failed lookup prints a warning and ends
+/// the list instead of failing `stake show`.
+async fn get_rewards(
+    rpc_client: &dyn Rpc,
+    stake_account: &Pubkey,
+    current_epoch: Epoch,
+    epochs: u64,
+) -> Vec<EpochReward> {
+    let mut rewards = Vec::new();
+    for epoch in (current_epoch.saturating_sub(epochs)..current_epoch).rev() {
+        let reward = match rpc_client
+            .get_inflation_reward(&[*stake_account], epoch)
+            .await
+        {
+            Ok(mut reward) => reward.pop().flatten(),
+            Err(err) => {
+                eprintln!(
+                    "Warning: rewards of epoch {} and earlier are unavailable: {}",
+                    epoch, err
+                );
+                break;
+            }
+        };
+        if let Some(reward) = reward {
+            let before = reward.post_balance.saturating_sub(reward.amount);
+            rewards.push(EpochReward {
+                epoch: reward.epoch,
+                lamports: reward.amount,
+                post_balance: reward.post_balance,
+                percent: if before == 0 {
+                    0.0
+                } else {
+                    reward.amount as f64 / before as f64 * 100.0
+                },
+            });
+        }
+    }
+    rewards
+}
+
+/// Fetches a stake account with its activation state and the rewards of the
+/// last `reward_epochs` epochs.
+pub async fn get_stake_info(
+    rpc_client: &dyn Rpc,
+    stake_account: &Pubkey,
+    reward_epochs: u64,
+) -> Result<StakeInfo, Box<dyn Error>> {
+    let account = rpc_client
+        .get_account(stake_account)
+        .await?
+        .ok_or_else(|| format!("Stake account {} not found", stake_account))?;
+    if account.owner != stake::program::id() {
+        return Err(format!("{} is not a stake account", stake_account).into());
+    }
+    let state: StakeStateV2 = bincode::deserialize(&account.data)
+        .map_err(|e| format!("Invalid stake account {}: {}", stake_account, e))?;
+    let meta = state.meta();
+    let mut info = StakeInfo {
+        stake_account: stake_account.to_string(),
+        lamports: account.lamports,
+        state: match state {
+            StakeStateV2::Uninitialized => "uninitialized",
+            StakeStateV2::RewardsPool => "rewards pool",
+            _ => "initialized",
+        },
+        rent_exempt_reserve: meta.map(|meta| meta.rent_exempt_reserve),
+        staker: meta.map(|meta| meta.authorized.staker.to_string()),
+        withdrawer: meta.map(|meta| meta.authorized.withdrawer.to_string()),
+        validator: None,
+        delegated_lamports: None,
+        active_lamports: 0,
+        activating_lamports: 0,
+        deactivating_lamports: 0,
+        activation_epoch: None,
+        deactivation_epoch: None,
+        rewards: Vec::new(),
+    };
+    let Some(delegation) = state.delegation() else {
+        return Ok(info);
+    };
+
+    let epoch = rpc_client.get_epoch_info().await?.epoch;
+    let history = rpc_client
+        .get_account(&stake_history::id())
+        .await?
+        .ok_or("The cluster has no stake history sysvar")?;
+    let history: StakeHistory = bincode::deserialize(&history.data)?;
+    let status = delegation.stake_activating_and_deactivating(
+        epoch,
+        &history,
+        new_rate_activation_epoch(rpc_client).await?,
+    );
+    info.state = if status.activating > 0 {
+        "activating"
+    } else if status.deactivating > 0 {
+        "deactivating"
+    } else if status.effective > 0 {
+        "active"
+    } else {
+        "inactive"
+    };
+    info.validator = Some(delegation.voter_pubkey.to_string());
+    info.delegated_lamports = Some(delegation.stake);
+    info.active_lamports = status.effective;
+    info.activating_lamports = status.activating;
+    info.deactivating_lamports = status.deactivating;
+    info.activation_epoch = Some(delegation.activation_epoch);
+    if delegation.deactivation_epoch != Epoch::MAX {
+        info.deactivation_epoch = Some(delegation.deactivation_epoch);
+    }
+    info.rewards = get_rewards(rpc_client, stake_account, epoch, reward_epochs).await;
+    Ok(info)
+}
+
+/// One row of `stake validators`.
+#[derive(Serialize, Debug)]
+pub struct ValidatorInfo {
+    pub vote_account: String,
+    pub identity: String,
+    pub commission: u8,
+    pub active_lamports: u64,
+    pub last_vote: u64,
+    pub delinquent: bool,
+}
+
+/// Lists up to `limit` validators by active stake, largest first.
+pub async fn get_validators(
+    rpc_client: &dyn Rpc,
+    limit: usize,
+    include_delinquent: bool,
+) -> Result<Vec<ValidatorInfo>, Box<dyn Error>> {
+    let accounts = rpc_client.get_vote_accounts().await?;
+    let delinquent = if include_delinquent {
+        accounts.delinquent
+    } else {
+        Vec::new()
+    };
+    let mut validators: Vec<ValidatorInfo> = accounts
+        .current
+        .into_iter()
+        .map(|account| (account, false))
+        .chain(delinquent.into_iter().map(|account| (account, true)))
+        .map(|(account, delinquent)| ValidatorInfo {
+            vote_account: account.vote_pubkey,
+            identity: account.node_pubkey,
+            commission: account.commission,
+            active_lamports: account.activated_stake,
+            last_vote: account.last_vote,
+            delinquent,
+        })
+        .collect();
+    validators.sort_by_key(|validator| Reverse(validator.active_lamports));
+    validators.truncate(limit);
+    Ok(validators)
+}
+
+pub fn format_validators(validators: &[ValidatorInfo]) -> String {
+    let mut table = format!(
+        "{:<44}  {:<44}  {:>10}  {:>20}  {:>11}",
+        "VOTE ACCOUNT", "IDENTITY", "COMMISSION", "ACTIVE STAKE (SOL)", "LAST VOTE"
+    );
+    for validator in validators {
+        table.push_str(&format!(
+            "\n{:<44}  {:<44}  {:>9}%  {:>20}  {:>11}",
+            validator.vote_account,
+            validator.identity,
+            validator.commission,
+            format_sol(validator.active_lamports),
+            validator.last_vote
+        ));
+        if validator.delinquent {
+            table.push_str("  delinquent");
+        }
+    }
+    table
+}
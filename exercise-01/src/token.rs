@@ -0,0 +1,325 @@
+use crate::{
+    amount::Amount,
+    rpc::Rpc,
+    transaction::{self, BlockhashSource, Receipt, SendOptions},
+};
+use serde::Serialize;
+use solana_sdk::{
+    program_pack::Pack, pubkey::Pubkey, signature::Keypair, signer::Signer, system_instruction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::{
+    amount_to_ui_amount_string_trimmed, instruction as token_instruction,
+    state::{Account as TokenAccount, Mint},
+};
+use std::{collections::HashMap, error::Error};
+
+/// Fetches and decodes a mint account owned by the SPL Token program.
+pub async fn get_mint(rpc_client: &dyn Rpc, mint: &Pubkey) -> Result<Mint, Box<dyn Error>> {
+    let account = rpc_client
+        .get_account(mint)
+        .await?
+        .ok_or_else(|| format!("Mint {} not found", mint))?;
+    if account.owner != spl_token::id() {
+        return Err(format!("{} is not an SPL Token mint", mint).into());
+    }
+    Mint::unpack(&account.data).map_err(|e| format!("Invalid mint {}: {}", mint, e).into())
+}
+
+/// Fetches and decodes a token account, `None` if it does not exist.
+async fn find_token_account(
+    rpc_client: &dyn Rpc,
+    account: &Pubkey,
+) -> Result<Option<TokenAccount>, Box<dyn Error>> {
+    let Some(data) = rpc_client.get_account(account).await? else {
+        return Ok(None);
+    };
+    if data.owner != spl_token::id() {
+        return Err(format!("{} is not an SPL Token account", account).into());
+    }
+    TokenAccount::unpack(&data.data)
+        .map(Some)
+        .map_err(|e| format!("Invalid token account {}: {}", account, e).into())
+}
+
+async fn get_token_account(
+    rpc_client: &dyn Rpc,
+    account: &Pubkey,
+) -> Result<TokenAccount, Box<dyn Error>> {
+    find_token_account(rpc_client, account)
+        .await?
+        .ok_or_else(|| format!("Token account {} not found", account).into())
+}
+
+/// Scales `amount` by the mint's decimals, or takes it as base units.
+fn token_units(amount: &Amount, base_units: bool, decimals: u8) -> Result<u64, Box<dyn Error>> {
+    if base_units {
+        amount.to_units(0)
+    } else {
+        amount.to_units(decimals)
+    }
+}
+
+pub async fn create_mint(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    mint_keypair: &Keypair,
+    mint_authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+    decimals: u8,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let rent = rpc_client
+        .get_minimum_balance_for_rent_exemption(Mint::LEN)
+        .await?;
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint_keypair.pubkey(),
+            rent,
+            Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        token_instruction::initialize_mint2(
+            &spl_token::id(),
+            &mint_keypair.pubkey(),
+            mint_authority,
+            freeze_authority,
+            decimals,
+        )?,
+    ];
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[payer, mint_keypair],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Creates the associated token account of `owner` for `mint` if it does not
+/// exist yet.
+pub async fn create_token_account(
+    rpc_client: &dyn Rpc,
+    payer: &Keypair,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    options: &SendOptions,
+) -> Result<(Pubkey, Receipt), Box<dyn Error>> {
+    let instruction =
+        create_associated_token_account_idempotent(&payer.pubkey(), owner, mint, &spl_token::id());
+    let receipt = transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[payer],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await?;
+    Ok((get_associated_token_address(owner, mint), receipt))
+}
+
+/// Mints `amount` tokens into the associated token account of `recipient`,
+/// creating it if needed. `authority` must be the mint authority.
+pub async fn mint_tokens(
+    rpc_client: &dyn Rpc,
+    authority: &Keypair,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: &Amount,
+    base_units: bool,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let decimals = get_mint(rpc_client, mint).await?.decimals;
+    let units = token_units(amount, base_units, decimals)?;
+    let destination = get_associated_token_address(recipient, mint);
+    let instructions = [
+        create_associated_token_account_idempotent(
+            &authority.pubkey(),
+            recipient,
+            mint,
+            &spl_token::id(),
+        ),
+        token_instruction::mint_to_checked(
+            &spl_token::id(),
+            mint,
+            &destination,
+            &authority.pubkey(),
+            &[],
+            units,
+            decimals,
+        )?,
+    ];
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[authority],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Moves tokens between the associated token accounts of `sender` and
+/// `recipient` with `transfer_checked`. `ALL` sends the whole balance.
+#[allow(clippy::too_many_arguments)]
+pub async fn transfer_tokens(
+    rpc_client: &dyn Rpc,
+    sender: &Keypair,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: &Amount,
+    base_units: bool,
+    fund_recipient: bool,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let decimals = get_mint(rpc_client, mint).await?.decimals;
+    let source = get_associated_token_address(&sender.pubkey(), mint);
+    let units = if amount.is_all() {
+        get_token_account(rpc_client, &source).await?.amount
+    } else {
+        token_units(amount, base_units, decimals)?
+    };
+    let destination = get_associated_token_address(recipient, mint);
+
+    let mut instructions = Vec::new();
+    if fund_recipient {
+        instructions.push(create_associated_token_account_idempotent(
+            &sender.pubkey(),
+            recipient,
+            mint,
+            &spl_token::id(),
+        ));
+    } else if rpc_client.get_account(&destination).await?.is_none() {
+        return Err(format!(
+            "Recipient has no token account for {}; pass --fund-recipient to create it",
+            mint
+        )
+        .into());
+    }
+    instructions.push(token_instruction::transfer_checked(
+        &spl_token::id(),
+        &source,
+        mint,
+        &destination,
+        &sender.pubkey(),
+        &[],
+        units,
+        decimals,
+    )?);
+    transaction::send_instructions(
+        rpc_client,
+        &instructions,
+        &[sender],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Closes an empty token account, returning its rent to `destination`.
+pub async fn close_token_account(
+    rpc_client: &dyn Rpc,
+    owner: &Keypair,
+    account: &Pubkey,
+    destination: &Pubkey,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let token_account = get_token_account(rpc_client, account).await?;
+    if token_account.amount != 0 && !token_account.is_native() {
+        return Err(format!(
+            "Token account {} still holds {} base units",
+            account, token_account.amount
+        )
+        .into());
+    }
+    let instruction = token_instruction::close_account(
+        &spl_token::id(),
+        account,
+        destination,
+        &owner.pubkey(),
+        &[],
+    )?;
+    transaction::send_instructions(
+        rpc_client,
+        &[instruction],
+        &[owner],
+        &BlockhashSource::default(),
+        options,
+    )
+    .await
+}
+
+/// Balance of the associated token account of `owner`, zero if it does not
+/// exist.
+pub async fn get_token_balance(
+    rpc_client: &dyn Rpc,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<String, Box<dyn Error>> {
+    let decimals = get_mint(rpc_client, mint).await?.decimals;
+    let account = get_associated_token_address(owner, mint);
+    let amount = find_token_account(rpc_client, &account)
+        .await?
+        .map_or(0, |account| account.amount);
+    Ok(amount_to_ui_amount_string_trimmed(amount, decimals))
+}
+
+/// One row of `token accounts`.
+#[derive(Serialize, Debug)]
+pub struct TokenAccountInfo {
+    pub mint: String,
+    pub account: String,
+    pub balance: String,
+}
+
+/// Lists every SPL Token account owned by `owner`.
+pub async fn get_token_accounts(
+    rpc_client: &dyn Rpc,
+    owner: &Pubkey,
+) -> Result<Vec<TokenAccountInfo>, Box<dyn Error>> {
+    let accounts: Vec<(Pubkey, TokenAccount)> = rpc_client
+        .get_token_accounts_by_owner(owner, &spl_token::id())
+        .await?
+        .into_iter()
+        .filter_map(|(pubkey, account)| Some((pubkey, TokenAccount::unpack(&account.data).ok()?)))
+        .collect();
+    let mut mints: Vec<Pubkey> = accounts.iter().map(|(_, account)| account.mint).collect();
+    mints.sort();
+    mints.dedup();
+    let mut decimals = HashMap::new();
+    for chunk in mints.chunks(100) {
+        let accounts = rpc_client.get_multiple_accounts(chunk).await?;
+        for (mint, account) in chunk.iter().zip(accounts) {
+            if let Some(mint_state) = account.and_then(|account| Mint::unpack(&account.data).ok()) {
+                decimals.insert(*mint, mint_state.decimals);
+            }
+        }
+    }
+    Ok(accounts
+        .into_iter()
+        .map(|(pubkey, account)| TokenAccountInfo {
+            mint: account.mint.to_string(),
+            account: pubkey.to_string(),
+            balance: decimals
+                .get(&account.mint)
+                .map_or("?".to_string(), |decimals| {
+                    amount_to_ui_amount_string_trimmed(account.amount, *decimals)
+                }),
+        })
+        .collect())
+}
+
+pub fn format_token_accounts(accounts: &[TokenAccountInfo]) -> String {
+    let mut table = format!("{:<44}  {:<44}  BALANCE", "MINT", "ACCOUNT");
+    for account in accounts {
+        table.push_str(&format!(
+            "\n{:<44}  {:<44}  {}",
+            account.mint, account.account, account.balance
+        ));
+    }
+    table
+}
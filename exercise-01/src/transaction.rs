@@ -0,0 +1,623 @@
+use crate::{
+    alt, preview,
+    rpc::Rpc,
+    simulate::{self, Simulated},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::ValueEnum;
+use serde::Serialize;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction::{self, SystemInstruction},
+    system_program,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// Longest `confirm_transaction` waits. Transactions with a recent blockhash
+/// normally expire well before this.
+pub const CONFIRM_TIMEOUT: Duration = Duration::from_secs(90);
+
+const INITIAL_POLL_DELAY: Duration = Duration::from_millis(500);
+const MAX_POLL_DELAY: Duration = Duration::from_secs(8);
+
+/// Text encodings for serialized transactions.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum TxEncoding {
+    Base58,
+    Base64,
+}
+
+/// Durable nonce account used in place of a recent blockhash.
+#[derive(Clone, Copy, Debug)]
+pub struct NonceSource {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+}
+
+/// Where a transaction's recent blockhash comes from. An explicit blockhash
+/// allows building fully offline; a nonce keeps the transaction valid until
+/// the nonce is advanced.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlockhashSource {
+    pub blockhash: Option<Hash>,
+    pub nonce: Option<NonceSource>,
+}
+
+/// Percentile of recent prioritization fees used by `--priority-fee auto`.
+pub const DEFAULT_FEE_PERCENTILE: u8 = 75;
+
+/// Compute unit price requested with `--priority-fee`.
+#[derive(Clone, Copy, Debug)]
+pub enum PriorityFee {
+    MicroLamports(u64),
+    /// Derive the price from the given percentile of recent fees paid for the
+    /// accounts the transaction writes to.
+    Auto(u8),
+}
+
+impl FromStr for PriorityFee {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "auto" {
+            return Ok(PriorityFee::Auto(DEFAULT_FEE_PERCENTILE));
+        }
+        if let Some(percentile) = value.strip_prefix("auto:") {
+            return match percentile.parse() {
+                Ok(percentile) if percentile <= 100 => Ok(PriorityFee::Auto(percentile)),
+                _ => Err(format!(
+                    "Invalid percentile '{}': expected 0-100",
+                    percentile
+                )),
+            };
+        }
+        value.parse().map(PriorityFee::MicroLamports).map_err(|_| {
+            format!(
+                "Invalid priority fee '{}': expected micro-lamports, auto or auto:<percentile>",
+                value
+            )
+        })
+    }
+}
+
+/// Compute budget instructions added to every transaction scw builds.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<PriorityFee>,
+}
+
+/// Settings shared by every command that sends a transaction.
+#[derive(Clone, Debug, Default)]
+pub struct SendOptions {
+    pub budget: ComputeBudget,
+    /// Lookup tables to compile version 0 messages against. Legacy messages
+    /// are built when this is empty.
+    pub lookup_tables: Vec<Pubkey>,
+    /// Simulate instead of sending, ending the command with `Simulated`.
+    pub dry_run: bool,
+    /// Send without asking for confirmation after the fee preview.
+    pub yes: bool,
+}
+
+/// Picks the given percentile of the prioritization fees recently paid by
+/// transactions writing to the same accounts.
+pub async fn recent_priority_fee(
+    rpc_client: &dyn Rpc,
+    instructions: &[Instruction],
+    percentile: u8,
+) -> Result<u64, Box<dyn Error>> {
+    let mut accounts: Vec<Pubkey> = instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+        .filter(|account| account.is_writable)
+        .map(|account| account.pubkey)
+        .collect();
+    accounts.sort();
+    accounts.dedup();
+    let mut fees = rpc_client.get_recent_prioritization_fees(&accounts).await?;
+    if fees.is_empty() {
+        return Ok(0);
+    }
+    fees.sort_unstable();
+    Ok(fees[(fees.len() - 1) * percentile as usize / 100])
+}
+
+/// Reads the durable nonce currently stored in a nonce account.
+pub async fn nonce_blockhash(
+    rpc_client: &dyn Rpc,
+    account: &Pubkey,
+) -> Result<Hash, Box<dyn Error>> {
+    Ok(crate::nonce::get_nonce_data(rpc_client, account)
+        .await?
+        .blockhash())
+}
+
+/// Compiles a legacy message, or a version 0 message when lookup tables are
+/// given so accounts found in them are referenced by index.
+pub fn compile_message(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    blockhash: &Hash,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<VersionedMessage, Box<dyn Error>> {
+    if lookup_tables.is_empty() {
+        return Ok(VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(payer),
+            blockhash,
+        )));
+    }
+    let message = v0::Message::try_compile(payer, instructions, lookup_tables, *blockhash)
+        .map_err(|e| format!("Failed to compile version 0 message: {}", e))?;
+    Ok(VersionedMessage::V0(message))
+}
+
+/// A transaction for `message` with every signature still missing.
+pub fn unsigned_transaction(message: VersionedMessage) -> VersionedTransaction {
+    VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message,
+    }
+}
+
+/// Accounts that must sign `transaction`, in signature order.
+pub fn required_signers(transaction: &VersionedTransaction) -> &[Pubkey] {
+    let keys = transaction.message.static_account_keys();
+    let count = transaction.message.header().num_required_signatures as usize;
+    &keys[..count.min(keys.len())]
+}
+
+pub fn is_signed(transaction: &VersionedTransaction) -> bool {
+    transaction
+        .signatures
+        .iter()
+        .all(|signature| *signature != Signature::default())
+}
+
+/// Adds the signatures of `signers`, leaving the other signatures as they
+/// are. Every keypair must be a required signer of the transaction.
+pub fn sign_transaction(
+    transaction: &mut VersionedTransaction,
+    signers: &[&Keypair],
+) -> Result<(), Box<dyn Error>> {
+    let message = transaction.message.serialize();
+    for signer in signers {
+        let position = required_signers(transaction)
+            .iter()
+            .position(|key| *key == signer.pubkey())
+            .ok_or_else(|| format!("{} is not a signer of this transaction", signer.pubkey()))?;
+        transaction.signatures[position] = signer.try_sign_message(&message)?;
+    }
+    Ok(())
+}
+
+/// Builds an unsigned transaction paid by `payer`. When a nonce is used,
+/// `advance_nonce_account` is prepended as the first instruction, followed by
+/// the compute budget instructions.
+pub async fn build_transaction(
+    rpc_client: &dyn Rpc,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    source: &BlockhashSource,
+    budget: &ComputeBudget,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction, Box<dyn Error>> {
+    let mut all_instructions = Vec::with_capacity(instructions.len() + 3);
+    if let Some(nonce) = &source.nonce {
+        all_instructions.push(system_instruction::advance_nonce_account(
+            &nonce.account,
+            &nonce.authority,
+        ));
+    }
+    if let Some(units) = budget.unit_limit {
+        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+    }
+    let unit_price = match budget.unit_price {
+        Some(PriorityFee::MicroLamports(price)) => Some(price),
+        Some(PriorityFee::Auto(percentile)) => {
+            Some(recent_priority_fee(rpc_client, instructions, percentile).await?)
+        }
+        None => None,
+    };
+    if let Some(price) = unit_price {
+        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+    }
+    all_instructions.extend_from_slice(instructions);
+
+    let blockhash = match (source.blockhash, &source.nonce) {
+        (Some(blockhash), _) => blockhash,
+        (None, Some(nonce)) => nonce_blockhash(rpc_client, &nonce.account).await?,
+        (None, None) => rpc_client.get_latest_blockhash().await?.0,
+    };
+
+    let message = compile_message(&all_instructions, payer, &blockhash, lookup_tables)?;
+    Ok(unsigned_transaction(message))
+}
+
+/// Final state of a submitted transaction.
+#[derive(Debug)]
+pub enum Confirmation {
+    /// Reached the client's commitment in this slot.
+    Confirmed(u64),
+    Failed(TransactionError),
+    /// The blockhash expired without the transaction landing, so it can no
+    /// longer be processed.
+    Expired,
+}
+
+/// Polls the status of `signature` with exponential backoff until it reaches
+/// the client's commitment, fails, or its blockhash expires. Expiry can only
+/// be detected when `last_valid_block_height` is known; otherwise `timeout`
+/// bounds the wait. Progress is written to stderr.
+pub async fn wait_for_confirmation(
+    rpc_client: &dyn Rpc,
+    signature: &Signature,
+    last_valid_block_height: Option<u64>,
+    timeout: Option<Duration>,
+) -> Result<Confirmation, Box<dyn Error>> {
+    let start = Instant::now();
+    let mut delay = INITIAL_POLL_DELAY;
+    let confirmation = loop {
+        let status = match rpc_client.get_signature_status(signature).await? {
+            None => match last_valid_block_height {
+                Some(height) if rpc_client.get_block_height().await? > height => {
+                    // The transaction can land between the status and the
+                    // block height requests. Once the blockhash has expired
+                    // nothing else can land, so one more look is final.
+                    match rpc_client.get_signature_status(signature).await? {
+                        None => break Confirmation::Expired,
+                        status => status,
+                    }
+                }
+                _ => None,
+            },
+            status => status,
+        };
+        let progress = match status {
+            Some(status) => match status.err {
+                Some(err) => break Confirmation::Failed(err),
+                None if status.satisfies_commitment(rpc_client.commitment()) => {
+                    break Confirmation::Confirmed(status.slot)
+                }
+                None => "landed, awaiting commitment",
+            },
+            None => "not yet seen",
+        };
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            eprintln!();
+            return Err(ConfirmationError::Timeout(*signature, start.elapsed()).into());
+        }
+        eprint!(
+            "\rWaiting for confirmation: {} ({}s)   ",
+            progress,
+            start.elapsed().as_secs()
+        );
+        io::stderr().flush()?;
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_POLL_DELAY);
+    };
+    if start.elapsed() >= INITIAL_POLL_DELAY {
+        eprintln!();
+    }
+    Ok(confirmation)
+}
+
+/// A transaction that did not reach the requested commitment.
+#[derive(Debug)]
+pub enum ConfirmationError {
+    Failed(Signature, TransactionError),
+    Expired(Signature),
+    Timeout(Signature, Duration),
+}
+
+impl fmt::Display for ConfirmationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfirmationError::Failed(signature, err) => {
+                write!(f, "Transaction {} failed: {}", signature, err)
+            }
+            ConfirmationError::Expired(signature) => write!(
+                f,
+                "Blockhash of {} expired before it was confirmed; it is safe to retry",
+                signature
+            ),
+            ConfirmationError::Timeout(signature, elapsed) => write!(
+                f,
+                "Timed out after {}s waiting for {} to be confirmed",
+                elapsed.as_secs(),
+                signature
+            ),
+        }
+    }
+}
+
+impl Error for ConfirmationError {}
+
+/// A confirmed transaction.
+#[derive(Clone, Copy, Debug)]
+pub struct Receipt {
+    pub signature: Signature,
+    pub slot: u64,
+}
+
+/// Waits for `signature` with the default timeout, turning a failed or
+/// expired transaction into a `ConfirmationError`.
+pub async fn confirm_transaction(
+    rpc_client: &dyn Rpc,
+    signature: &Signature,
+    last_valid_block_height: Option<u64>,
+) -> Result<Receipt, Box<dyn Error>> {
+    match wait_for_confirmation(
+        rpc_client,
+        signature,
+        last_valid_block_height,
+        Some(CONFIRM_TIMEOUT),
+    )
+    .await?
+    {
+        Confirmation::Confirmed(slot) => Ok(Receipt {
+            signature: *signature,
+            slot,
+        }),
+        Confirmation::Failed(err) => Err(ConfirmationError::Failed(*signature, err).into()),
+        Confirmation::Expired => Err(ConfirmationError::Expired(*signature).into()),
+    }
+}
+
+/// Sends a signed transaction and waits for it with `confirm_transaction`.
+pub async fn send_and_confirm(
+    rpc_client: &dyn Rpc,
+    transaction: &VersionedTransaction,
+    last_valid_block_height: Option<u64>,
+) -> Result<Receipt, Box<dyn Error>> {
+    let signature = rpc_client.send_transaction(transaction).await?;
+    confirm_transaction(rpc_client, &signature, last_valid_block_height).await
+}
+
+/// Builds, signs and sends a transaction, waiting for confirmation. The fee
+/// and balance changes are previewed first and must be confirmed unless
+/// `--yes` is given. Under `--dry-run` the transaction is simulated instead
+/// and `Simulated` is returned as the error.
+pub async fn send_instructions(
+    rpc_client: &dyn Rpc,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    source: &BlockhashSource,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let payer = signers.first().ok_or("At least one signer is required")?;
+    // Fetching the blockhash here gives its expiry height, which lets the
+    // confirmation stop as soon as the transaction can no longer land.
+    let (source, last_valid_block_height) = match (source.blockhash, source.nonce) {
+        (None, None) => {
+            let (blockhash, height) = rpc_client.get_latest_blockhash().await?;
+            let source = BlockhashSource {
+                blockhash: Some(blockhash),
+                nonce: None,
+            };
+            (source, Some(height))
+        }
+        _ => (*source, None),
+    };
+    let lookup_tables = alt::fetch_lookup_tables(rpc_client, &options.lookup_tables).await?;
+    let mut transaction = build_transaction(
+        rpc_client,
+        instructions,
+        &payer.pubkey(),
+        &source,
+        &options.budget,
+        &lookup_tables,
+    )
+    .await?;
+    sign_transaction(&mut transaction, signers)?;
+    if !is_signed(&transaction) {
+        return Err("Transaction is missing signatures".into());
+    }
+    if options.dry_run {
+        let simulation = simulate::simulate(rpc_client, &transaction).await?;
+        return Err(Simulated(simulation).into());
+    }
+    let preview = preview::preview(rpc_client, &transaction).await?;
+    preview::confirm(&preview, options.yes)?;
+    send_and_confirm(rpc_client, &transaction, last_valid_block_height).await
+}
+
+pub fn encode_transaction(
+    transaction: &VersionedTransaction,
+    encoding: TxEncoding,
+) -> Result<String, Box<dyn Error>> {
+    let bytes = bincode::serialize(transaction)?;
+    Ok(match encoding {
+        TxEncoding::Base58 => bs58::encode(bytes).into_string(),
+        TxEncoding::Base64 => BASE64.encode(bytes),
+    })
+}
+
+/// Decodes a base64 or base58 serialized legacy or version 0 transaction,
+/// returning the encoding that was detected. The transaction is sanitized,
+/// so its account indexes and signature count are consistent.
+pub fn decode_transaction(
+    data: &str,
+) -> Result<(VersionedTransaction, TxEncoding), Box<dyn Error>> {
+    let data = data.trim();
+    let (transaction, encoding) = match BASE64
+        .decode(data)
+        .ok()
+        .and_then(|bytes| bincode::deserialize::<VersionedTransaction>(&bytes).ok())
+    {
+        Some(transaction) => (transaction, TxEncoding::Base64),
+        None => {
+            let bytes = bs58::decode(data)
+                .into_vec()
+                .map_err(|_| "Transaction is neither base64 nor base58 encoded")?;
+            let transaction =
+                bincode::deserialize(&bytes).map_err(|e| format!("Invalid transaction: {}", e))?;
+            (transaction, TxEncoding::Base58)
+        }
+    };
+    transaction
+        .sanitize()
+        .map_err(|e| format!("Invalid transaction: {}", e))?;
+    Ok((transaction, encoding))
+}
+
+fn describe_instruction(instruction: &Instruction) -> String {
+    if instruction.program_id == system_program::id() {
+        match bincode::deserialize::<SystemInstruction>(&instruction.data) {
+            Ok(SystemInstruction::Transfer { lamports }) if instruction.accounts.len() >= 2 => {
+                return format!(
+                    "Transfer {} SOL from {} to {}",
+                    crate::amount::format_sol(lamports),
+                    instruction.accounts[0].pubkey,
+                    instruction.accounts[1].pubkey
+                )
+            }
+            Ok(SystemInstruction::AdvanceNonceAccount) if instruction.accounts.len() >= 3 => {
+                return format!(
+                    "Advance nonce account {} (authority {})",
+                    instruction.accounts[0].pubkey, instruction.accounts[2].pubkey
+                )
+            }
+            _ => {}
+        }
+    }
+    if instruction.program_id == compute_budget::id() {
+        let data = &instruction.data;
+        match data.first() {
+            Some(2) if data.len() == 5 => {
+                let units = u32::from_le_bytes(data[1..5].try_into().unwrap());
+                return format!("Set compute unit limit to {}", units);
+            }
+            Some(3) if data.len() == 9 => {
+                let price = u64::from_le_bytes(data[1..9].try_into().unwrap());
+                return format!("Set compute unit price to {} micro-lamports", price);
+            }
+            _ => {}
+        }
+    }
+    format!(
+        "Program {} ({} accounts, {} bytes of data)",
+        instruction.program_id,
+        instruction.accounts.len(),
+        instruction.data.len()
+    )
+}
+
+/// Signature slot of a transaction and whether it has been filled.
+#[derive(Serialize, Debug)]
+pub struct SignerStatus {
+    pub pubkey: String,
+    pub signed: bool,
+}
+
+/// What a transaction does, so it can be reviewed before signing.
+#[derive(Serialize, Debug)]
+pub struct TransactionSummary {
+    pub fee_payer: String,
+    pub blockhash: String,
+    /// `legacy` or `0`.
+    pub version: String,
+    pub lookup_tables: Vec<String>,
+    pub instructions: Vec<String>,
+    pub signers: Vec<SignerStatus>,
+}
+
+impl fmt::Display for TransactionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Fee payer: {}", self.fee_payer)?;
+        write!(f, "Blockhash: {}", self.blockhash)?;
+        if !self.lookup_tables.is_empty() {
+            write!(f, "\nVersion: {}", self.version)?;
+            for table in &self.lookup_tables {
+                write!(f, "\nLookup table: {}", table)?;
+            }
+        }
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            write!(f, "\nInstruction {}: {}", index + 1, instruction)?;
+        }
+        for signer in &self.signers {
+            let status = if signer.signed { "present" } else { "missing" };
+            write!(f, "\nSigner {}: {}", signer.pubkey, status)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summarizes `transaction`, whose full list of account keys, including
+/// those loaded from lookup tables, is `account_keys`.
+pub fn summarize_transaction(
+    transaction: &VersionedTransaction,
+    account_keys: &[Pubkey],
+) -> Result<TransactionSummary, Box<dyn Error>> {
+    let message = &transaction.message;
+    let key = |index: usize| {
+        account_keys.get(index).copied().ok_or_else(|| {
+            format!(
+                "Transaction references account {} but has only {}",
+                index,
+                account_keys.len()
+            )
+        })
+    };
+    let instructions = message
+        .instructions()
+        .iter()
+        .map(|compiled| {
+            let instruction = Instruction {
+                program_id: key(compiled.program_id_index as usize)?,
+                accounts: compiled
+                    .accounts
+                    .iter()
+                    .map(|&i| {
+                        let i = i as usize;
+                        Ok(AccountMeta {
+                            pubkey: key(i)?,
+                            is_signer: message.is_signer(i),
+                            is_writable: message.is_maybe_writable(i, None),
+                        })
+                    })
+                    .collect::<Result<_, String>>()?,
+                data: compiled.data.clone(),
+            };
+            Ok(describe_instruction(&instruction))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(TransactionSummary {
+        fee_payer: key(0)?.to_string(),
+        blockhash: message.recent_blockhash().to_string(),
+        version: match message {
+            VersionedMessage::Legacy(_) => "legacy".to_string(),
+            VersionedMessage::V0(_) => "0".to_string(),
+        },
+        lookup_tables: message
+            .address_table_lookups()
+            .unwrap_or_default()
+            .iter()
+            .map(|lookup| lookup.account_key.to_string())
+            .collect(),
+        instructions,
+        signers: required_signers(transaction)
+            .iter()
+            .zip(&transaction.signatures)
+            .map(|(signer, signature)| SignerStatus {
+                pubkey: signer.to_string(),
+                signed: *signature != Signature::default(),
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,72 @@
+use crate::{
+    amount::{format_sol, Amount},
+    rpc::Rpc,
+    transaction::{self, BlockhashSource, ComputeBudget, Receipt, SendOptions},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, system_instruction};
+use std::error::Error;
+
+pub async fn request_airdrop(
+    rpc_client: &dyn Rpc,
+    pubkey: &Pubkey,
+    lamports: u64,
+) -> Result<Receipt, Box<dyn Error>> {
+    let signature = rpc_client.request_airdrop(pubkey, lamports).await?;
+    // The faucet signed with a blockhash no newer than this one, so this
+    // expiry height is an upper bound for the airdrop's.
+    let (_, last_valid_block_height) = rpc_client.get_latest_blockhash().await?;
+    transaction::confirm_transaction(rpc_client, &signature, Some(last_valid_block_height)).await
+}
+
+pub async fn transfer_funds(
+    rpc_client: &dyn Rpc,
+    sender_keypair: &Keypair,
+    receiver_pub_key: &Pubkey,
+    amount_lamports: u64,
+    source: &BlockhashSource,
+    nonce_authority: Option<&Keypair>,
+    options: &SendOptions,
+) -> Result<Receipt, Box<dyn Error>> {
+    let instruction =
+        system_instruction::transfer(&sender_keypair.pubkey(), receiver_pub_key, amount_lamports);
+    let mut signers = vec![sender_keypair];
+    signers.extend(nonce_authority);
+    transaction::send_instructions(rpc_client, &[instruction], &signers, source, options).await
+}
+
+/// Resolves the lamports to transfer. `ALL` is the sender's balance minus the
+/// fee of the transfer itself.
+pub async fn transfer_lamports(
+    rpc_client: &dyn Rpc,
+    amount: &Amount,
+    in_lamports: bool,
+    sender: &Pubkey,
+    receiver: &Pubkey,
+    source: &BlockhashSource,
+    budget: &ComputeBudget,
+) -> Result<u64, Box<dyn Error>> {
+    if !amount.is_all() {
+        return amount.to_lamports(in_lamports);
+    }
+    let balance = rpc_client.get_balance(sender).await?;
+    let instruction = system_instruction::transfer(sender, receiver, balance);
+    let transaction = transaction::build_transaction(
+        rpc_client,
+        &[instruction],
+        sender,
+        source,
+        budget,
+        // Lookup tables shrink the transaction but do not change its fee.
+        &[],
+    )
+    .await?;
+    let fee = rpc_client.get_fee_for_message(&transaction.message).await?;
+    match balance.checked_sub(fee) {
+        Some(lamports) if lamports > 0 => Ok(lamports),
+        _ => Err(format!(
+            "Balance of {} SOL does not cover the transaction fee",
+            format_sol(balance)
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{keystore, keystore::EncryptedKeystore, mnemonic::DerivationInfo};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use std::{
+    env,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct WalletKeypair {
+    pub public_key: String,
+    pub secret_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation: Option<DerivationInfo>,
+}
+
+/// Any wallet file layout `read_keypair` knows how to load.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum WalletFile {
+    Encrypted(EncryptedKeystore),
+    Plain(WalletKeypair),
+    /// 64-byte JSON array written by `solana-keygen`.
+    SolanaCli(Vec<u8>),
+    /// Bare base58-encoded secret key, as exported by browser wallets.
+    Base58(String),
+}
+
+/// Keypair encodings `scw export` can write.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum KeypairFormat {
+    /// `{ public_key, secret_key }` JSON used by scw.
+    Scw,
+    /// 64-byte JSON array used by solana-keygen, Anchor and the test validator.
+    SolanaCli,
+    /// Base58-encoded 64-byte secret key.
+    Base58,
+}
+
+impl WalletFile {
+    /// Detects the wallet layout from the file contents.
+    pub fn parse(data: &str) -> Result<Self, Box<dyn Error>> {
+        let trimmed = data.trim();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            serde_json::from_str(trimmed)
+                .map_err(|e| format!("Unrecognized keypair format: {}", e).into())
+        } else {
+            Ok(WalletFile::Base58(trimmed.to_string()))
+        }
+    }
+
+    pub fn public_key(&self) -> Result<Pubkey, Box<dyn Error>> {
+        match self {
+            WalletFile::Encrypted(keystore) => Ok(keystore.public_key.parse()?),
+            WalletFile::Plain(wallet) => Ok(wallet.public_key.parse()?),
+            WalletFile::SolanaCli(_) | WalletFile::Base58(_) => Ok(self.unlock("")?.pubkey()),
+        }
+    }
+
+    pub fn derivation(&self) -> Option<&DerivationInfo> {
+        match self {
+            WalletFile::Encrypted(keystore) => keystore.derivation.as_ref(),
+            WalletFile::Plain(wallet) => wallet.derivation.as_ref(),
+            WalletFile::SolanaCli(_) | WalletFile::Base58(_) => None,
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, WalletFile::Encrypted(_))
+    }
+
+    /// Returns the keypair, prompting for the password of encrypted wallets.
+    pub fn unlock(&self, file_path: &str) -> Result<Keypair, Box<dyn Error>> {
+        let bytes = match self {
+            WalletFile::Encrypted(keystore) => {
+                let password = keystore::read_password(&format!("Password for {}: ", file_path))?;
+                return keystore.decrypt(&password);
+            }
+            WalletFile::Plain(wallet) => bs58::decode(&wallet.secret_key).into_vec()?,
+            WalletFile::SolanaCli(bytes) => bytes.clone(),
+            WalletFile::Base58(secret) => bs58::decode(secret).into_vec()?,
+        };
+        Keypair::from_bytes(&bytes).map_err(|e| format!("Invalid keypair bytes: {}", e).into())
+    }
+}
+
+/// Expands a leading `~/` to the user's home directory.
+pub fn expand_path(file_path: &str) -> PathBuf {
+    match (file_path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(file_path),
+    }
+}
+
+pub fn save_keypair(
+    keypair: &Keypair,
+    file_path: &str,
+    password: Option<&str>,
+    derivation: Option<&DerivationInfo>,
+) -> Result<(), Box<dyn Error>> {
+    let json = match password {
+        Some(password) => {
+            let mut keystore = EncryptedKeystore::encrypt(keypair, password)?;
+            keystore.derivation = derivation.cloned();
+            serde_json::to_string_pretty(&keystore)?
+        }
+        None => {
+            let wallet = WalletKeypair {
+                public_key: keypair.pubkey().to_string(),
+                secret_key: bs58::encode(keypair.to_bytes()).into_string(),
+                derivation: derivation.cloned(),
+            };
+            serde_json::to_string_pretty(&wallet)?
+        }
+    };
+    write_secret_file(file_path, &json).map_err(|e| {
+        eprintln!("Failed to save keypair at '{}': {}", file_path, e);
+        e.into()
+    })
+}
+
+/// Writes a file holding key material so that only the owner can read it,
+/// as solana-cli does for keypair files. An existing file is truncated and
+/// its permissions tightened.
+pub fn write_secret_file(file_path: &str, contents: &str) -> io::Result<()> {
+    let path = expand_path(file_path);
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path)?;
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+}
+
+/// Encodes a keypair in one of the unencrypted export formats.
+pub fn encode_keypair(keypair: &Keypair, format: KeypairFormat) -> Result<String, Box<dyn Error>> {
+    match format {
+        KeypairFormat::Scw => Ok(serde_json::to_string_pretty(&WalletKeypair {
+            public_key: keypair.pubkey().to_string(),
+            secret_key: bs58::encode(keypair.to_bytes()).into_string(),
+            derivation: None,
+        })?),
+        KeypairFormat::SolanaCli => Ok(serde_json::to_string(&keypair.to_bytes().to_vec())?),
+        KeypairFormat::Base58 => Ok(bs58::encode(keypair.to_bytes()).into_string()),
+    }
+}
+
+pub fn read_wallet_file(file_path: &str) -> Result<WalletFile, Box<dyn Error>> {
+    let data = fs::read_to_string(expand_path(file_path)).map_err(|e| {
+        eprintln!("Failed to read keypair file '{}': {}", file_path, e);
+        e
+    })?;
+    WalletFile::parse(&data)
+}
+
+pub fn read_keypair(file_path: &str) -> Result<Keypair, Box<dyn Error>> {
+    read_wallet_file(file_path)?.unlock(file_path).map_err(|e| {
+        eprintln!("Failed to decode keypair from '{}': {}", file_path, e);
+        e
+    })
+}
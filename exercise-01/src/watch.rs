@@ -0,0 +1,251 @@
+use crate::{
+    amount::format_sol,
+    output::{self, OutputFormat},
+    rpc::Rpc,
+};
+use futures_util::{stream::BoxStream, StreamExt};
+use serde::Serialize;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcAccountInfoConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::{Response, RpcLogsResponse},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, transaction::TransactionError,
+};
+use std::{error::Error, fmt, time::Duration};
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A notification printed by `watch`.
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WatchEvent {
+    Account {
+        slot: u64,
+        lamports: u64,
+        /// Change since the previous notification, or since the balance read
+        /// when the watch started.
+        delta: Option<i128>,
+        owner: String,
+        data_len: Option<u64>,
+    },
+    Logs {
+        slot: u64,
+        signature: String,
+        error: Option<TransactionError>,
+        logs: Vec<String>,
+    },
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchEvent::Account {
+                slot,
+                lamports,
+                delta,
+                ..
+            } => {
+                write!(f, "[slot {}] Balance: {} SOL", slot, format_sol(*lamports))?;
+                match delta {
+                    Some(delta) if *delta != 0 => {
+                        let sign = if *delta < 0 { "-" } else { "+" };
+                        write!(f, " ({}{})", sign, format_sol(delta.unsigned_abs() as u64))
+                    }
+                    Some(_) => write!(f, " (data changed)"),
+                    None => Ok(()),
+                }
+            }
+            WatchEvent::Logs {
+                slot,
+                signature,
+                error,
+                logs,
+            } => {
+                write!(f, "[slot {}] Transaction {}", slot, signature)?;
+                if let Some(error) = error {
+                    write!(f, " failed: {}", error)?;
+                }
+                for log in logs {
+                    write!(f, "\n  {}", log)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How a connection ended.
+enum Disconnect {
+    /// Ctrl-C or the requested number of events.
+    Stopped,
+    Lost(String),
+}
+
+struct Watcher<'a> {
+    address: &'a Pubkey,
+    logs: bool,
+    limit: Option<usize>,
+    format: OutputFormat,
+    commitment: CommitmentConfig,
+    lamports: Option<u64>,
+    events: usize,
+}
+
+impl Watcher<'_> {
+    /// Prints an event and reports whether the event limit has been reached.
+    fn emit(&mut self, event: WatchEvent) -> bool {
+        output::emit(&event, self.format);
+        self.events += 1;
+        self.limit.is_some_and(|limit| self.events >= limit)
+    }
+
+    fn account_event(&mut self, response: Response<UiAccount>) -> WatchEvent {
+        let account = response.value;
+        let delta = self
+            .lamports
+            .map(|before| account.lamports as i128 - before as i128);
+        self.lamports = Some(account.lamports);
+        WatchEvent::Account {
+            slot: response.context.slot,
+            lamports: account.lamports,
+            delta,
+            owner: account.owner,
+            data_len: account.space,
+        }
+    }
+
+    /// Streams notifications from one websocket connection until it drops.
+    async fn run(&mut self, client: &PubsubClient) -> Disconnect {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.commitment),
+            ..RpcAccountInfoConfig::default()
+        };
+        let mut accounts = match client.account_subscribe(self.address, Some(config)).await {
+            Ok((stream, _)) => stream,
+            Err(err) => return Disconnect::Lost(err.to_string()),
+        };
+        let mut logs = None;
+        if self.logs {
+            let filter = RpcTransactionLogsFilter::Mentions(vec![self.address.to_string()]);
+            match client
+                .logs_subscribe(
+                    filter,
+                    RpcTransactionLogsConfig {
+                        commitment: Some(self.commitment),
+                    },
+                )
+                .await
+            {
+                Ok((stream, _)) => logs = Some(stream),
+                Err(err) => return Disconnect::Lost(err.to_string()),
+            }
+        }
+        loop {
+            let event = tokio::select! {
+                update = accounts.next() => match update {
+                    Some(response) => self.account_event(response),
+                    None => return Disconnect::Lost("account subscription closed".to_string()),
+                },
+                update = next_logs(&mut logs) => match update {
+                    Some(response) => WatchEvent::Logs {
+                        slot: response.context.slot,
+                        signature: response.value.signature,
+                        error: response.value.err,
+                        logs: response.value.logs,
+                    },
+                    None => return Disconnect::Lost("logs subscription closed".to_string()),
+                },
+                _ = tokio::signal::ctrl_c() => return Disconnect::Stopped,
+            };
+            if self.emit(event) {
+                return Disconnect::Stopped;
+            }
+        }
+    }
+}
+
+/// Waits for the next log notification, or forever when logs are not watched.
+async fn next_logs(
+    logs: &mut Option<BoxStream<'_, Response<RpcLogsResponse>>>,
+) -> Option<Response<RpcLogsResponse>> {
+    match logs {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Streams balance changes of `address`, and with `logs` the log lines of
+/// every transaction that mentions it, until Ctrl-C or `limit` events.
+/// Dropped connections are retried with exponential backoff. Returns the
+/// number of events printed.
+pub async fn watch(
+    rpc_client: &dyn Rpc,
+    ws_url: &str,
+    address: &Pubkey,
+    logs: bool,
+    limit: Option<usize>,
+    format: OutputFormat,
+) -> Result<usize, Box<dyn Error>> {
+    let lamports = match rpc_client.get_balance(address).await {
+        Ok(lamports) => Some(lamports),
+        Err(err) => {
+            eprintln!("Could not read the starting balance: {}", err);
+            None
+        }
+    };
+    match lamports {
+        Some(lamports) => eprintln!(
+            "Watching {} (balance {} SOL). Press Ctrl-C to stop.",
+            address,
+            format_sol(lamports)
+        ),
+        None => eprintln!("Watching {}. Press Ctrl-C to stop.", address),
+    }
+    let mut watcher = Watcher {
+        address,
+        logs,
+        limit,
+        format,
+        commitment: rpc_client.commitment(),
+        lamports,
+        events: 0,
+    };
+    let mut delay = INITIAL_RECONNECT_DELAY;
+    loop {
+        match PubsubClient::new(ws_url).await {
+            Ok(client) => {
+                let events = watcher.events;
+                let disconnect = watcher.run(&client).await;
+                // Only a connection that delivered something resets the backoff.
+                if watcher.events > events {
+                    delay = INITIAL_RECONNECT_DELAY;
+                }
+                match disconnect {
+                    Disconnect::Stopped => return Ok(watcher.events),
+                    Disconnect::Lost(reason) => eprintln!(
+                        "Connection to {} lost ({}); reconnecting in {}s",
+                        ws_url,
+                        reason,
+                        delay.as_secs()
+                    ),
+                }
+            }
+            Err(err) => eprintln!(
+                "Could not connect to {} ({}); retrying in {}s",
+                ws_url,
+                err,
+                delay.as_secs()
+            ),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(watcher.events),
+        }
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
@@ -9,15 +9,18 @@ execute() {
     fi
 }
 
+# Password used to encrypt and unlock the test wallets
+export SCW_PASSWORD="test-password"
+
 # Build the project
 execute cargo build --release
 
 # Create Wallets
-execute ./target/release/scw create -f my_wallet.json
+execute ./target/release/scw create -f my_wallet.json --force
 echo "Contents of my_wallet.json:"
 cat my_wallet.json
 
-execute ./target/release/scw create -f recipient_wallet.json
+execute ./target/release/scw create -f recipient_wallet.json --force
 echo "Contents of recipient_wallet.json:"
 cat recipient_wallet.json
 
@@ -37,7 +40,7 @@ execute ./target/release/scw balance -w $MY_WALLET_PUBKEY
 execute ./target/release/scw balance -w $RECIPIENT_WALLET_PUBKEY
 
 # Transfer SOL
-execute ./target/release/scw transfer -f my_wallet.json -t $RECIPIENT_WALLET_PUBKEY -a 0.5
+execute ./target/release/scw transfer -f my_wallet.json -t $RECIPIENT_WALLET_PUBKEY -a 0.5 --yes
 
 # Check Balance Again
 execute ./target/release/scw balance -w $MY_WALLET_PUBKEY
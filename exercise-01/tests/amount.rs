@@ -0,0 +1,96 @@
+//! Parsing and scaling of command line amounts without floating point.
+
+use scw::amount::{self, Amount};
+use std::str::FromStr;
+
+fn amount(value: &str) -> Amount {
+    Amount::from_str(value).unwrap()
+}
+
+#[test]
+fn amounts_scale_to_base_units() {
+    for (value, decimals, units) in [
+        ("1", 9, 1_000_000_000),
+        ("0.000000001", 9, 1),
+        ("1.5", 9, 1_500_000_000),
+        (".5", 9, 500_000_000),
+        ("5.", 9, 5_000_000_000),
+        ("0.1", 9, 100_000_000),
+        ("0.30", 2, 30),
+        ("1.500000000000", 9, 1_500_000_000),
+        ("007", 0, 7),
+        ("0", 6, 0),
+        ("0.000", 6, 0),
+        ("18446744073709551615", 0, u64::MAX),
+        ("18446744073.709551615", 9, u64::MAX),
+    ] {
+        assert_eq!(
+            amount(value).to_units(decimals).unwrap(),
+            units,
+            "{} with {} decimals",
+            value,
+            decimals
+        );
+    }
+}
+
+#[test]
+fn over_precise_and_oversized_amounts_are_rejected() {
+    for (value, decimals, error) in [
+        (
+            "0.0000000001",
+            9,
+            "Amount 0.0000000001 has more than 9 decimal places",
+        ),
+        ("1.001", 2, "Amount 1.001 has more than 2 decimal places"),
+        ("1.5", 0, "Amount 1.5 has more than 0 decimal places"),
+        (
+            "18446744073709551616",
+            0,
+            "Amount 18446744073709551616 is too large",
+        ),
+        (
+            "18446744073.709551616",
+            9,
+            "Amount 18446744073.709551616 is too large",
+        ),
+        ("ALL", 9, "ALL is not supported for this command"),
+    ] {
+        let err = amount(value).to_units(decimals).unwrap_err();
+        assert_eq!(err.to_string(), error);
+    }
+}
+
+#[test]
+fn only_decimals_and_all_parse() {
+    assert!(amount("ALL").is_all());
+    assert!(amount("all").is_all());
+    assert_eq!(amount("1.50").to_string(), "1.50");
+    for value in ["", ".", "-1", "1e9", "1,5", "1.2.3", " 1", "0x10", "one"] {
+        assert!(Amount::from_str(value).is_err(), "{:?} parsed", value);
+    }
+}
+
+#[test]
+fn lamports_flag_takes_whole_lamports() {
+    assert_eq!(amount("1.5").to_lamports(false).unwrap(), 1_500_000_000);
+    assert_eq!(amount("1500").to_lamports(true).unwrap(), 1_500);
+    assert!(amount("1.5").to_lamports(true).is_err());
+    assert_eq!(amount("1.0").to_lamports(true).unwrap(), 1);
+}
+
+#[test]
+fn units_format_without_trailing_zeros() {
+    for (units, decimals, formatted) in [
+        (0, 9, "0"),
+        (1, 9, "0.000000001"),
+        (1_500_000_000, 9, "1.5"),
+        (2_000_000_000, 9, "2"),
+        (30, 2, "0.3"),
+        (7, 0, "7"),
+        (u64::MAX, 9, "18446744073.709551615"),
+    ] {
+        assert_eq!(amount::format_units(units, decimals), formatted);
+    }
+    assert_eq!(amount::format_sol(5_000), "0.000005");
+}
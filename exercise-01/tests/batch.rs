@@ -0,0 +1,221 @@
+//! `batch-transfer` runs against `MockRpc`: expiry, resends and resuming
+//! from the journal.
+
+mod common;
+
+use common::{confirmed, funded_keypair};
+use scw::{
+    batch::{self, Payout},
+    mock::MockRpc,
+    rpc::Rpc,
+    transaction::{self, BlockhashSource},
+};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    system_instruction,
+};
+use std::fs;
+use tempfile::TempDir;
+
+/// Payouts of 1000 and 2000 lamports read from a CSV in `dir`.
+fn payouts(dir: &TempDir) -> Vec<Payout> {
+    let csv = dir.path().join("payouts.csv");
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    fs::write(
+        &csv,
+        format!("recipient,amount\n{},1000\n{},2000\n", first, second),
+    )
+    .unwrap();
+    batch::read_payouts(csv.to_str().unwrap(), true, |value| Ok(value.parse()?)).unwrap()
+}
+
+/// The `status` column of the results file, one entry per payout.
+fn statuses(dir: &TempDir, payouts: &[Payout], journal: &str) -> Vec<String> {
+    let results = dir.path().join("results.csv");
+    let results = results.to_str().unwrap();
+    batch::write_results(results, payouts, journal).unwrap();
+    csv::Reader::from_path(results)
+        .unwrap()
+        .records()
+        .map(|record| record.unwrap()[3].to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn expired_batches_are_resent() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let dir = TempDir::new().unwrap();
+    let payouts = payouts(&dir);
+    let journal = dir.path().join("journal.jsonl");
+    let journal = journal.to_str().unwrap();
+
+    rpc.drop_next_transaction();
+    batch::run_batch(&rpc, &sender, &payouts, journal, &confirmed(), 1)
+        .await
+        .unwrap();
+
+    assert_eq!(rpc.sent_transactions().len(), 2);
+    assert_eq!(rpc.balance(&payouts[0].recipient), 1_000);
+    assert_eq!(rpc.balance(&payouts[1].recipient), 2_000);
+    assert_eq!(
+        statuses(&dir, &payouts, journal),
+        ["confirmed", "confirmed"]
+    );
+    let journaled: Vec<String> = fs::read_to_string(journal)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+            entry["status"].as_str().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(journaled, ["sent", "expired", "sent", "confirmed"]);
+}
+
+#[tokio::test]
+async fn retries_are_limited() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let dir = TempDir::new().unwrap();
+    let payouts = payouts(&dir);
+    let journal = dir.path().join("journal.jsonl");
+    let journal = journal.to_str().unwrap();
+
+    rpc.drop_transactions();
+    batch::run_batch(&rpc, &sender, &payouts, journal, &confirmed(), 2)
+        .await
+        .unwrap();
+
+    assert_eq!(rpc.sent_transactions().len(), 3);
+    assert_eq!(rpc.balance(&payouts[0].recipient), 0);
+    assert_eq!(statuses(&dir, &payouts, journal), ["expired", "expired"]);
+}
+
+#[tokio::test]
+async fn batch_landing_at_expiry_is_not_paid_again() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let dir = TempDir::new().unwrap();
+    let payouts = payouts(&dir);
+    let journal = dir.path().join("journal.jsonl");
+    let journal = journal.to_str().unwrap();
+
+    rpc.land_at_expiry();
+    batch::run_batch(&rpc, &sender, &payouts, journal, &confirmed(), 3)
+        .await
+        .unwrap();
+
+    assert_eq!(rpc.sent_transactions().len(), 1);
+    assert_eq!(rpc.balance(&payouts[0].recipient), 1_000);
+    assert_eq!(rpc.balance(&payouts[1].recipient), 2_000);
+    assert_eq!(
+        statuses(&dir, &payouts, journal),
+        ["confirmed", "confirmed"]
+    );
+}
+
+#[tokio::test]
+async fn resumed_runs_settle_batches_left_as_sent() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let dir = TempDir::new().unwrap();
+    let payouts = payouts(&dir);
+    let journal = dir.path().join("journal.jsonl");
+    let journal = journal.to_str().unwrap();
+
+    // The first row was paid but the run stopped before journaling the
+    // outcome. The second row's transaction never reached the cluster.
+    let (_, last_valid_block_height) = rpc.get_latest_blockhash().await.unwrap();
+    let landed = transaction::send_instructions(
+        &rpc,
+        &[system_instruction::transfer(
+            &sender.pubkey(),
+            &payouts[0].recipient,
+            payouts[0].lamports,
+        )],
+        &[&sender],
+        &BlockhashSource::default(),
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    let lost = Keypair::new().sign_message(b"never sent");
+    let entry = |payout: &Payout, signature: String, last_valid: u64| {
+        serde_json::json!({
+            "rows": [{
+                "row": payout.row,
+                "recipient": payout.recipient.to_string(),
+                "lamports": payout.lamports,
+            }],
+            "signature": signature,
+            "last_valid_block_height": last_valid,
+            "status": "sent",
+        })
+    };
+    fs::write(
+        journal,
+        format!(
+            "{}\n{}\n",
+            entry(
+                &payouts[0],
+                landed.signature.to_string(),
+                last_valid_block_height
+            ),
+            entry(&payouts[1], lost.to_string(), 0),
+        ),
+    )
+    .unwrap();
+
+    batch::run_batch(&rpc, &sender, &payouts, journal, &confirmed(), 0)
+        .await
+        .unwrap();
+
+    assert_eq!(rpc.balance(&payouts[0].recipient), 1_000);
+    assert_eq!(rpc.balance(&payouts[1].recipient), 2_000);
+    let sent = rpc.sent_transactions();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(
+        sent[1].message.static_account_keys()[1..3],
+        [payouts[1].recipient, solana_sdk::system_program::id()]
+    );
+    assert_eq!(
+        statuses(&dir, &payouts, journal),
+        ["confirmed", "confirmed"]
+    );
+}
+
+#[tokio::test]
+async fn torn_journal_lines_are_dropped_before_resuming() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let dir = TempDir::new().unwrap();
+    let payouts = payouts(&dir);
+    let journal = dir.path().join("journal.jsonl");
+    let journal = journal.to_str().unwrap();
+
+    rpc.drop_next_transaction();
+    batch::run_batch(&rpc, &sender, &payouts, journal, &confirmed(), 0)
+        .await
+        .unwrap();
+    assert_eq!(statuses(&dir, &payouts, journal), ["expired", "expired"]);
+    // The run crashed halfway through writing the next entry.
+    let mut contents = fs::read_to_string(journal).unwrap();
+    contents.push_str("{\"rows\":[{\"row\":1,");
+    fs::write(journal, contents).unwrap();
+
+    for _ in 0..2 {
+        batch::run_batch(&rpc, &sender, &payouts, journal, &confirmed(), 0)
+            .await
+            .unwrap();
+    }
+    assert_eq!(rpc.sent_transactions().len(), 2);
+    assert_eq!(rpc.balance(&payouts[0].recipient), 1_000);
+    assert_eq!(
+        statuses(&dir, &payouts, journal),
+        ["confirmed", "confirmed"]
+    );
+    for line in fs::read_to_string(journal).unwrap().lines() {
+        serde_json::from_str::<serde_json::Value>(line).unwrap();
+    }
+}
@@ -0,0 +1,467 @@
+//! Runs the `scw` binary against a temporary config directory. These cover
+//! the commands that work without a cluster.
+
+use scw::{
+    config,
+    keystore::PASSWORD_ENV,
+    registry::CONFIG_DIR_ENV,
+    transaction::{self, TxEncoding},
+};
+use serde_json::Value;
+use solana_sdk::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{Message, MessageHeader, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use std::{fs, path::Path, process::Command};
+use tempfile::TempDir;
+
+struct Scw {
+    dir: TempDir,
+}
+
+impl Scw {
+    fn new() -> Self {
+        Scw {
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_scw"));
+        command
+            .args(args)
+            .current_dir(self.dir.path())
+            .env(CONFIG_DIR_ENV, self.dir.path().join("config"))
+            .env(PASSWORD_ENV, "correct horse")
+            .env_remove(config::PROFILE_ENV)
+            .env_remove(config::RPC_URL_ENV);
+        command
+    }
+
+    /// Runs with `--output json` and returns the exit status and parsed stdout.
+    fn json(&self, args: &[&str]) -> (i32, Value) {
+        let mut all_args = vec!["--output", "json"];
+        all_args.extend_from_slice(args);
+        let output = self.command(&all_args).output().unwrap();
+        let value = serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+            panic!(
+                "invalid JSON from {:?}: {}\nstdout: {}\nstderr: {}",
+                args,
+                e,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )
+        });
+        (output.status.code().unwrap(), value)
+    }
+
+    /// Runs a command expected to succeed and returns its JSON result.
+    fn ok(&self, args: &[&str]) -> Value {
+        let (status, value) = self.json(args);
+        assert_eq!(status, 0, "{:?} failed: {}", args, value);
+        value
+    }
+
+    fn create_wallet(&self, name: &str) -> String {
+        let created = self.ok(&["create", "-f", name]);
+        created["address"].as_str().unwrap().to_string()
+    }
+}
+
+#[test]
+fn named_wallets_resolve_anywhere_an_address_is_taken() {
+    let scw = Scw::new();
+    let alice = scw.create_wallet("alice");
+    let bob = scw.create_wallet("bob");
+
+    assert_eq!(scw.ok(&["address", "-w", "alice"])["address"], alice);
+    let wallets = scw.ok(&["list-wallets"]);
+    let rows = wallets["wallets"].as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["name"], "alice");
+    assert_eq!(rows[0]["default"], true);
+    assert_eq!(rows[1]["address"], bob);
+}
+
+#[test]
+fn unreadable_wallets_are_listed_as_errors() {
+    let scw = Scw::new();
+    let alice = scw.create_wallet("alice");
+    let bob = scw.ok(&["create", "-f", "bob"]);
+    fs::write(bob["path"].as_str().unwrap(), "not a keypair").unwrap();
+
+    let wallets = scw.ok(&["list-wallets"]);
+    let rows = wallets["wallets"].as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["address"], alice);
+    assert!(rows[0]["error"].is_null());
+    assert_eq!(rows[1]["name"], "bob");
+    assert!(rows[1]["address"].is_null());
+    assert!(rows[1]["error"].is_string());
+}
+
+#[test]
+fn existing_wallets_are_only_overwritten_with_force() {
+    let scw = Scw::new();
+    let alice = scw.create_wallet("alice");
+    let (status, error) = scw.json(&["create", "-f", "alice"]);
+    assert_eq!(status, 1);
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("--force"));
+    assert_eq!(scw.ok(&["address", "-w", "alice"])["address"], alice);
+    let replaced = scw.ok(&["create", "-f", "alice", "--force"]);
+    assert_ne!(replaced["address"], alice);
+
+    let path = scw.path("wallet.json");
+    let first = scw.ok(&["create", "-f", &path]);
+    for args in [
+        vec!["create", "-f", &path],
+        vec!["grind", "--starts-with", "A", "-f", &path],
+        vec!["import", "-i", &path, "-f", &path],
+    ] {
+        let (status, _) = scw.json(&args);
+        assert_eq!(status, 1, "{:?} overwrote the wallet", args);
+    }
+    assert_eq!(
+        scw.ok(&["address", "-w", &path])["address"],
+        first["address"]
+    );
+    let imported = scw.ok(&["import", "-i", &path, "-f", &path, "--force"]);
+    assert_eq!(imported["address"], first["address"]);
+}
+
+#[test]
+fn contacts_are_usable_as_aliases() {
+    let scw = Scw::new();
+    let bob = scw.create_wallet("bob");
+
+    scw.ok(&["contacts", "add", "friend", &bob, "--label", "Bob"]);
+    assert_eq!(scw.ok(&["address", "-w", "@friend"])["address"], bob);
+    assert_eq!(
+        scw.ok(&["contacts", "list"])["contacts"]["friend"]["label"],
+        "Bob"
+    );
+
+    let (status, error) = scw.json(&["contacts", "add", "@friend", &bob]);
+    assert_eq!(status, 1);
+    assert_eq!(error["error"]["code"], "error");
+
+    scw.ok(&["contacts", "remove", "friend"]);
+    let (status, error) = scw.json(&["address", "-w", "@friend"]);
+    assert_eq!(status, 1);
+    assert_eq!(error["error"]["message"], "Unknown contact: @friend");
+}
+
+#[test]
+fn unsigned_transfer_is_signed_offline() {
+    let scw = Scw::new();
+    let alice = scw.create_wallet("alice");
+    let bob = scw.create_wallet("bob");
+    let file = scw.path("transfer.tx");
+
+    let unsigned = scw.ok(&[
+        "transfer",
+        "--unsigned",
+        &file,
+        "--blockhash",
+        "11111111111111111111111111111111",
+        "--from",
+        "alice",
+        "-t",
+        "bob",
+        "-a",
+        "0.5",
+    ]);
+    assert_eq!(unsigned["from"], alice);
+    assert_eq!(unsigned["to"], bob);
+    assert_eq!(unsigned["lamports"], 500_000_000);
+
+    let output = scw
+        .command(&["--output", "json", "sign", &file, "-s", "alice"])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let signed: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(signed["fully_signed"], true);
+    assert_eq!(signed["transaction"]["fee_payer"], alice);
+    assert_eq!(signed["transaction"]["signers"][0]["signed"], true);
+    assert!(Path::new(&file).exists());
+}
+
+#[test]
+fn only_required_signers_are_unlocked() {
+    for default_wallet in [false, true] {
+        let scw = Scw::new();
+        if default_wallet {
+            scw.create_wallet("alice");
+        }
+        let signer = scw.path("signer.json");
+        scw.ok(&["create", "-f", &signer, "--plaintext"]);
+        let file = scw.path("transfer.tx");
+        scw.ok(&[
+            "transfer",
+            "--unsigned",
+            &file,
+            "--blockhash",
+            "11111111111111111111111111111111",
+            "--from",
+            &signer,
+            "-t",
+            "2SS7vdzGspD7jdbZ94RGEqmHNbSfoP7eaNJtwgibDJuP",
+            "-a",
+            "0.5",
+        ]);
+
+        // A wrong password would fail if the default wallet were unlocked.
+        let output = scw
+            .command(&["--output", "json", "sign", &file, "-s", &signer])
+            .env(PASSWORD_ENV, "wrong password")
+            .stdin(std::process::Stdio::null())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let signed: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(signed["fully_signed"], true);
+    }
+}
+
+#[test]
+fn malformed_transactions_are_rejected_before_signing() {
+    let scw = Scw::new();
+    scw.create_wallet("alice");
+    // The instruction's program index points past the only account key.
+    let message = Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 0,
+        },
+        account_keys: vec![Pubkey::new_unique()],
+        recent_blockhash: Hash::default(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(
+            5,
+            Vec::new(),
+            Vec::new(),
+        )],
+    };
+    let malformed = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::Legacy(message),
+    };
+    let file = scw.path("malformed.tx");
+    fs::write(
+        &file,
+        transaction::encode_transaction(&malformed, TxEncoding::Base64).unwrap(),
+    )
+    .unwrap();
+
+    for command in ["sign", "broadcast"] {
+        let (status, error) = scw.json(&[command, &file]);
+        assert_eq!(status, 1, "{} exited with {}: {}", command, status, error);
+        assert!(error["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid transaction"));
+    }
+}
+
+#[test]
+fn off_curve_recipients_are_refused() {
+    let scw = Scw::new();
+    scw.create_wallet("alice");
+    // Not a point on the ed25519 curve, like every program-derived address.
+    let pda = "6y44xmfACgTtHzfvDeBHsbhNsiABffR2PeA8NoT4tjfv";
+    let args = [
+        "transfer",
+        "--unsigned",
+        "transfer.tx",
+        "--blockhash",
+        "11111111111111111111111111111111",
+        "-t",
+        pda,
+        "-a",
+        "1",
+    ];
+
+    let (status, error) = scw.json(&args);
+    assert_eq!(status, 1);
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("--allow-off-curve"));
+
+    let mut allowed = args.to_vec();
+    allowed.push("--allow-off-curve");
+    assert_eq!(scw.ok(&allowed)["to"], pda);
+}
+
+#[test]
+fn batch_recipients_are_resolved_and_checked_before_sending() {
+    let scw = Scw::new();
+    let bob = scw.create_wallet("bob");
+    scw.ok(&["contacts", "add", "friend", &bob]);
+    let pda = "6y44xmfACgTtHzfvDeBHsbhNsiABffR2PeA8NoT4tjfv";
+
+    fs::write(
+        scw.path("unknown.csv"),
+        "recipient,amount\n@friend,1\n@stranger,2\n",
+    )
+    .unwrap();
+    let (status, error) = scw.json(&["batch-transfer", "unknown.csv"]);
+    assert_eq!(status, 1);
+    assert_eq!(
+        error["error"]["message"],
+        "Row 2: invalid recipient '@stranger': Unknown contact: @stranger"
+    );
+
+    fs::write(
+        scw.path("pda.csv"),
+        format!("recipient,amount\n@friend,1\nbob,1\n{},2\n", pda),
+    )
+    .unwrap();
+    let (status, error) = scw.json(&["batch-transfer", "pda.csv"]);
+    assert_eq!(status, 1);
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("--allow-off-curve"));
+}
+
+#[test]
+fn websocket_port_is_the_next_port_up() {
+    assert_eq!(
+        config::websocket_url("http://127.0.0.1:8899").unwrap(),
+        "ws://127.0.0.1:8900/"
+    );
+    assert_eq!(
+        config::websocket_url("https://api.devnet.solana.com").unwrap(),
+        "wss://api.devnet.solana.com/"
+    );
+    assert!(config::websocket_url("http://127.0.0.1:65535").is_err());
+
+    let scw = Scw::new();
+    scw.ok(&["set-config", "-u", "http://127.0.0.1:65535"]);
+    let (status, error) = scw.json(&["profile", "show"]);
+    assert_eq!(status, 1, "{}", error);
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("--ws-url"));
+    scw.ok(&[
+        "set-config",
+        "-u",
+        "http://127.0.0.1:65535",
+        "--ws-url",
+        "ws://127.0.0.1:9000",
+    ]);
+    scw.ok(&["profile", "show"]);
+}
+
+#[test]
+fn corrupt_config_is_an_error() {
+    let scw = Scw::new();
+    fs::create_dir_all(scw.path("config")).unwrap();
+    fs::write(scw.path("config/config.json"), "{ not json").unwrap();
+
+    let (status, error) = scw.json(&["list-wallets"]);
+    assert_eq!(status, 1);
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Invalid config file"));
+}
+
+#[test]
+fn empty_password_is_refused_for_new_wallets() {
+    let scw = Scw::new();
+    let output = scw
+        .command(&["--output", "json", "create", "-f", "alice"])
+        .env(PASSWORD_ENV, "")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let error: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(error["error"]["message"], "SCW_PASSWORD is set but empty");
+    let wallets = scw.ok(&["list-wallets"]);
+    assert!(wallets["wallets"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn nonce_show_needs_no_wallet() {
+    let scw = Scw::new();
+    // Without a cluster the lookup fails with an RPC error rather than on
+    // the missing default wallet.
+    let (status, error) = scw.json(&["nonce", "show", &Pubkey::new_unique().to_string()]);
+    assert_eq!(error["error"]["code"], "rpc_error", "{}", error);
+    assert_eq!(status, 3);
+}
+
+#[cfg(unix)]
+#[test]
+fn key_files_are_only_readable_by_the_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let scw = Scw::new();
+    let encrypted = scw.ok(&["create", "-f", "alice"]);
+    let plaintext = scw.path("plain.json");
+    // An existing world-readable file is tightened when overwritten.
+    fs::write(&plaintext, "").unwrap();
+    fs::set_permissions(&plaintext, fs::Permissions::from_mode(0o644)).unwrap();
+    scw.ok(&["create", "-f", &plaintext, "--plaintext", "--force"]);
+    let exported = scw.path("exported.json");
+    scw.ok(&["export", "-f", "alice", "-o", &exported]);
+
+    for path in [encrypted["path"].as_str().unwrap(), &plaintext, &exported] {
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "{}", path);
+    }
+}
+
+#[test]
+fn signed_messages_verify() {
+    let scw = Scw::new();
+    let alice = scw.create_wallet("alice");
+    scw.create_wallet("bob");
+
+    let signed = scw.ok(&["sign-message", "hello"]);
+    assert_eq!(signed["signer"], alice);
+    let signature = signed["signature"].as_str().unwrap();
+
+    let verified = scw.ok(&["verify-message", "hello", "-s", signature]);
+    assert_eq!(verified["valid"], true);
+
+    let (status, _) = scw.json(&[
+        "verify-message",
+        "hello",
+        "-s",
+        signature,
+        "--signer",
+        "bob",
+    ]);
+    assert_ne!(status, 0);
+    let (status, _) = scw.json(&["verify-message", "goodbye", "-s", signature]);
+    assert_ne!(status, 0);
+}
+
+#[test]
+fn invalid_addresses_exit_with_status_2() {
+    let scw = Scw::new();
+    let (status, error) = scw.json(&["stake", "show", "not-an-address"]);
+    assert_eq!(status, 2);
+    assert_eq!(error["error"]["code"], "invalid_argument");
+}
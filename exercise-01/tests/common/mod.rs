@@ -0,0 +1,20 @@
+//! Helpers shared by the integration tests that run against `MockRpc`.
+#![allow(dead_code)]
+
+use scw::{mock::MockRpc, transaction::SendOptions};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+/// Sends without asking for confirmation, as `--yes` does.
+pub fn confirmed() -> SendOptions {
+    SendOptions {
+        yes: true,
+        ..SendOptions::default()
+    }
+}
+
+/// A new keypair holding `lamports`.
+pub fn funded_keypair(rpc: &MockRpc, lamports: u64) -> Keypair {
+    let keypair = Keypair::new();
+    rpc.fund(&keypair.pubkey(), lamports);
+    keypair
+}
@@ -0,0 +1,72 @@
+//! `history` decoding transactions landed in `MockRpc`.
+
+mod common;
+
+use common::{confirmed, funded_keypair};
+use scw::{
+    history::{self, HistoryFormat},
+    mock::{MockRpc, LAMPORTS_PER_SIGNATURE},
+    transaction::BlockhashSource,
+    transfer,
+};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer,
+};
+
+async fn send(rpc: &MockRpc, sender: &Keypair, recipient: &Pubkey, lamports: u64) -> String {
+    let receipt = transfer::transfer_funds(
+        rpc,
+        sender,
+        recipient,
+        lamports,
+        &BlockhashSource::default(),
+        None,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    receipt.signature.to_string()
+}
+
+#[tokio::test]
+async fn history_lists_transfers_newest_first() {
+    let rpc = MockRpc::new();
+    let wallet = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let other = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let first = send(&rpc, &wallet, &other.pubkey(), 1_000_000).await;
+    let unrelated = Keypair::new().pubkey();
+    send(&rpc, &other, &unrelated, 1_000).await;
+    let second = send(&rpc, &other, &wallet.pubkey(), 250_000).await;
+
+    let entries = history::get_history(&rpc, &wallet.pubkey(), 10, None)
+        .await
+        .unwrap();
+    let signatures: Vec<&str> = entries.iter().map(|e| e.signature.as_str()).collect();
+    assert_eq!(signatures, [second.as_str(), first.as_str()]);
+    assert_eq!(entries[0].change_lamports, 250_000);
+    assert_eq!(
+        entries[1].change_lamports,
+        -(1_000_000 + LAMPORTS_PER_SIGNATURE as i64)
+    );
+    assert_eq!(entries[1].fee_lamports, LAMPORTS_PER_SIGNATURE);
+    assert_eq!(entries[1].status, "ok");
+    assert_eq!(
+        entries[1].instructions,
+        [format!(
+            "system transfer 0.001 SOL from {} to {}",
+            wallet.pubkey(),
+            other.pubkey()
+        )]
+    );
+
+    let older = history::get_history(&rpc, &wallet.pubkey(), 10, Some(second.parse().unwrap()))
+        .await
+        .unwrap();
+    assert_eq!(older.len(), 1);
+    assert_eq!(older[0].signature, first);
+
+    let mut csv = Vec::new();
+    history::write_history(&entries[..1], HistoryFormat::Csv, &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.lines().nth(1).unwrap().starts_with(&second), "{}", csv);
+}
@@ -0,0 +1,178 @@
+//! Version 0 transactions compiled against lookup tables, sent to `MockRpc`.
+
+mod common;
+
+use common::funded_keypair;
+use scw::{
+    alt,
+    mock::{MockRpc, LAMPORTS_PER_SIGNATURE, SLOTS_PER_EPOCH},
+    transaction::{self, BlockhashSource, SendOptions},
+};
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::{
+        self,
+        state::{AddressLookupTable, LookupTableMeta},
+    },
+    instruction::Instruction,
+    message::VersionedMessage,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signer::Signer,
+    slot_hashes::MAX_ENTRIES,
+    system_instruction,
+};
+use std::borrow::Cow;
+
+/// Stores a lookup table holding `addresses` and returns its address.
+fn lookup_table(rpc: &MockRpc, authority: &Pubkey, addresses: &[Pubkey]) -> Pubkey {
+    store_table(rpc, LookupTableMeta::new(*authority), addresses)
+}
+
+fn store_table(rpc: &MockRpc, meta: LookupTableMeta, addresses: &[Pubkey]) -> Pubkey {
+    let table = AddressLookupTable {
+        meta,
+        addresses: Cow::Borrowed(addresses),
+    };
+    let key = Pubkey::new_unique();
+    rpc.set_account(
+        &key,
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data: table.serialize_for_tests().unwrap(),
+            owner: address_lookup_table::program::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    key
+}
+
+fn payouts(sender: &Pubkey, recipients: &[Pubkey], lamports: u64) -> Vec<Instruction> {
+    recipients
+        .iter()
+        .map(|recipient| system_instruction::transfer(sender, recipient, lamports))
+        .collect()
+}
+
+#[tokio::test]
+async fn lookup_tables_produce_version_0_transactions() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let recipients: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+    let table = lookup_table(&rpc, &sender.pubkey(), &recipients);
+    let options = SendOptions {
+        yes: true,
+        lookup_tables: vec![table],
+        ..SendOptions::default()
+    };
+
+    // Forty transfers do not fit in a legacy transaction.
+    transaction::send_instructions(
+        &rpc,
+        &payouts(&sender.pubkey(), &recipients, 1_000),
+        &[&sender],
+        &BlockhashSource::default(),
+        &options,
+    )
+    .await
+    .unwrap();
+
+    let sent = &rpc.sent_transactions()[0];
+    let VersionedMessage::V0(message) = &sent.message else {
+        panic!("expected a version 0 message");
+    };
+    assert_eq!(message.address_table_lookups[0].account_key, table);
+    assert_eq!(message.address_table_lookups[0].writable_indexes.len(), 40);
+    assert!(bincode::serialized_size(sent).unwrap() <= 1232);
+    for recipient in &recipients {
+        assert_eq!(rpc.balance(recipient), 1_000);
+    }
+    assert_eq!(
+        rpc.balance(&sender.pubkey()),
+        LAMPORTS_PER_SOL - 40 * 1_000 - LAMPORTS_PER_SIGNATURE
+    );
+}
+
+#[tokio::test]
+async fn accounts_missing_from_the_table_stay_static() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let in_table = Pubkey::new_unique();
+    let outside = Pubkey::new_unique();
+    let table = lookup_table(&rpc, &sender.pubkey(), &[in_table]);
+    let options = SendOptions {
+        yes: true,
+        lookup_tables: vec![table],
+        ..SendOptions::default()
+    };
+
+    transaction::send_instructions(
+        &rpc,
+        &payouts(&sender.pubkey(), &[in_table, outside], 1_000),
+        &[&sender],
+        &BlockhashSource::default(),
+        &options,
+    )
+    .await
+    .unwrap();
+
+    let message = &rpc.sent_transactions()[0].message;
+    assert!(message.static_account_keys().contains(&outside));
+    assert!(!message.static_account_keys().contains(&in_table));
+    assert_eq!(rpc.balance(&in_table), 1_000);
+    assert_eq!(rpc.balance(&outside), 1_000);
+}
+
+#[tokio::test]
+async fn unknown_lookup_table_is_an_error() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let missing = Pubkey::new_unique();
+    let options = SendOptions {
+        yes: true,
+        lookup_tables: vec![missing],
+        ..SendOptions::default()
+    };
+
+    let err = transaction::send_instructions(
+        &rpc,
+        &payouts(&sender.pubkey(), &[Pubkey::new_unique()], 1_000),
+        &[&sender],
+        &BlockhashSource::default(),
+        &options,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Lookup table {} not found", missing)
+    );
+    assert!(rpc.sent_transactions().is_empty());
+}
+
+#[tokio::test]
+async fn show_reports_when_a_deactivated_table_can_be_closed() {
+    let rpc = MockRpc::new();
+    let authority = Pubkey::new_unique();
+    let meta = LookupTableMeta {
+        deactivation_slot: 0,
+        ..LookupTableMeta::new(authority)
+    };
+    let table = store_table(&rpc, meta, &[Pubkey::new_unique()]);
+
+    let info = alt::get_lookup_table_info(&rpc, &table).await.unwrap();
+    assert_eq!(info.authority, Some(authority.to_string()));
+    assert_eq!(info.addresses.len(), 1);
+    assert_eq!(
+        info.status,
+        format!(
+            "deactivating since slot 0, can be closed in about {} slots",
+            MAX_ENTRIES + 1
+        )
+    );
+
+    rpc.warp_to_epoch(MAX_ENTRIES as u64 / SLOTS_PER_EPOCH + 1);
+    let info = alt::get_lookup_table_info(&rpc, &table).await.unwrap();
+    assert_eq!(info.status, "deactivated at slot 0, can be closed");
+}
@@ -0,0 +1,145 @@
+//! Durable nonce accounts and nonce-based transfers against `MockRpc`.
+
+mod common;
+
+use common::{confirmed, funded_keypair};
+use scw::{
+    mock::{MockRpc, LAMPORTS_PER_SIGNATURE},
+    nonce,
+    transaction::{BlockhashSource, NonceSource},
+    transfer,
+};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, nonce::State, rent::Rent, signature::Keypair, signer::Signer,
+};
+
+/// A funded wallet and the nonce account it is the authority of.
+async fn setup(rpc: &MockRpc) -> (Keypair, Keypair) {
+    let wallet = funded_keypair(rpc, LAMPORTS_PER_SOL);
+    let nonce_account = Keypair::new();
+    nonce::create_nonce_account(
+        rpc,
+        &wallet,
+        &nonce_account,
+        &wallet.pubkey(),
+        1_000,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    (wallet, nonce_account)
+}
+
+#[tokio::test]
+async fn transfers_use_and_advance_the_nonce() {
+    let rpc = MockRpc::new();
+    let (wallet, nonce_account) = setup(&rpc).await;
+    let info = nonce::get_nonce_info(&rpc, &nonce_account.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(info.authority, wallet.pubkey().to_string());
+    assert_eq!(
+        info.lamports,
+        Rent::default().minimum_balance(State::size()) + 1_000
+    );
+    assert_eq!(info.lamports_per_signature, LAMPORTS_PER_SIGNATURE);
+
+    let recipient = Keypair::new().pubkey();
+    let source = BlockhashSource {
+        blockhash: None,
+        nonce: Some(NonceSource {
+            account: nonce_account.pubkey(),
+            authority: wallet.pubkey(),
+        }),
+    };
+    transfer::transfer_funds(
+        &rpc,
+        &wallet,
+        &recipient,
+        5_000,
+        &source,
+        None,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    let sent = rpc.sent_transactions();
+    assert_eq!(
+        sent.last().unwrap().message.recent_blockhash().to_string(),
+        info.nonce
+    );
+    assert_eq!(rpc.balance(&recipient), 5_000);
+
+    let advanced = nonce::get_nonce_info(&rpc, &nonce_account.pubkey())
+        .await
+        .unwrap();
+    assert_ne!(advanced.nonce, info.nonce);
+}
+
+#[tokio::test]
+async fn only_the_current_authority_can_use_the_nonce() {
+    let rpc = MockRpc::new();
+    let (wallet, nonce_account) = setup(&rpc).await;
+    let new_authority = Keypair::new();
+    nonce::authorize_nonce(
+        &rpc,
+        &wallet,
+        &nonce_account.pubkey(),
+        &wallet,
+        &new_authority.pubkey(),
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+
+    let err = nonce::advance_nonce(
+        &rpc,
+        &wallet,
+        &nonce_account.pubkey(),
+        &wallet,
+        &confirmed(),
+    )
+    .await;
+    assert!(err.is_err());
+    nonce::advance_nonce(
+        &rpc,
+        &wallet,
+        &nonce_account.pubkey(),
+        &new_authority,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn withdrawals_keep_the_account_rent_exempt() {
+    let rpc = MockRpc::new();
+    let (wallet, nonce_account) = setup(&rpc).await;
+    let recipient = Keypair::new().pubkey();
+    let nonce_pubkey = nonce_account.pubkey();
+    let options = confirmed();
+    let withdraw = |lamports| {
+        nonce::withdraw_from_nonce(
+            &rpc,
+            &wallet,
+            &nonce_pubkey,
+            &wallet,
+            &recipient,
+            lamports,
+            &options,
+        )
+    };
+
+    assert!(withdraw(2_000).await.is_err());
+    withdraw(1_000).await.unwrap();
+    assert_eq!(rpc.balance(&recipient), 1_000);
+
+    withdraw(rpc.balance(&nonce_account.pubkey()))
+        .await
+        .unwrap();
+    assert_eq!(rpc.balance(&nonce_account.pubkey()), 0);
+    assert!(nonce::get_nonce_info(&rpc, &nonce_account.pubkey())
+        .await
+        .is_err());
+}
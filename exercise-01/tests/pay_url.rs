@@ -0,0 +1,215 @@
+//! Solana Pay transfer request URLs and the transfers that pay them.
+
+mod common;
+
+use common::{confirmed, funded_keypair};
+use scw::{
+    amount::Amount,
+    mock::MockRpc,
+    pay::{self, TransferRequest},
+    rpc::Rpc,
+    token,
+    transaction::{self, BlockhashSource},
+};
+use solana_sdk::{
+    instruction::AccountMeta, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair,
+    signer::Signer, system_program,
+};
+use std::str::FromStr;
+
+fn request(recipient: Pubkey) -> TransferRequest {
+    TransferRequest {
+        recipient,
+        amount: Some(Amount::from_str("0.5").unwrap()),
+        spl_token: None,
+        references: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        label: Some("Café & Co".to_string()),
+        message: Some("Order #12".to_string()),
+        memo: Some("INV 7".to_string()),
+    }
+}
+
+#[test]
+fn urls_round_trip() {
+    let request = request(Pubkey::new_unique());
+    let url = request.to_string();
+    assert!(url.starts_with(&format!(
+        "solana:{}?amount=0.5&reference=",
+        request.recipient
+    )));
+    assert!(url.contains("label=Caf%C3%A9+%26+Co&message=Order+%2312&memo=INV+7"));
+    assert_eq!(TransferRequest::from_str(&url).unwrap(), request);
+
+    let bare = TransferRequest {
+        amount: None,
+        references: Vec::new(),
+        label: None,
+        message: None,
+        memo: None,
+        ..request
+    };
+    assert_eq!(bare.to_string(), format!("solana:{}", bare.recipient));
+    assert_eq!(TransferRequest::from_str(&bare.to_string()).unwrap(), bare);
+}
+
+#[test]
+fn parse_rejects_malformed_requests() {
+    let recipient = Pubkey::new_unique();
+    for url in [
+        format!("bitcoin:{}", recipient),
+        "solana:https%3A%2F%2Fexample.com%2Fpay".to_string(),
+        "solana:not-a-key".to_string(),
+        format!("solana:{}?amount=.5", recipient),
+        format!("solana:{}?amount=ALL", recipient),
+        format!("solana:{}?amount=1&amount=2", recipient),
+        format!("solana:{}?reference=nope", recipient),
+    ] {
+        assert!(TransferRequest::from_str(&url).is_err(), "{} parsed", url);
+    }
+    let parsed = TransferRequest::from_str(&format!("solana:{}?future=1", recipient)).unwrap();
+    assert_eq!(parsed.recipient, recipient);
+}
+
+#[test]
+fn sol_payments_carry_the_memo_and_references() {
+    let payer = Pubkey::new_unique();
+    let request = request(Pubkey::new_unique());
+    let instructions = pay::sol_transfer_instructions(&payer, &request, LAMPORTS_PER_SOL / 2);
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions[0].program_id, spl_memo::id());
+    assert_eq!(instructions[0].data, b"INV 7");
+    let transfer = &instructions[1];
+    assert_eq!(transfer.program_id, system_program::id());
+    assert_eq!(
+        transfer.accounts[2..],
+        request
+            .references
+            .iter()
+            .map(|reference| AccountMeta::new_readonly(*reference, false))
+            .collect::<Vec<_>>()[..]
+    );
+}
+
+#[tokio::test]
+async fn references_do_not_stop_the_transfer() {
+    let rpc = MockRpc::new();
+    let payer = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let request = TransferRequest {
+        memo: None,
+        ..request(Pubkey::new_unique())
+    };
+    let instructions =
+        pay::sol_transfer_instructions(&payer.pubkey(), &request, LAMPORTS_PER_SOL / 2);
+    transaction::send_instructions(
+        &rpc,
+        &instructions,
+        &[&payer],
+        &BlockhashSource::default(),
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        rpc.get_balance(&request.recipient).await.unwrap(),
+        LAMPORTS_PER_SOL / 2
+    );
+    let sent = rpc.sent_transactions();
+    let keys = sent[0].message.static_account_keys();
+    assert!(request
+        .references
+        .iter()
+        .all(|reference| keys.contains(reference)));
+}
+
+#[tokio::test]
+async fn sol_payments_are_found_and_verified() {
+    let rpc = MockRpc::new();
+    let payer = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let request = request(Pubkey::new_unique());
+    let receipt = pay::pay(&rpc, &payer, &request, None, false, &confirmed())
+        .await
+        .unwrap();
+
+    let signature = pay::find_reference(&rpc, &request.references[1])
+        .await
+        .unwrap();
+    assert_eq!(signature, receipt.signature);
+    let payment = pay::validate_transfer(&rpc, &signature, &request)
+        .await
+        .unwrap();
+    assert_eq!(payment.received, "0.5");
+
+    let larger = TransferRequest {
+        amount: Some(Amount::from_str("0.6").unwrap()),
+        ..request.clone()
+    };
+    let err = pay::validate_transfer(&rpc, &signature, &larger)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Recipient received 0.5 but 0.6 was requested"
+    );
+    let other_memo = TransferRequest {
+        memo: Some("INV 8".to_string()),
+        ..request
+    };
+    assert!(pay::validate_transfer(&rpc, &signature, &other_memo)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn token_payments_are_sent_and_verified() {
+    let rpc = MockRpc::new();
+    let payer = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let mint = Keypair::new();
+    token::create_mint(&rpc, &payer, &mint, &payer.pubkey(), None, 6, &confirmed())
+        .await
+        .unwrap();
+    token::mint_tokens(
+        &rpc,
+        &payer,
+        &mint.pubkey(),
+        &payer.pubkey(),
+        &Amount::from_str("10").unwrap(),
+        false,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    let request = TransferRequest {
+        amount: Some(Amount::from_str("2.5").unwrap()),
+        spl_token: Some(mint.pubkey()),
+        ..request(Pubkey::new_unique())
+    };
+
+    let err = pay::pay(&rpc, &payer, &request, None, false, &confirmed())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("--fund-recipient"), "{}", err);
+    let receipt = pay::pay(&rpc, &payer, &request, None, true, &confirmed())
+        .await
+        .unwrap();
+    let balance = token::get_token_balance(&rpc, &request.recipient, &mint.pubkey()).await;
+    assert_eq!(balance.unwrap(), "2.5");
+
+    let signature = pay::find_reference(&rpc, &request.references[0])
+        .await
+        .unwrap();
+    assert_eq!(signature, receipt.signature);
+    let payment = pay::validate_transfer(&rpc, &signature, &request)
+        .await
+        .unwrap();
+    assert_eq!(payment.received, "2.5");
+    assert_eq!(payment.spl_token, Some(mint.pubkey().to_string()));
+
+    let in_sol = TransferRequest {
+        spl_token: None,
+        ..request
+    };
+    assert!(pay::validate_transfer(&rpc, &signature, &in_sol)
+        .await
+        .is_err());
+}
@@ -0,0 +1,110 @@
+//! Seed parsing, PDA derivation and the seed search behind `pda --find`.
+
+use scw::pda::{self, Seed};
+use solana_sdk::pubkey::Pubkey;
+use std::{error::Error, str::FromStr};
+
+fn seed(spec: &str) -> Seed {
+    Seed::parse(spec, |key| Ok(Pubkey::from_str(key)?)).unwrap()
+}
+
+fn bytes(spec: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Seed::parse(spec, |key| Ok(Pubkey::from_str(key)?)).map(|seed| seed.bytes)
+}
+
+#[test]
+fn seeds_decode_every_encoding() {
+    let key = Pubkey::new_unique();
+    assert_eq!(bytes("str:vault").unwrap(), b"vault");
+    assert_eq!(bytes(&format!("pubkey:{}", key)).unwrap(), key.to_bytes());
+    assert_eq!(bytes("hex:0x00ff10").unwrap(), [0x00, 0xff, 0x10]);
+    assert_eq!(bytes("base58:2g").unwrap(), [97]);
+    assert_eq!(bytes("base64:dmF1bHQ=").unwrap(), b"vault");
+    assert_eq!(bytes("bytes:1, 2,255").unwrap(), [1, 2, 255]);
+    assert_eq!(bytes("u8:7").unwrap(), [7]);
+    assert_eq!(bytes("u32:7").unwrap(), 7u32.to_le_bytes());
+    assert_eq!(bytes("u32le:7").unwrap(), 7u32.to_le_bytes());
+    assert_eq!(bytes("U16BE:258").unwrap(), [1, 2]);
+    assert_eq!(bytes("u64be:7").unwrap(), 7u64.to_be_bytes());
+    assert_eq!(bytes("i64le:-2").unwrap(), (-2i64).to_le_bytes());
+    assert_eq!(
+        bytes(&format!("u128:{}", u128::MAX)).unwrap(),
+        u128::MAX.to_le_bytes()
+    );
+}
+
+#[test]
+fn invalid_seeds_are_rejected() {
+    for spec in [
+        "vault",
+        "foo:1",
+        "u8:256",
+        "i8:128",
+        "i8:-129",
+        "u32:-1",
+        "hex:abc",
+        "hex:zz",
+        "bytes:1,256",
+        "pubkey:nope",
+        "str:this seed is longer than thirty-two bytes",
+    ] {
+        assert!(bytes(spec).is_err(), "{} parsed", spec);
+    }
+    assert_eq!(bytes("i8:-128").unwrap(), [0x80]);
+}
+
+#[test]
+fn derive_matches_find_program_address() {
+    let program = Pubkey::new_unique();
+    let signer = Pubkey::new_unique();
+    let seeds = [
+        seed("str:vault"),
+        seed(&format!("pubkey:{}", signer)),
+        seed("u32le:7"),
+    ];
+    let info = pda::derive(&program, &seeds).unwrap();
+    let (address, bump) =
+        Pubkey::find_program_address(&[b"vault", signer.as_ref(), &7u32.to_le_bytes()], &program);
+    assert_eq!(info.address, address.to_string());
+    assert_eq!(info.bump, bump);
+    assert!(info.canonical);
+    assert_eq!(info.seeds[2].hex, "07000000");
+}
+
+#[test]
+fn find_recovers_seed_order_and_bump() {
+    let program = Pubkey::new_unique();
+    let (address, bump) = Pubkey::find_program_address(&[b"user", &7u64.to_le_bytes()], &program);
+    let candidates = [
+        seed("u32le:7"),
+        seed("u64be:7"),
+        seed("u64le:7"),
+        seed("str:vault"),
+        seed("str:user"),
+    ];
+    let found = pda::find_seeds(&program, &candidates, &address).unwrap();
+    assert_eq!(found.bump, bump);
+    assert!(found.canonical);
+    let specs: Vec<&str> = found.seeds.iter().map(|seed| seed.seed.as_str()).collect();
+    assert_eq!(specs, ["str:user", "u64le:7"]);
+
+    assert!(pda::find_seeds(&program, &candidates[..4], &address).is_err());
+    assert!(pda::find_seeds(&program, &candidates, &Pubkey::from([7; 32])).is_err());
+}
+
+#[test]
+fn find_reports_non_canonical_bumps() {
+    let program = Pubkey::new_unique();
+    let (_, canonical) = Pubkey::find_program_address(&[b"vault"], &program);
+    let (address, bump) = (0..canonical)
+        .rev()
+        .find_map(|bump| {
+            Pubkey::create_program_address(&[b"vault", &[bump]], &program)
+                .ok()
+                .map(|address| (address, bump))
+        })
+        .unwrap();
+    let found = pda::find_seeds(&program, &[seed("str:vault")], &address).unwrap();
+    assert_eq!(found.bump, bump);
+    assert!(!found.canonical);
+}
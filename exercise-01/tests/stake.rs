@@ -0,0 +1,117 @@
+//! `stake show` and `stake validators` against `MockRpc`. The stake program
+//! itself is not emulated, so stake accounts are written directly.
+
+use scw::{mock::MockRpc, stake};
+use solana_client::rpc_response::{RpcInflationReward, RpcVoteAccountInfo, RpcVoteAccountStatus};
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    stake::{
+        self as stake_program,
+        stake_flags::StakeFlags,
+        state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2},
+    },
+};
+
+const RESERVE: u64 = 2_282_880;
+
+/// A stake account delegating `lamports` since `activation_epoch`.
+fn delegated_stake(rpc: &MockRpc, lamports: u64, activation_epoch: u64) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let state = StakeStateV2::Stake(
+        Meta {
+            rent_exempt_reserve: RESERVE,
+            authorized: Authorized {
+                staker: authority,
+                withdrawer: authority,
+            },
+            lockup: Lockup::default(),
+        },
+        Stake {
+            delegation: Delegation {
+                voter_pubkey: Pubkey::new_unique(),
+                stake: lamports,
+                activation_epoch,
+                ..Delegation::default()
+            },
+            credits_observed: 0,
+        },
+        StakeFlags::empty(),
+    );
+    let mut data = vec![0; StakeStateV2::size_of()];
+    bincode::serialize_into(&mut data[..], &state).unwrap();
+    rpc.set_account(
+        &address,
+        Account {
+            lamports: lamports + RESERVE,
+            data,
+            owner: stake_program::program::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    address
+}
+
+fn reward(epoch: u64, amount: u64, post_balance: u64) -> RpcInflationReward {
+    RpcInflationReward {
+        epoch,
+        effective_slot: 0,
+        amount,
+        post_balance,
+        commission: None,
+    }
+}
+
+#[tokio::test]
+async fn show_lists_the_rewards_the_node_still_has() {
+    let rpc = MockRpc::new();
+    let address = delegated_stake(&rpc, 1_000_000_000, 2);
+    rpc.warp_to_epoch(10);
+    rpc.set_inflation_reward(&address, reward(9, 2_000_000, 1_002_000_000));
+    rpc.set_inflation_reward(&address, reward(7, 2_000_000, 1_000_000_000));
+    rpc.set_inflation_reward(&address, reward(6, 2_000_000, 998_000_000));
+    rpc.prune_epochs_before(7);
+
+    let info = stake::get_stake_info(&rpc, &address, 5).await.unwrap();
+    assert_eq!(info.state, "active");
+    assert_eq!(info.active_lamports, 1_000_000_000);
+    assert_eq!(info.activation_epoch, Some(2));
+    assert_eq!(info.rent_exempt_reserve, Some(RESERVE));
+    // Epoch 8 has no reward and epoch 6 was pruned.
+    let epochs: Vec<u64> = info.rewards.iter().map(|reward| reward.epoch).collect();
+    assert_eq!(epochs, [9, 7]);
+    assert_eq!(info.rewards[0].lamports, 2_000_000);
+    assert!((info.rewards[0].percent - 0.2).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn validators_are_sorted_by_stake() {
+    let rpc = MockRpc::new();
+    let validator = |vote: &str, stake: u64| RpcVoteAccountInfo {
+        vote_pubkey: vote.to_string(),
+        node_pubkey: Pubkey::new_unique().to_string(),
+        activated_stake: stake,
+        commission: 5,
+        epoch_vote_account: true,
+        epoch_credits: Vec::new(),
+        last_vote: 100,
+        root_slot: 50,
+    };
+    rpc.set_vote_accounts(RpcVoteAccountStatus {
+        current: vec![validator("small", 10), validator("large", 30)],
+        delinquent: vec![validator("late", 20)],
+    });
+
+    let names = |validators: Vec<stake::ValidatorInfo>| -> Vec<String> {
+        validators
+            .into_iter()
+            .map(|validator| validator.vote_account)
+            .collect()
+    };
+    let current = stake::get_validators(&rpc, 10, false).await.unwrap();
+    assert_eq!(names(current), ["large", "small"]);
+    let all = stake::get_validators(&rpc, 2, true).await.unwrap();
+    assert_eq!(names(all), ["large", "late"]);
+}
@@ -0,0 +1,165 @@
+//! SPL Token commands against the in-memory `MockRpc`.
+
+mod common;
+
+use common::{confirmed, funded_keypair};
+use scw::{
+    amount::Amount,
+    mock::{MockRpc, LAMPORTS_PER_SIGNATURE},
+    rpc::Rpc,
+    token,
+};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer};
+use spl_associated_token_account::get_associated_token_address;
+use std::str::FromStr;
+
+fn amount(value: &str) -> Amount {
+    Amount::from_str(value).unwrap()
+}
+
+/// A mint with two decimals whose authority holds 10 tokens.
+async fn setup(rpc: &MockRpc) -> (Keypair, Keypair) {
+    let authority = funded_keypair(rpc, LAMPORTS_PER_SOL);
+    let mint = Keypair::new();
+    token::create_mint(
+        rpc,
+        &authority,
+        &mint,
+        &authority.pubkey(),
+        None,
+        2,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    token::mint_tokens(
+        rpc,
+        &authority,
+        &mint.pubkey(),
+        &authority.pubkey(),
+        &amount("10"),
+        false,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    (authority, mint)
+}
+
+#[tokio::test]
+async fn minted_tokens_show_in_balances_and_listing() {
+    let rpc = MockRpc::new();
+    let (authority, mint) = setup(&rpc).await;
+    let mint = mint.pubkey();
+
+    let balance = token::get_token_balance(&rpc, &authority.pubkey(), &mint).await;
+    assert_eq!(balance.unwrap(), "10");
+    let stranger = Keypair::new().pubkey();
+    let balance = token::get_token_balance(&rpc, &stranger, &mint).await;
+    assert_eq!(balance.unwrap(), "0");
+
+    let accounts = token::get_token_accounts(&rpc, &authority.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].mint, mint.to_string());
+    assert_eq!(
+        accounts[0].account,
+        get_associated_token_address(&authority.pubkey(), &mint).to_string()
+    );
+    assert_eq!(accounts[0].balance, "10");
+}
+
+#[tokio::test]
+async fn transfer_needs_a_recipient_account_unless_funded() {
+    let rpc = MockRpc::new();
+    let (sender, mint) = setup(&rpc).await;
+    let mint = mint.pubkey();
+    let recipient = Keypair::new().pubkey();
+
+    let err = token::transfer_tokens(
+        &rpc,
+        &sender,
+        &mint,
+        &recipient,
+        &amount("1.5"),
+        false,
+        false,
+        &confirmed(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("--fund-recipient"), "{}", err);
+
+    token::transfer_tokens(
+        &rpc,
+        &sender,
+        &mint,
+        &recipient,
+        &amount("1.5"),
+        false,
+        true,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    let balance = token::get_token_balance(&rpc, &recipient, &mint).await;
+    assert_eq!(balance.unwrap(), "1.5");
+
+    token::transfer_tokens(
+        &rpc,
+        &sender,
+        &mint,
+        &recipient,
+        &amount("ALL"),
+        false,
+        false,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    let balance = token::get_token_balance(&rpc, &recipient, &mint).await;
+    assert_eq!(balance.unwrap(), "10");
+    let balance = token::get_token_balance(&rpc, &sender.pubkey(), &mint).await;
+    assert_eq!(balance.unwrap(), "0");
+}
+
+#[tokio::test]
+async fn only_empty_accounts_are_closed() {
+    let rpc = MockRpc::new();
+    let (owner, mint) = setup(&rpc).await;
+    let account = get_associated_token_address(&owner.pubkey(), &mint.pubkey());
+
+    let err = token::close_token_account(&rpc, &owner, &account, &owner.pubkey(), &confirmed())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("still holds 1000"), "{}", err);
+
+    let burner = Keypair::new().pubkey();
+    token::transfer_tokens(
+        &rpc,
+        &owner,
+        &mint.pubkey(),
+        &burner,
+        &amount("ALL"),
+        false,
+        true,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    let before = rpc.balance(&owner.pubkey());
+    let rent = rpc.balance(&account);
+    token::close_token_account(&rpc, &owner, &account, &owner.pubkey(), &confirmed())
+        .await
+        .unwrap();
+    assert!(rpc.get_account(&account).await.unwrap().is_none());
+    assert_eq!(
+        rpc.balance(&owner.pubkey()),
+        before + rent - LAMPORTS_PER_SIGNATURE
+    );
+    assert!(token::get_token_accounts(&rpc, &owner.pubkey())
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,334 @@
+//! Funding, sending and confirming against the in-memory `MockRpc`.
+
+mod common;
+
+use common::{confirmed, funded_keypair};
+use scw::{
+    amount::Amount,
+    mock::{MockRpc, LAMPORTS_PER_SIGNATURE},
+    preview,
+    rpc::Rpc,
+    simulate::{self, Simulated},
+    transaction::{
+        self, BlockhashSource, ComputeBudget, ConfirmationError, PriorityFee, SendOptions,
+    },
+    transfer,
+};
+use solana_client::client_error::ClientError;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::InstructionError,
+    message::VersionedMessage,
+    native_token::LAMPORTS_PER_SOL,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction::{self, SystemError},
+    transaction::TransactionError,
+};
+use std::str::FromStr;
+
+async fn send(
+    rpc: &MockRpc,
+    sender: &Keypair,
+    lamports: u64,
+    options: &SendOptions,
+) -> Result<(Keypair, transaction::Receipt), Box<dyn std::error::Error>> {
+    let recipient = Keypair::new();
+    let receipt = transfer::transfer_funds(
+        rpc,
+        sender,
+        &recipient.pubkey(),
+        lamports,
+        &BlockhashSource::default(),
+        None,
+        options,
+    )
+    .await?;
+    Ok((recipient, receipt))
+}
+
+#[tokio::test]
+async fn airdrop_credits_the_balance() {
+    let rpc = MockRpc::new();
+    let wallet = Keypair::new();
+    let receipt = transfer::request_airdrop(&rpc, &wallet.pubkey(), LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    assert!(receipt.slot > 0);
+    assert_eq!(
+        rpc.get_balance(&wallet.pubkey()).await.unwrap(),
+        LAMPORTS_PER_SOL
+    );
+}
+
+#[tokio::test]
+async fn transfer_moves_lamports_and_charges_the_fee() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+
+    let (recipient, receipt) = send(&rpc, &sender, 1_000, &confirmed()).await.unwrap();
+    assert_eq!(rpc.balance(&recipient.pubkey()), 1_000);
+    assert_eq!(
+        rpc.balance(&sender.pubkey()),
+        LAMPORTS_PER_SOL - 1_000 - LAMPORTS_PER_SIGNATURE
+    );
+    assert_eq!(rpc.sent_transactions()[0].signatures[0], receipt.signature);
+}
+
+#[tokio::test]
+async fn transfer_all_leaves_only_the_fee() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let recipient = Keypair::new();
+
+    let lamports = transfer::transfer_lamports(
+        &rpc,
+        &Amount::from_str("ALL").unwrap(),
+        false,
+        &sender.pubkey(),
+        &recipient.pubkey(),
+        &BlockhashSource::default(),
+        &ComputeBudget::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(lamports, LAMPORTS_PER_SOL - LAMPORTS_PER_SIGNATURE);
+
+    transfer::transfer_funds(
+        &rpc,
+        &sender,
+        &recipient.pubkey(),
+        lamports,
+        &BlockhashSource::default(),
+        None,
+        &confirmed(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(rpc.balance(&sender.pubkey()), 0);
+    assert_eq!(rpc.balance(&recipient.pubkey()), lamports);
+}
+
+#[tokio::test]
+async fn insufficient_funds_fail_preflight_without_changes() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, 10_000);
+
+    let err = send(&rpc, &sender, LAMPORTS_PER_SOL, &confirmed())
+        .await
+        .unwrap_err();
+    let err = err.downcast_ref::<ClientError>().unwrap();
+    assert_eq!(
+        err.get_transaction_error(),
+        Some(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SystemError::ResultWithNegativeLamports as u32)
+        ))
+    );
+    assert_eq!(rpc.balance(&sender.pubkey()), 10_000);
+    assert!(rpc.sent_transactions().is_empty());
+}
+
+#[tokio::test]
+async fn dry_run_simulates_instead_of_sending() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let options = SendOptions {
+        dry_run: true,
+        ..SendOptions::default()
+    };
+
+    let err = send(&rpc, &sender, 1_000, &options).await.unwrap_err();
+    let Simulated(simulation) = err.downcast_ref::<Simulated>().unwrap();
+    assert!(simulation.error.is_none());
+    assert_eq!(simulation.fee_lamports, LAMPORTS_PER_SIGNATURE);
+    let sender_change = simulation
+        .balance_changes
+        .iter()
+        .find(|change| change.address == sender.pubkey().to_string())
+        .unwrap();
+    assert_eq!(
+        sender_change.after,
+        LAMPORTS_PER_SOL - 1_000 - LAMPORTS_PER_SIGNATURE
+    );
+    assert_eq!(rpc.balance(&sender.pubkey()), LAMPORTS_PER_SOL);
+    assert!(rpc.sent_transactions().is_empty());
+}
+
+#[tokio::test]
+async fn dry_run_reports_failures() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, 10_000);
+    let options = SendOptions {
+        dry_run: true,
+        ..SendOptions::default()
+    };
+
+    let err = send(&rpc, &sender, LAMPORTS_PER_SOL, &options)
+        .await
+        .unwrap_err();
+    let Simulated(simulation) = err.downcast_ref::<Simulated>().unwrap();
+    assert!(matches!(
+        simulation.error,
+        Some(TransactionError::InstructionError(0, _))
+    ));
+    assert!(simulation.balance_changes.is_empty());
+}
+
+#[tokio::test]
+async fn malformed_transactions_are_not_simulated() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    let mut transaction = transaction::build_transaction(
+        &rpc,
+        &[system_instruction::transfer(
+            &sender.pubkey(),
+            &Keypair::new().pubkey(),
+            1_000,
+        )],
+        &sender.pubkey(),
+        &BlockhashSource::default(),
+        &ComputeBudget::default(),
+        &[],
+    )
+    .await
+    .unwrap();
+    let VersionedMessage::Legacy(message) = &mut transaction.message else {
+        unreachable!()
+    };
+    message.instructions[0].program_id_index = 9;
+    transaction::sign_transaction(&mut transaction, &[&sender]).unwrap();
+
+    for err in [
+        simulate::simulate(&rpc, &transaction).await.unwrap_err(),
+        preview::preview(&rpc, &transaction).await.unwrap_err(),
+    ] {
+        assert!(
+            err.to_string().starts_with("Invalid transaction"),
+            "{}",
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn dropped_transaction_expires() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    rpc.drop_transactions();
+
+    let err = send(&rpc, &sender, 1_000, &confirmed()).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ConfirmationError>(),
+        Some(ConfirmationError::Expired(_))
+    ));
+    assert_eq!(rpc.balance(&sender.pubkey()), LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn transaction_landing_at_expiry_is_confirmed() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    rpc.land_at_expiry();
+
+    let (recipient, receipt) = send(&rpc, &sender, 1_000, &confirmed()).await.unwrap();
+    assert_eq!(rpc.balance(&recipient.pubkey()), 1_000);
+    assert!(rpc
+        .get_signature_status(&receipt.signature)
+        .await
+        .unwrap()
+        .is_some());
+    assert_eq!(rpc.sent_transactions().len(), 1);
+}
+
+#[tokio::test]
+async fn auto_priority_fee_uses_recent_fees() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+    rpc.set_prioritization_fees(vec![500, 100, 300, 200, 400]);
+    let options = SendOptions {
+        budget: ComputeBudget {
+            unit_limit: Some(300),
+            unit_price: Some(PriorityFee::from_str("auto:50").unwrap()),
+        },
+        ..confirmed()
+    };
+
+    send(&rpc, &sender, 1_000, &options).await.unwrap();
+    let sent = &rpc.sent_transactions()[0];
+    let data: Vec<&[u8]> = sent
+        .message
+        .instructions()
+        .iter()
+        .map(|instruction| instruction.data.as_slice())
+        .collect();
+    assert_eq!(
+        data[..2],
+        [
+            ComputeBudgetInstruction::set_compute_unit_limit(300)
+                .data
+                .as_slice(),
+            ComputeBudgetInstruction::set_compute_unit_price(300)
+                .data
+                .as_slice(),
+        ]
+    );
+    assert_eq!(
+        data[2],
+        system_instruction::transfer(&sender.pubkey(), &sender.pubkey(), 1_000)
+            .data
+            .as_slice()
+    );
+}
+
+async fn preview_transfer(rpc: &MockRpc, sender: &Keypair, lamports: u64) -> preview::Preview {
+    let recipient = Keypair::new();
+    let mut transaction = transaction::build_transaction(
+        rpc,
+        &[system_instruction::transfer(
+            &sender.pubkey(),
+            &recipient.pubkey(),
+            lamports,
+        )],
+        &sender.pubkey(),
+        &BlockhashSource::default(),
+        &ComputeBudget::default(),
+        &[],
+    )
+    .await
+    .unwrap();
+    transaction::sign_transaction(&mut transaction, &[sender]).unwrap();
+    preview::preview(rpc, &transaction).await.unwrap()
+}
+
+#[tokio::test]
+async fn preview_shows_the_fee_and_sender_balance() {
+    let rpc = MockRpc::new();
+    let sender = funded_keypair(&rpc, LAMPORTS_PER_SOL);
+
+    let preview = preview_transfer(&rpc, &sender, LAMPORTS_PER_SOL / 2).await;
+    assert_eq!(preview.fee_lamports, LAMPORTS_PER_SIGNATURE);
+    assert_eq!(preview.payer_before, LAMPORTS_PER_SOL);
+    assert_eq!(
+        preview.payer_after,
+        LAMPORTS_PER_SOL / 2 - LAMPORTS_PER_SIGNATURE
+    );
+    assert!(preview.error.is_none());
+    assert!(preview.warnings.is_empty());
+    assert!(rpc.sent_transactions().is_empty());
+}
+
+#[tokio::test]
+async fn preview_warns_about_balances_below_rent_exemption() {
+    let rpc = MockRpc::new();
+    let minimum = rpc.get_minimum_balance_for_rent_exemption(0).await.unwrap();
+    let sender = funded_keypair(&rpc, minimum + LAMPORTS_PER_SIGNATURE + 1_000);
+
+    let preview = preview_transfer(&rpc, &sender, 1_000).await;
+    assert_eq!(preview.warnings.len(), 1);
+    assert!(preview.warnings[0].starts_with("Recipient "));
+
+    let preview = preview_transfer(&rpc, &sender, minimum).await;
+    assert_eq!(preview.warnings.len(), 1);
+    assert!(preview.warnings[0].starts_with(&format!("Sender {}", sender.pubkey())));
+}